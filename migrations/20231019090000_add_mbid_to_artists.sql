@@ -0,0 +1 @@
+ALTER TABLE artists ADD COLUMN mbid uuid UNIQUE;
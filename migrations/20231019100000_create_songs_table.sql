@@ -0,0 +1,13 @@
+CREATE TABLE songs (
+    id uuid NOT NULL,
+    PRIMARY KEY (id),
+    concert_id uuid NOT NULL,
+    set_number INTEGER NOT NULL,
+    set_name TEXT,
+    encore INTEGER,
+    position INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    created_at timestamptz NOT NULL,
+    FOREIGN KEY (concert_id) REFERENCES concerts (id) ON DELETE CASCADE,
+    UNIQUE (concert_id, set_number, position)
+);
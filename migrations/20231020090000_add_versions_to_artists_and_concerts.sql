@@ -0,0 +1,4 @@
+ALTER TABLE artists ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE artists ADD COLUMN updated_at timestamptz NOT NULL DEFAULT now();
+ALTER TABLE concerts ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE concerts ADD COLUMN updated_at timestamptz NOT NULL DEFAULT now();
@@ -0,0 +1,19 @@
+-- Responses saved for requests sent with an `Idempotency-Key`, replayed when
+-- a client retries the same request.
+CREATE TYPE header_pair AS (
+    name TEXT,
+    value BYTEA
+);
+
+CREATE TABLE idempotency (
+    client_id TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    request_hash TEXT NOT NULL,
+    response_status_code SMALLINT,
+    response_headers header_pair[],
+    response_body BYTEA,
+    created_at timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (client_id, idempotency_key)
+);
+
+CREATE INDEX idempotency_created_at_idx ON idempotency (created_at);
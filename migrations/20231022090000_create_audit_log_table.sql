@@ -0,0 +1,16 @@
+-- An append-only history of every change to artists and concerts.
+CREATE TABLE audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    entity_type TEXT NOT NULL,
+    entity_id uuid NOT NULL,
+    -- The entity's version after the change
+    revision INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    changed_at timestamptz NOT NULL DEFAULT now(),
+    -- `{"field": {"before": ..., "after": ...}}` for every field that changed
+    changes JSONB NOT NULL,
+    -- The whole entity after the change, to revert to
+    snapshot JSONB NOT NULL,
+    UNIQUE (entity_type, entity_id, revision)
+);
@@ -0,0 +1,28 @@
+-- Changes proposed by the public, applied once a moderator approves them.
+CREATE TYPE submission_kind AS ENUM ('new_concert', 'update_artist');
+CREATE TYPE submission_status AS ENUM ('pending', 'approved', 'rejected');
+
+CREATE TABLE submissions (
+    id uuid PRIMARY KEY,
+    kind submission_kind NOT NULL,
+    -- The artist being updated, and the version the change was based on
+    target_id uuid,
+    target_version INTEGER,
+    -- The request body as submitted
+    payload JSONB NOT NULL,
+    submitted_by TEXT NOT NULL,
+    status submission_status NOT NULL DEFAULT 'pending',
+    reviewed_by TEXT,
+    reviewed_at timestamptz,
+    -- The concert or artist the approved change created or updated
+    result_id uuid,
+    created_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX submissions_pending_idx ON submissions (created_at) WHERE status = 'pending';
+
+CREATE TABLE moderators (
+    id uuid PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL
+);
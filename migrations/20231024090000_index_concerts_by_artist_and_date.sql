@@ -0,0 +1,3 @@
+-- The artist page and `GET /artists/{id}/concerts` list an artist's concerts
+-- by date.
+CREATE INDEX concerts_artist_id_date_idx ON concerts (artist_id, date);
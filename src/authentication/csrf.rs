@@ -0,0 +1,17 @@
+use crate::session_state::TypedSession;
+use crate::utils::e500;
+
+/// The hidden field every form posted to a logged in page carries.
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct CsrfForm {
+    pub csrf_token: String,
+}
+
+/// Refuse a form post that did not come from a page this session rendered.
+pub fn reject_forged_requests(session: &TypedSession, csrf_token: &str) -> Result<(), actix_web::Error> {
+    if session.verify_csrf_token(csrf_token).map_err(e500)? {
+        Ok(())
+    } else {
+        Err(actix_web::error::ErrorForbidden("The form is out of date, please reload the page and try again."))
+    }
+}
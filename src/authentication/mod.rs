@@ -0,0 +1,7 @@
+mod csrf;
+mod moderator;
+mod password;
+
+pub use csrf::*;
+pub use moderator::*;
+pub use password::*;
@@ -0,0 +1,66 @@
+use crate::session_state::TypedSession;
+use crate::utils::{e500, see_other};
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::{web, FromRequest, HttpRequest};
+use actix_web_flash_messages::FlashMessage;
+use anyhow::Context;
+use futures_util::future::LocalBoxFuture;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The moderator logged in to the session.
+///
+/// Extracting it from a request without one redirects to `/login`.
+#[derive(Debug, Clone)]
+pub struct Moderator {
+    pub id: Uuid,
+    pub username: String,
+}
+
+impl FromRequest for Moderator {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let session = TypedSession::from_request(req, payload).into_inner();
+        let pool = req.app_data::<web::Data<PgPool>>().cloned();
+
+        Box::pin(async move {
+            let session = session?;
+            let pool = pool
+                .context("The database pool is not configured")
+                .map_err(e500)?;
+
+            let moderator_id = session.get_moderator_id().map_err(e500)?;
+            let username = match moderator_id {
+                Some(id) => get_username(id, &pool).await.map_err(e500)?,
+                None => None,
+            };
+
+            match (moderator_id, username) {
+                (Some(id), Some(username)) => Ok(Self { id, username }),
+                _ => {
+                    FlashMessage::error("Please log in to moderate submissions.").send();
+                    let e = anyhow::anyhow!("The moderator has not logged in");
+                    Err(InternalError::from_response(e, see_other("/login")).into())
+                }
+            }
+        })
+    }
+}
+
+#[tracing::instrument(name = "Get moderator username", skip(pool))]
+async fn get_username(moderator_id: Uuid, pool: &PgPool) -> Result<Option<String>, anyhow::Error> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT username
+        FROM moderators
+        WHERE id = $1
+        "#,
+        moderator_id,
+    )
+    .fetch_optional(pool)
+    .await
+    .context("Failed to retrieve the moderator's username")
+}
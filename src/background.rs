@@ -0,0 +1,86 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::Notify;
+
+/// Tasks that requests spawn to run past their handler, such as the export
+/// streams, so that shutdown can wait for them before closing the database
+/// pool. Registered as application data.
+#[derive(Clone, Default)]
+pub struct BackgroundTasks(Arc<Tasks>);
+
+#[derive(Default)]
+struct Tasks {
+    running: AtomicUsize,
+    finished: Notify,
+}
+
+impl BackgroundTasks {
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.0.running.fetch_add(1, Ordering::SeqCst);
+        let running = Running(self.0.clone());
+        tokio::spawn(async move {
+            // Dropped when the task ends, or when its runtime drops it.
+            let _running = running;
+            task.await;
+        });
+    }
+
+    /// Complete once no task is running.
+    pub async fn wait(&self) {
+        loop {
+            // Created before checking, so that a task finishing in between
+            // still wakes it.
+            let finished = self.0.finished.notified();
+            if self.0.running.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            finished.await;
+        }
+    }
+}
+
+struct Running(Arc<Tasks>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.finished.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BackgroundTasks;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn waiting_completes_once_every_task_has_finished() {
+        let tasks = BackgroundTasks::default();
+        let (finish, finished) = oneshot::channel::<()>();
+        tasks.spawn(async {
+            let _ = finished.await;
+        });
+        tasks.spawn(async {});
+
+        let wait = tasks.wait();
+        tokio::pin!(wait);
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut wait).await.is_err());
+
+        finish.send(()).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), wait)
+            .await
+            .expect("Still waiting once every task finished");
+    }
+
+    #[tokio::test]
+    async fn waiting_without_tasks_completes_at_once() {
+        let tasks = BackgroundTasks::default();
+
+        tokio::time::timeout(Duration::from_millis(50), tasks.wait())
+            .await
+            .expect("Waited without any task running");
+    }
+}
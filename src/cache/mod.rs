@@ -0,0 +1,3 @@
+mod response_cache;
+
+pub use response_cache::*;
@@ -0,0 +1,133 @@
+use crate::configuration::CacheSettings;
+use crate::routes::is_fresh;
+use actix_web::http::header::{CacheControl, CacheDirective, EntityTag, ETag, CONTENT_TYPE};
+use actix_web::{HttpRequest, HttpResponse};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::future::Future;
+
+/// A response as it is kept in the cache.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct CachedResponse {
+    pub content_type: String,
+    /// The strong entity tag of the response, if it has one
+    pub etag: Option<String>,
+    pub body: String,
+}
+
+/// A read-through cache of the responses of the read endpoints, kept in
+/// Redis so that every instance of the application shares it.
+///
+/// Entries are keyed under a generation, which `invalidate` moves on after
+/// any write to artists or concerts commits: the entries of the previous
+/// generation are no longer read and expire on their own. As the generation
+/// is read before Postgres is queried, a response is never cached under a
+/// generation it is older than.
+///
+/// Failing to reach Redis is logged and the response loaded from Postgres.
+#[derive(Clone)]
+pub struct ResponseCache {
+    settings: CacheSettings,
+    /// `None` when the cache is disabled
+    connection: Option<ConnectionManager>,
+}
+
+impl ResponseCache {
+    pub async fn new(settings: CacheSettings, client: redis::Client) -> Result<Self, redis::RedisError> {
+        let connection = match settings.enabled {
+            true => Some(ConnectionManager::new(client).await?),
+            false => None,
+        };
+        Ok(Self { settings, connection })
+    }
+
+    /// The cached response for `key`, or else what `load` returns, which is
+    /// cached when it succeeds.
+    pub async fn get_or_load<F, Fut, E>(&self, key: &str, load: F) -> Result<CachedResponse, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<CachedResponse, E>>,
+    {
+        let mut connection = match &self.connection {
+            Some(connection) => connection.clone(),
+            None => return load().await,
+        };
+
+        let generation = match connection.get::<_, Option<u64>>(self.generation_key()).await {
+            Ok(generation) => generation.unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!(error.cause_chain = ?e, "Failed to read the cache generation");
+                return load().await;
+            }
+        };
+        let key = format!("{}:{}:{}", self.settings.key_prefix, generation, key);
+
+        match connection.get::<_, Option<String>>(&key).await {
+            Ok(Some(cached)) => match serde_json::from_str(&cached) {
+                Ok(response) => {
+                    tracing::debug!(key = %key, "Cache hit");
+                    return Ok(response);
+                }
+                Err(e) => tracing::warn!(error.cause_chain = ?e, "Failed to parse a cached response"),
+            },
+            Ok(None) => tracing::debug!(key = %key, "Cache miss"),
+            Err(e) => tracing::warn!(error.cause_chain = ?e, "Failed to read from the cache"),
+        }
+
+        let response = load().await?;
+        let cached = serde_json::to_string(&response).expect("Failed to serialize a response");
+        let stored = redis::cmd("SET")
+            .arg(&key)
+            .arg(cached)
+            .arg("EX")
+            .arg(self.settings.ttl_seconds)
+            .query_async::<_, ()>(&mut connection)
+            .await;
+        if let Err(e) = stored {
+            tracing::warn!(error.cause_chain = ?e, "Failed to write to the cache");
+        }
+        Ok(response)
+    }
+
+    /// Drop every cached response. Call once the transaction writing to
+    /// artists or concerts has committed, never before.
+    #[tracing::instrument(name = "Invalidating the response cache", skip(self))]
+    pub async fn invalidate(&self) {
+        if let Some(connection) = &self.connection {
+            if let Err(e) = connection.clone().incr::<_, _, u64>(self.generation_key(), 1).await {
+                tracing::error!(error.cause_chain = ?e, "Failed to invalidate the response cache");
+            }
+        }
+    }
+
+    /// The response to send for `cached`, or `304 Not Modified` when the
+    /// client already has it.
+    pub fn respond(&self, req: &HttpRequest, cached: CachedResponse) -> HttpResponse {
+        let cache_control = CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(self.settings.max_age_seconds as u32),
+        ]);
+        let tag = cached.etag.map(EntityTag::new_strong);
+        if let Some(tag) = &tag {
+            if is_fresh(req, tag) {
+                return HttpResponse::NotModified()
+                    .insert_header(ETag(tag.clone()))
+                    .insert_header(cache_control)
+                    .finish();
+            }
+        }
+
+        let mut response = HttpResponse::Ok();
+        response
+            .insert_header((CONTENT_TYPE, cached.content_type))
+            .insert_header(cache_control);
+        if let Some(tag) = tag {
+            response.insert_header(ETag(tag));
+        }
+        response.body(cached.body)
+    }
+
+    fn generation_key(&self) -> String {
+        format!("{}:generation", self.settings.key_prefix)
+    }
+}
@@ -1,12 +1,47 @@
+use crate::configuration::redact;
+use crate::domain::ValidationErrors;
 use serde_aux::field_attributes::deserialize_number_from_string;
-use secrecy::Secret;
+use secrecy::{ExposeSecret, Secret};
 
+/// The session cookies are signed and encrypted with a key derived from the
+/// secret, which needs at least this many bytes.
+const MIN_HMAC_SECRET_BYTES: usize = 64;
 
-#[derive(serde::Deserialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct ApplicationSettings {
+    /// The port to listen on, or 0 for any free one
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub host: String,
     pub base_url: String,
+    #[serde(serialize_with = "redact")]
     pub hmac_secret: Secret<String>,
+    /// How long in-flight requests are given to finish once shutdown is
+    /// requested, before their connections are dropped.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub shutdown_timeout_seconds: u64,
+    /// Whether a reverse proxy sets the `Forwarded` or `X-Forwarded-For`
+    /// header, so that the client's address is taken from it rather than
+    /// from the connection. Any client can send those headers, so this
+    /// must stay off unless every request goes through the proxy.
+    #[serde(default)]
+    pub trust_proxy_headers: bool,
+}
+
+impl ApplicationSettings {
+    pub(crate) fn validate(&self, errors: &mut ValidationErrors) {
+        if self.host.is_empty() {
+            errors.push("application.host", "The host is required");
+        }
+        if !self.base_url.starts_with("http://") && !self.base_url.starts_with("https://") {
+            errors.push("application.base_url", "The base URL must start with http:// or https://");
+        }
+        if self.hmac_secret.expose_secret().len() < MIN_HMAC_SECRET_BYTES {
+            errors.push(
+                "application.hmac_secret",
+                format!("The secret must be at least {} bytes long", MIN_HMAC_SECRET_BYTES),
+            );
+        }
+    }
 }
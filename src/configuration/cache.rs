@@ -0,0 +1,35 @@
+use crate::domain::ValidationErrors;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSettings {
+    /// When unset, read endpoints always query Postgres.
+    pub enabled: bool,
+    /// How long a cached response is kept in Redis, should an invalidation
+    /// ever be missed.
+    pub ttl_seconds: u64,
+    /// The `max-age` clients and proxies are told they may reuse a
+    /// response for.
+    pub max_age_seconds: u64,
+    /// Prepended to the Redis keys of the cached responses.
+    pub key_prefix: String,
+}
+
+impl CacheSettings {
+    pub(crate) fn validate(&self, errors: &mut ValidationErrors) {
+        if self.ttl_seconds == 0 {
+            errors.push("cache.ttl_seconds", "Responses must be cached for at least a second");
+        }
+    }
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_seconds: 300,
+            max_age_seconds: 10,
+            key_prefix: "cache".into(),
+        }
+    }
+}
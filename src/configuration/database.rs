@@ -1,20 +1,46 @@
+use crate::configuration::redact;
+use crate::domain::ValidationErrors;
 use secrecy::{ExposeSecret, Secret};
 use serde_aux::field_attributes::deserialize_number_from_string;
 use sqlx::postgres::{PgConnectOptions, PgSslMode};
 use sqlx::ConnectOptions;
 
-#[derive(serde::Deserialize, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct DatabaseSettings {
     pub username: String,
+    #[serde(serialize_with = "redact")]
     pub password: Secret<String>,
     #[serde(deserialize_with="deserialize_number_from_string")]
     pub port: u16,
     pub host: String,
     pub database_name: String,
     pub require_ssl: bool,
+    /// The most connections the pool opens; requests wait for one once every
+    /// connection is in use
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+}
+
+fn default_max_connections() -> u32 {
+    10
 }
 
 impl DatabaseSettings {
+    pub(crate) fn validate(&self, errors: &mut ValidationErrors) {
+        if self.port == 0 {
+            errors.push("database.port", "The port must be between 1 and 65535");
+        }
+        for (field, value) in [("database.host", &self.host), ("database.username", &self.username), ("database.database_name", &self.database_name)] {
+            if value.is_empty() {
+                errors.push(field, "A value is required");
+            }
+        }
+        if self.max_connections == 0 {
+            errors.push("database.max_connections", "The pool needs at least one connection");
+        }
+    }
+
     pub fn without_db(&self) -> PgConnectOptions {
         let ssl_mode = if self.require_ssl {
             PgSslMode::Require
@@ -1,30 +1,75 @@
-use std::convert::TryFrom;
+use std::path::Path;
 
-pub enum Environment {
-   Local,
-   Production,
-}
+/// The settings every environment starts from, rather than an environment.
+const BASE: &str = "base";
+
+/// A deployment environment, named after its file in `configuration/`, e.g.
+/// `staging` for `configuration/staging.yaml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Environment(String);
 
 impl Environment {
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Environment::Local => "local",
-            Environment::Production => "production",
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Every environment with a file in `directory`, sorted by name.
+    pub fn available(directory: &Path) -> std::io::Result<Vec<Self>> {
+        let mut environments = Vec::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("yaml") {
+                continue;
+            }
+            match path.file_stem().and_then(|s| s.to_str()) {
+                Some(name) if name != BASE => environments.push(Self(name.to_string())),
+                _ => {}
+            }
+        }
+        environments.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(environments)
+    }
+
+    /// The environment called `name`, as long as `directory` has a file for it.
+    pub fn find(name: &str, directory: &Path) -> Result<Self, String> {
+        let available = Self::available(directory)
+            .map_err(|e| format!("Failed to list the environments in {}: {}", directory.display(), e))?;
+        let name = name.to_lowercase();
+
+        match available.iter().find(|environment| environment.0 == name) {
+            Some(environment) => Ok(environment.clone()),
+            None => Err(format!(
+                "{} is not a supported environment. Please use one of: {}",
+                name,
+                available.iter().map(Self::as_str).collect::<Vec<_>>().join(", ")
+            )),
         }
     }
 }
 
-impl TryFrom<String> for Environment {
-   type Error = String;
-
-   fn try_from(s: String) -> Result<Self, Self::Error> {
-        match s.to_lowercase().as_str() {
-            "local" => Ok(Self::Local),
-            "production" => Ok(Self::Production),
-            other => Err(format!(
-                "{} is not a supported environment. Please use either 'local' or 'production'",
-                other
-            ))
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn environments_are_discovered_from_their_files() {
+        let directory = std::env::temp_dir().join(format!("allbands-configuration-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&directory).unwrap();
+        for file in ["base.yaml", "production.yaml", "staging.yaml", "README.md"] {
+            std::fs::write(directory.join(file), "").unwrap();
         }
-   }
+
+        let available = Environment::available(&directory).unwrap();
+        let staging = Environment::find("Staging", &directory);
+        let ci = Environment::find("ci", &directory);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(vec!["production", "staging"], available.iter().map(Environment::as_str).collect::<Vec<_>>());
+        assert_eq!("staging", staging.unwrap().as_str());
+        assert_eq!(
+            "ci is not a supported environment. Please use one of: production, staging",
+            ci.unwrap_err()
+        );
+    }
 }
@@ -0,0 +1,19 @@
+/// Endpoints that can be switched off without a redeploy. Changes are picked
+/// up on SIGHUP.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureFlags {
+    /// `POST /import/concerts` and `POST /import/setlistfm`
+    pub imports: bool,
+    /// `POST /submissions/concerts` and `POST /submissions/artists/{id}`
+    pub submissions: bool,
+}
+
+impl Default for FeatureFlags {
+    fn default() -> Self {
+        Self {
+            imports: true,
+            submissions: true,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::domain::ValidationErrors;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IdempotencySettings {
+    /// How long a saved response is replayed for its `Idempotency-Key`.
+    /// Once expired, the key may be reused for a different request.
+    pub expiry_hours: u64,
+}
+
+impl IdempotencySettings {
+    pub(crate) fn validate(&self, errors: &mut ValidationErrors) {
+        if self.expiry_hours == 0 {
+            errors.push("idempotency.expiry_hours", "Saved responses must be kept for at least an hour");
+        }
+    }
+
+    pub fn expiry(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.expiry_hours * 60 * 60)
+    }
+}
+
+impl Default for IdempotencySettings {
+    fn default() -> Self {
+        Self { expiry_hours: 24 }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::domain::ValidationErrors;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ImportSettings {
+    /// The largest body `POST /import/concerts` and `POST /import/setlistfm`
+    /// read. Larger ones are refused with `413 Payload Too Large`.
+    pub max_body_bytes: usize,
+}
+
+impl ImportSettings {
+    pub(crate) fn validate(&self, errors: &mut ValidationErrors) {
+        if self.max_body_bytes == 0 {
+            errors.push("import.max_body_bytes", "Imports must accept a body of at least one byte");
+        }
+    }
+}
+
+impl Default for ImportSettings {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 50 * 1024 * 1024,
+        }
+    }
+}
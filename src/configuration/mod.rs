@@ -2,8 +2,22 @@ mod database;
 mod settings;
 mod application;
 mod environment;
+mod musicbrainz;
+mod idempotency;
+mod telemetry;
+mod rate_limit;
+mod cache;
+mod features;
+mod import;
 
 pub use database::*;
 pub use settings::*;
 pub use application::*;
 pub use environment::*;
+pub use musicbrainz::*;
+pub use idempotency::*;
+pub use telemetry::*;
+pub use rate_limit::*;
+pub use cache::*;
+pub use features::*;
+pub use import::*;
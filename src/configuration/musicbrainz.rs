@@ -0,0 +1,7 @@
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct MusicBrainzSettings {
+    /// Path to a MusicBrainz JSON artist dump used to prefill new artists
+    /// created with an MBID. Prefilling is disabled when unset.
+    pub artist_dump: Option<String>,
+}
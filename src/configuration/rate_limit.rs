@@ -0,0 +1,85 @@
+use crate::domain::ValidationErrors;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    pub enabled: bool,
+    /// The sliding window requests are counted over.
+    pub window_seconds: u64,
+    /// How many requests a client may send per window to the routes without
+    /// a limit of their own, all together.
+    pub default_limit: u32,
+    /// Routes counted separately, with a limit of their own.
+    pub routes: Vec<RouteLimit>,
+    /// Route templates that are never limited, such as the probes.
+    pub exempt_routes: Vec<String>,
+    /// Prepended to the Redis keys of the counters.
+    pub key_prefix: String,
+    /// How long a request waits on Redis for its count before it is let
+    /// through uncounted.
+    pub timeout_milliseconds: u64,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RouteLimit {
+    pub method: String,
+    /// The route template, e.g. `/concerts/{id}`
+    pub path: String,
+    pub limit: u32,
+}
+
+impl RateLimitSettings {
+    pub(crate) fn validate(&self, errors: &mut ValidationErrors) {
+        if self.window_seconds == 0 {
+            errors.push("rate_limit.window_seconds", "The window must be at least a second long");
+        }
+        if self.default_limit == 0 {
+            errors.push("rate_limit.default_limit", "The limit must be at least 1");
+        }
+        if self.timeout_milliseconds == 0 {
+            errors.push("rate_limit.timeout_milliseconds", "The timeout must be at least a millisecond long");
+        }
+        for (i, route) in self.routes.iter().enumerate() {
+            if actix_web::http::Method::from_bytes(route.method.to_uppercase().as_bytes()).is_err() {
+                errors.push(&format!("rate_limit.routes[{}].method", i), format!("{} is not an HTTP method", route.method));
+            }
+            if !route.path.starts_with('/') {
+                errors.push(&format!("rate_limit.routes[{}].path", i), "The route template must start with /");
+            }
+            if route.limit == 0 {
+                errors.push(&format!("rate_limit.routes[{}].limit", i), "The limit must be at least 1");
+            }
+        }
+    }
+
+    pub fn window(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.window_seconds)
+    }
+
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout_milliseconds)
+    }
+
+    /// The limit of the route with `method` and template `path`, if it has
+    /// one of its own.
+    pub fn route_limit(&self, method: &str, path: &str) -> Option<&RouteLimit> {
+        self.routes
+            .iter()
+            .find(|route| route.method.eq_ignore_ascii_case(method) && route.path == path)
+    }
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_seconds: 60,
+            default_limit: 300,
+            routes: vec![],
+            exempt_routes: vec!["/health".into(), "/health/ready".into(), "/metrics".into()],
+            key_prefix: "rate_limit".into(),
+            timeout_milliseconds: 100,
+        }
+    }
+}
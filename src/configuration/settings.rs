@@ -1,22 +1,88 @@
-use crate::configuration::{ApplicationSettings, DatabaseSettings, Environment};
-use secrecy::Secret;
+use crate::configuration::{ApplicationSettings, CacheSettings, DatabaseSettings, Environment, FeatureFlags, IdempotencySettings, ImportSettings, MusicBrainzSettings, RateLimitSettings, TelemetrySettings};
+use crate::domain::ValidationErrors;
+use secrecy::{ExposeSecret, Secret};
+use std::collections::HashMap;
 
-#[derive(serde::Deserialize, Clone)]
+/// Environment variables ending in this are paths to a file holding the value
+/// of the setting, e.g. `APP_DATABASE__PASSWORD_FILE=/run/secrets/db`.
+const FILE_SUFFIX: &str = "_FILE";
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Settings {
     pub database: DatabaseSettings,
     pub application: ApplicationSettings,
-    pub redis_uri: Secret<String>
+    #[serde(serialize_with = "redact")]
+    pub redis_uri: Secret<String>,
+    #[serde(default)]
+    pub musicbrainz: MusicBrainzSettings,
+    #[serde(default)]
+    pub idempotency: IdempotencySettings,
+    #[serde(default)]
+    pub telemetry: TelemetrySettings,
+    #[serde(default)]
+    pub rate_limit: RateLimitSettings,
+    #[serde(default)]
+    pub cache: CacheSettings,
+    #[serde(default)]
+    pub features: FeatureFlags,
+    #[serde(default)]
+    pub import: ImportSettings,
+}
+
+impl Settings {
+    /// Check the values the types alone don't rule out, reporting every
+    /// problem rather than only the first.
+    pub fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        self.application.validate(&mut errors);
+        self.database.validate(&mut errors);
+        let scheme = self.redis_uri.expose_secret().split_once("://").map(|(scheme, _)| scheme);
+        if !matches!(scheme, Some("redis" | "rediss" | "redis+unix" | "unix")) {
+            errors.push("redis_uri", "The scheme must be redis, rediss, redis+unix or unix");
+        }
+        self.idempotency.validate(&mut errors);
+        self.telemetry.validate(&mut errors);
+        self.rate_limit.validate(&mut errors);
+        self.cache.validate(&mut errors);
+        self.import.validate(&mut errors);
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+/// Serializes secrets as a placeholder, so the effective configuration can be
+/// printed.
+pub fn redact<S: serde::Serializer>(_: &Secret<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str("[REDACTED]")
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ConfigurationError {
+    #[error("{0}")]
+    Environment(String),
+    #[error("Failed to load the configuration")]
+    Load(#[from] config::ConfigError),
+    #[error("Failed to read {variable} from {path}")]
+    SecretFile {
+        variable: String,
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("The configuration is invalid: {0}")]
+    Invalid(ValidationErrors),
 }
 
-pub fn get_configuration() -> Result<Settings, config::ConfigError> {
+pub fn get_configuration() -> Result<Settings, ConfigurationError> {
     let base_path = std::env::current_dir().expect("Failed to locate base path");
     let configuration_directory = base_path.join("configuration");
     
-    let environment: Environment = std::env::var("APP_ENVIRONMENT")
-
-        .unwrap_or_else(|_| "local".into())
-        .try_into()
-        .expect("Failed to parse APP_ENVIRONMENT");
+    let environment = Environment::find(
+        &std::env::var("APP_ENVIRONMENT").unwrap_or_else(|_| "local".into()),
+        &configuration_directory,
+    )
+    .map_err(ConfigurationError::Environment)?;
 
     let environment_filename = format!("{}.yaml", environment.as_str());
     let settings =  config::Config::builder()
@@ -30,8 +96,110 @@ pub fn get_configuration() -> Result<Settings, config::ConfigError> {
             config::Environment::with_prefix("APP")
                 .prefix_separator("_")
                 .separator("__")
+                .source(Some(environment_variables(std::env::vars())?))
         )
         .build()?;
 
-    settings.try_deserialize::<Settings>()
+    let settings = settings.try_deserialize::<Settings>()?;
+    settings.validate().map_err(ConfigurationError::Invalid)?;
+
+    Ok(settings)
+}
+
+/// The `APP_` variables that override the configuration files, with those
+/// ending in `_FILE` replaced by the contents of the file they point to.
+/// `APP_ENVIRONMENT` picks the files rather than being a setting itself.
+fn environment_variables(
+    variables: impl Iterator<Item = (String, String)>,
+) -> Result<HashMap<String, String>, ConfigurationError> {
+    let mut overrides = HashMap::new();
+    let mut from_files = Vec::new();
+    for (variable, value) in variables {
+        if !variable.starts_with("APP_") || variable == "APP_ENVIRONMENT" {
+            continue;
+        }
+        match variable.strip_suffix(FILE_SUFFIX) {
+            Some(setting) => {
+                let contents = std::fs::read_to_string(&value).map_err(|source| ConfigurationError::SecretFile {
+                    variable: variable.clone(),
+                    path: value.clone(),
+                    source,
+                })?;
+                from_files.push((setting.to_string(), contents.trim_end_matches(['\r', '\n']).to_string()));
+            }
+            None => {
+                overrides.insert(variable, value);
+            }
+        }
+    }
+
+    for (setting, value) in from_files {
+        if overrides.insert(setting.clone(), value).is_some() {
+            return Err(ConfigurationError::Invalid(ValidationErrors::field(
+                &setting,
+                format!("Set both {} and {}{}, use only one", setting, setting, FILE_SUFFIX),
+            )));
+        }
+    }
+
+    Ok(overrides)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{Config, File, FileFormat};
+
+    const BASE: &str = include_str!("../../configuration/base.yaml");
+    const LOCAL: &str = include_str!("../../configuration/local.yaml");
+
+    fn load(overrides: &str) -> Result<Settings, config::ConfigError> {
+        Config::builder()
+            .add_source(File::from_str(BASE, FileFormat::Yaml))
+            .add_source(File::from_str(LOCAL, FileFormat::Yaml))
+            .add_source(File::from_str(overrides, FileFormat::Yaml))
+            .build()?
+            .try_deserialize()
+    }
+
+    #[test]
+    fn the_local_configuration_is_valid() {
+        let settings = load("{}").expect("Failed to load the configuration");
+
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let error = load("cache:\n  ttl_secnods: 5").err().expect("The typo was accepted");
+
+        assert!(error.to_string().contains("ttl_secnods"), "{}", error);
+    }
+
+    #[test]
+    fn every_invalid_value_is_reported() {
+        let settings = load("application:\n  hmac_secret: short\nredis_uri: http://127.0.0.1:6379\ndatabase:\n  port: 0")
+            .expect("Failed to load the configuration");
+
+        let errors = settings.validate().expect_err("The configuration was accepted");
+
+        let fields: Vec<_> = errors.errors().iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(vec!["application.hmac_secret", "database.port", "redis_uri"], fields);
+    }
+
+    #[test]
+    fn file_variables_are_read_into_the_setting() {
+        let path = std::env::temp_dir().join(format!("allbands-secret-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "hunter2\n").unwrap();
+        let variables = vec![
+            ("APP_ENVIRONMENT".to_string(), "local".to_string()),
+            ("APP_DATABASE__PASSWORD_FILE".to_string(), path.display().to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+
+        let overrides = environment_variables(variables.into_iter()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(HashMap::from([("APP_DATABASE__PASSWORD".to_string(), "hunter2".to_string())]), overrides);
+    }
 }
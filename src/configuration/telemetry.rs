@@ -0,0 +1,68 @@
+use crate::domain::ValidationErrors;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TelemetrySettings {
+    /// OTLP/HTTP endpoint spans are exported to, e.g.
+    /// `http://localhost:4318/v1/traces`. Export is disabled when unset.
+    pub otlp_endpoint: Option<String>,
+    /// The fraction of traces started here that are exported, between 0 and
+    /// 1. Traces continued from a `traceparent` follow the caller's decision.
+    pub sampling_ratio: f64,
+    /// The `service.name` spans are exported under
+    pub service_name: String,
+    #[serde(default)]
+    pub body_logging: BodyLoggingSettings,
+}
+
+impl TelemetrySettings {
+    pub(crate) fn validate(&self, errors: &mut ValidationErrors) {
+        if let Some(endpoint) = &self.otlp_endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                errors.push("telemetry.otlp_endpoint", "The endpoint must start with http:// or https://");
+            }
+        }
+        if !(0.0..=1.0).contains(&self.sampling_ratio) {
+            errors.push("telemetry.sampling_ratio", "The ratio must be between 0 and 1");
+        }
+        if self.service_name.is_empty() {
+            errors.push("telemetry.service_name", "The service name is required");
+        }
+        if self.body_logging.max_bytes == 0 {
+            errors.push("telemetry.body_logging.max_bytes", "Bodies must be logged up to at least one byte");
+        }
+    }
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            otlp_endpoint: None,
+            sampling_ratio: 1.0,
+            service_name: "allbands".into(),
+            body_logging: BodyLoggingSettings::default(),
+        }
+    }
+}
+
+/// Which request and response bodies are logged. Bodies are redacted of
+/// fields named like credentials before they are logged.
+#[derive(serde::Deserialize, serde::Serialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct BodyLoggingSettings {
+    pub enabled: bool,
+    /// Bodies are cut to this many bytes in the logs
+    pub max_bytes: usize,
+    /// The route templates, e.g. `/concerts/{id}`, whose bodies are logged
+    pub routes: Vec<String>,
+}
+
+impl Default for BodyLoggingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 4096,
+            routes: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use crate::domain::ArtistName;
+
+/// A validated partial update of an artist, where `None` leaves the stored
+/// value unchanged.
+#[derive(Default)]
+pub struct ArtistPatch {
+    pub name: Option<ArtistName>,
+    pub sort_name: Option<String>,
+    pub disambiguation: Option<String>,
+}
@@ -1,20 +1,43 @@
-use crate::domain::{NewArtist, UpdateArtist};
+use crate::domain::{
+    Actor,
+    ArtistPatch,
+    AuditAction,
+    AuditEntry,
+    Audited,
+    CatalogueFilter,
+    DomainError,
+    NewArtist,
+    UpdateArtist,
+};
+use futures_util::stream::BoxStream;
 use uuid::Uuid;
 use sqlx::{PgPool, Transaction, Postgres};
     
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct Artist {
     pub id: Uuid,
     pub name: String,
     pub sort_name: String,
     pub disambiguation: String,
+    pub mbid: Option<Uuid>,
+    /// Incremented on every update, and the basis of the artist's `ETag`
+    pub version: i32,
+}
+
+/// What `Artist::upsert_by_mbid` did to the artist stored with an MBID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Upserted {
+    Created,
+    Updated,
+    /// The stored artist already had the same fields
+    Unchanged,
 }
 
 impl Artist {
     /// Find an artist given an artist identifier
     ///
-    /// Returns the artist or throws a `sqlx::Error`
+    /// Returns the artist, or `None` when there is no artist with that id
     #[tracing::instrument(
         name = "Find artist by id",
         skip(id, pool)
@@ -22,19 +45,92 @@ impl Artist {
     pub async fn find_by_id(
         id: Uuid, 
         pool: &PgPool
-    ) -> Result<Option<Self>, sqlx::Error> {
+    ) -> Result<Option<Self>, DomainError> {
         let entity = sqlx::query_as!(
             Artist,
             r#"
-            SELECT id, name, sort_name, disambiguation
+            SELECT id, name, sort_name, disambiguation, mbid, version
             FROM artists
             WHERE id = $1
             "#,
             id
         )
         .fetch_optional(pool)
-        .await
-        .expect("Failed to execute request");
+        .await?;
+
+        Ok(entity)
+    }
+
+    /// Find every artist whose identifier is in `ids`
+    #[tracing::instrument(
+        name = "Find artists by ids",
+        skip(ids, pool)
+    )]
+    pub async fn find_by_ids(
+        ids: &[Uuid],
+        pool: &PgPool
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let entities = sqlx::query_as!(
+            Artist,
+            r#"
+            SELECT id, name, sort_name, disambiguation, mbid, version
+            FROM artists
+            WHERE id = ANY($1)
+            "#,
+            ids
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entities)
+    }
+
+    /// Find an artist given its exact name
+    ///
+    /// Artist names are unique, so at most one artist is returned
+    #[tracing::instrument(
+        name = "Find artist by name",
+        skip(executor)
+    )]
+    pub async fn find_by_name(
+        name: &str,
+        executor: impl sqlx::PgExecutor<'_>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let entity = sqlx::query_as!(
+            Artist,
+            r#"
+            SELECT id, name, sort_name, disambiguation, mbid, version
+            FROM artists
+            WHERE name = $1
+            "#,
+            name
+        )
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(entity)
+    }
+
+    /// Find an artist given its MusicBrainz identifier
+    #[tracing::instrument(
+        name = "Find artist by MBID",
+        skip(executor)
+    )]
+    pub async fn find_by_mbid(
+        mbid: Uuid,
+        executor: impl sqlx::PgExecutor<'_>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let entity = sqlx::query_as!(
+            Artist,
+            r#"
+            SELECT id, name, sort_name, disambiguation, mbid, version
+            FROM artists
+            WHERE mbid = $1
+            "#,
+            mbid
+        )
+        .fetch_optional(executor)
+        .await?;
 
         Ok(entity)
     }
@@ -45,58 +141,264 @@ impl Artist {
     )]
     pub async fn insert(
         item: &NewArtist,
+        actor: &Actor,
         transaction: &mut Transaction<'_, Postgres>,
-        ) -> Result<Self, sqlx::Error> {
+        ) -> Result<Self, DomainError> {
         let artist_id = Uuid::new_v4();
 
         let entity = sqlx::query_as!(
             Artist,
             r#"
-            INSERT INTO artists(id, name, sort_name, disambiguation, created_at)
-            VALUES($1, $2, $3, $4, $5)
-            RETURNING id, name, sort_name, disambiguation
+            INSERT INTO artists(id, name, sort_name, disambiguation, mbid, created_at)
+            VALUES($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, sort_name, disambiguation, mbid, version
             "#,
             artist_id,
             &item.name.as_ref(),
             &item.sort_name,
             &item.disambiguation,
+            item.mbid,
             chrono::Utc::now(),
             )
-            .fetch_one(transaction)
-            .await
-            .expect("Failed to execute request");
+            .fetch_one(&mut *transaction)
+            .await?;
+
+        AuditEntry::record(AuditAction::Created, None, &entity, actor, transaction).await?;
 
         Ok(entity)
     }
 
+    /// Insert an artist carrying a MusicBrainz identifier, or refresh the
+    /// name, sort name and disambiguation of the artist already stored with
+    /// that identifier.
+    ///
+    /// An artist already stored with the same fields is left as it is,
+    /// without bumping its version or recording a revision.
+    ///
+    /// Returns the stored artist and what was done to it
+    #[tracing::instrument(
+        name = "Upserting artist by MBID",
+        skip(transaction, item)
+    )]
+    pub async fn upsert_by_mbid(
+        mbid: Uuid,
+        item: &NewArtist,
+        actor: &Actor,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<(Self, Upserted), sqlx::Error> {
+        let before = sqlx::query_as!(
+            Artist,
+            r#"
+            SELECT id, name, sort_name, disambiguation, mbid, version
+            FROM artists
+            WHERE mbid = $1
+            FOR UPDATE
+            "#,
+            mbid,
+        )
+        .fetch_optional(&mut *transaction)
+        .await?;
+        if let Some(before) = before.as_ref() {
+            if before.name == item.name.as_ref()
+                && before.sort_name == item.sort_name
+                && before.disambiguation == item.disambiguation
+            {
+                return Ok((before.clone(), Upserted::Unchanged));
+            }
+        }
+
+        let record = sqlx::query!(
+            r#"
+            INSERT INTO artists(id, name, sort_name, disambiguation, mbid, created_at)
+            VALUES($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (mbid) DO UPDATE
+            SET name = EXCLUDED.name,
+                sort_name = EXCLUDED.sort_name,
+                disambiguation = EXCLUDED.disambiguation,
+                version = artists.version + 1,
+                updated_at = now()
+            RETURNING id, name, sort_name, disambiguation, mbid, version
+            "#,
+            Uuid::new_v4(),
+            &item.name.as_ref(),
+            &item.sort_name,
+            &item.disambiguation,
+            mbid,
+            chrono::Utc::now(),
+        )
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let entity = Artist {
+            id: record.id,
+            name: record.name,
+            sort_name: record.sort_name,
+            disambiguation: record.disambiguation,
+            mbid: record.mbid,
+            version: record.version,
+        };
+
+        let (action, upserted) = match before {
+            Some(_) => (AuditAction::Updated, Upserted::Updated),
+            None => (AuditAction::Created, Upserted::Created),
+        };
+        AuditEntry::record(action, before.as_ref(), &entity, actor, transaction).await?;
+
+        Ok((entity, upserted))
+    }
+
+    /// Update an artist, provided its stored version is one of
+    /// `expected_versions`, or whatever its version when that is `None`.
+    ///
+    /// Fails with `DomainError::VersionMismatch` when someone else updated
+    /// the artist in the meantime.
     #[tracing::instrument(
         name = "Updating artist in the database",
         skip(transaction, item)
     )]
     pub async fn update(
         item: &UpdateArtist,
+        expected_versions: Option<&[i32]>,
+        actor: &Actor,
         transaction: &mut Transaction<'_, Postgres>,
-    ) -> Result<Self, sqlx::Error> {
+    ) -> Result<Self, DomainError> {
+        let before = Self::lock(item.id, transaction).await?;
+
         let entity = sqlx::query_as!(
             Artist,
             r#"
             UPDATE artists
-            SET name = $1, sort_name = $2, disambiguation = $3
-            WHERE id = $4
-            RETURNING id, name, sort_name, disambiguation
+            SET name = $1, sort_name = $2, disambiguation = $3,
+                version = version + 1, updated_at = now()
+            WHERE id = $4 AND ($5::int4[] IS NULL OR version = ANY($5))
+            RETURNING id, name, sort_name, disambiguation, mbid, version
             "#,
             &item.name.as_ref(),
             &item.sort_name,
             &item.disambiguation,
             &item.id,
+            expected_versions,
         )
-        .fetch_one(transaction)
-        .await
-        .expect("Failed to execute request");
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(DomainError::VersionMismatch)?;
+
+        AuditEntry::record(AuditAction::Updated, Some(&before), &entity, actor, transaction).await?;
 
         Ok(entity)
     }
 
+    /// Update only the fields present in `patch`, with the same version
+    /// check as `update`.
+    #[tracing::instrument(
+        name = "Patching artist in the database",
+        skip(transaction, patch)
+    )]
+    pub async fn patch(
+        id: Uuid,
+        patch: &ArtistPatch,
+        expected_versions: Option<&[i32]>,
+        actor: &Actor,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, DomainError> {
+        let before = Self::lock(id, transaction).await?;
+
+        let entity = sqlx::query_as!(
+            Artist,
+            r#"
+            UPDATE artists
+            SET name = COALESCE($1, name),
+                sort_name = COALESCE($2, sort_name),
+                disambiguation = COALESCE($3, disambiguation),
+                version = version + 1, updated_at = now()
+            WHERE id = $4 AND ($5::int4[] IS NULL OR version = ANY($5))
+            RETURNING id, name, sort_name, disambiguation, mbid, version
+            "#,
+            patch.name.as_ref().map(AsRef::as_ref),
+            patch.sort_name,
+            patch.disambiguation,
+            id,
+            expected_versions,
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(DomainError::VersionMismatch)?;
+
+        AuditEntry::record(AuditAction::Updated, Some(&before), &entity, actor, transaction).await?;
+
+        Ok(entity)
+    }
+
+    /// Restore the name, sort name and disambiguation the artist had at
+    /// `revision`, with the same version check as `update`.
+    ///
+    /// Fails with `DomainError::NotFound` when the artist has no such
+    /// revision.
+    #[tracing::instrument(
+        name = "Reverting artist to a revision",
+        skip(transaction)
+    )]
+    pub async fn revert(
+        id: Uuid,
+        revision: i32,
+        expected_versions: Option<&[i32]>,
+        actor: &Actor,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, DomainError> {
+        let before = Self::lock(id, transaction).await?;
+        let target = AuditEntry::find_snapshot::<Artist>(id, revision, transaction)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+
+        let entity = sqlx::query_as!(
+            Artist,
+            r#"
+            UPDATE artists
+            SET name = $1, sort_name = $2, disambiguation = $3,
+                version = version + 1, updated_at = now()
+            WHERE id = $4 AND ($5::int4[] IS NULL OR version = ANY($5))
+            RETURNING id, name, sort_name, disambiguation, mbid, version
+            "#,
+            target.name,
+            target.sort_name,
+            target.disambiguation,
+            id,
+            expected_versions,
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(DomainError::VersionMismatch)?;
+
+        let action = AuditAction::Reverted { revision };
+        AuditEntry::record(action, Some(&before), &entity, actor, transaction).await?;
+
+        Ok(entity)
+    }
+
+    /// Lock the artist for the rest of the transaction, so that the change
+    /// recorded in the audit log starts from what is actually stored.
+    ///
+    /// Fails with `DomainError::NotFound` when there is no such artist.
+    async fn lock(
+        id: Uuid,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, DomainError> {
+        let entity = sqlx::query_as!(
+            Artist,
+            r#"
+            SELECT id, name, sort_name, disambiguation, mbid, version
+            FROM artists
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id,
+        )
+        .fetch_optional(transaction)
+        .await?;
+
+        entity.ok_or(DomainError::NotFound)
+    }
+
     #[tracing::instrument(
         name = "Find all artists",
         skip(pool)
@@ -105,15 +407,58 @@ impl Artist {
         let entities = sqlx::query_as!(
             Artist,
             r#"
-            SELECT id, name, sort_name, disambiguation
+            SELECT id, name, sort_name, disambiguation, mbid, version
             FROM artists
             ORDER BY name ASC
             "#,
         )
         .fetch_all(pool)
-        .await
-        .expect("Failed to execute request");
+        .await?;
 
         Ok(entities)
     }
+
+    /// Stream the artists matching `filter` row by row.
+    ///
+    /// When a date range is given only artists with at least one concert in
+    /// that range are returned.
+    pub fn stream<'a>(
+        filter: &'a CatalogueFilter,
+        executor: impl sqlx::PgExecutor<'a> + 'a,
+    ) -> BoxStream<'a, Result<Self, sqlx::Error>> {
+        sqlx::query_as!(
+            Artist,
+            r#"
+            SELECT id, name, sort_name, disambiguation, mbid, version
+            FROM artists a
+            WHERE ($1::uuid IS NULL OR a.id = $1)
+            AND (
+                ($2::date IS NULL AND $3::date IS NULL)
+                OR EXISTS (
+                    SELECT 1 FROM concerts c
+                    WHERE c.artist_id = a.id
+                    AND ($2::date IS NULL OR c.date >= $2)
+                    AND ($3::date IS NULL OR c.date <= $3)
+                )
+            )
+            ORDER BY name ASC
+            "#,
+            filter.artist_id,
+            filter.from,
+            filter.to,
+        )
+        .fetch(executor)
+    }
+}
+
+impl Audited for Artist {
+    const ENTITY_TYPE: &'static str = "artist";
+
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i32 {
+        self.version
+    }
 }
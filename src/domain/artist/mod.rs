@@ -2,8 +2,10 @@ mod entity;
 mod new_artist;
 mod artist_name;
 mod update_artist;
+mod artist_patch;
 
 pub use entity::*;
 pub use new_artist::NewArtist;
 pub use artist_name::ArtistName;
 pub use update_artist::UpdateArtist;
+pub use artist_patch::ArtistPatch;
@@ -6,4 +6,5 @@ pub struct NewArtist {
     pub name: ArtistName,
     pub sort_name: String,
     pub disambiguation: String,
+    pub mbid: Option<uuid::Uuid>,
 }
@@ -0,0 +1,65 @@
+use serde_json::{Map, Value};
+
+/// The fields that differ between two versions of an entity, as
+/// `{"field": {"before": ..., "after": ...}}`.
+///
+/// With no `before`, every field of `after` is reported as added. Fields
+/// that only track the change itself, such as `version`, are left out.
+pub fn diff(before: Option<&Value>, after: &Value) -> Value {
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let changes = after
+        .iter()
+        .filter(|(field, _)| field.as_str() != "version")
+        .filter(|(field, value)| before.get(field.as_str()) != Some(value))
+        .map(|(field, value)| {
+            let change = serde_json::json!({
+                "before": before.get(field.as_str()).cloned().unwrap_or(Value::Null),
+                "after": value,
+            });
+            (field.clone(), change)
+        })
+        .collect();
+
+    Value::Object(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+    use serde_json::json;
+
+    #[test]
+    fn only_changed_fields_are_reported() {
+        let before = json!({"name": "Billy", "city": "Lansing", "version": 1});
+        let after = json!({"name": "Billy Strings", "city": "Lansing", "version": 2});
+
+        assert_eq!(
+            diff(Some(&before), &after),
+            json!({"name": {"before": "Billy", "after": "Billy Strings"}}),
+        );
+    }
+
+    #[test]
+    fn every_field_is_added_without_a_previous_version() {
+        let after = json!({"name": "Billy Strings", "version": 1});
+
+        assert_eq!(
+            diff(None, &after),
+            json!({"name": {"before": null, "after": "Billy Strings"}}),
+        );
+    }
+
+    #[test]
+    fn a_removed_value_is_reported_as_null() {
+        let before = json!({"state": "CA"});
+        let after = json!({"state": null});
+
+        assert_eq!(
+            diff(Some(&before), &after),
+            json!({"state": {"before": "CA", "after": null}}),
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::domain::{diff, Actor};
+use serde::de::DeserializeOwned;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// An entity whose changes are recorded in the audit log.
+pub trait Audited: serde::Serialize {
+    /// The `entity_type` of its rows in the audit log
+    const ENTITY_TYPE: &'static str;
+
+    fn id(&self) -> Uuid;
+
+    fn version(&self) -> i32;
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AuditAction {
+    Created,
+    Updated,
+    /// Restored the fields of an earlier revision
+    Reverted { revision: i32 },
+}
+
+impl AuditAction {
+    fn describe(&self) -> String {
+        match self {
+            Self::Created => "created".to_string(),
+            Self::Updated => "updated".to_string(),
+            Self::Reverted { revision } => format!("reverted to revision {}", revision),
+        }
+    }
+}
+
+/// One change to an entity, as listed in its history.
+#[derive(Debug, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct AuditEntry {
+    /// The entity's version after the change
+    pub revision: i32,
+    pub action: String,
+    pub actor: String,
+    pub changed_at: chrono::DateTime<chrono::Utc>,
+    /// `{"field": {"before": ..., "after": ...}}` for every field that changed
+    #[schema(value_type = Object)]
+    pub changes: serde_json::Value,
+}
+
+impl AuditEntry {
+    /// Append the change from `before` to `after` to the audit log, in the
+    /// transaction that made it.
+    #[tracing::instrument(
+        name = "Recording a change in the audit log",
+        skip(before, after, transaction)
+    )]
+    pub async fn record<T: Audited>(
+        action: AuditAction,
+        before: Option<&T>,
+        after: &T,
+        actor: &Actor,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), sqlx::Error> {
+        let snapshot = serde_json::to_value(after).map_err(serialization_error)?;
+        let before = before
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(serialization_error)?;
+        let changes = diff(before.as_ref(), &snapshot);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (entity_type, entity_id, revision, action, actor, changes, snapshot)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            T::ENTITY_TYPE,
+            after.id(),
+            after.version(),
+            action.describe(),
+            actor.as_ref(),
+            changes,
+            snapshot,
+        )
+        .execute(transaction)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The history of an entity, oldest change first
+    #[tracing::instrument(
+        name = "Find the history of an entity",
+        skip(pool)
+    )]
+    pub async fn find_by_entity(
+        entity_type: &str,
+        entity_id: Uuid,
+        pool: &PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            AuditEntry,
+            r#"
+            SELECT revision, action, actor, changed_at, changes
+            FROM audit_log
+            WHERE entity_type = $1 AND entity_id = $2
+            ORDER BY revision
+            "#,
+            entity_type,
+            entity_id,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The entity as it was at `revision`, or `None` when there is no such
+    /// revision
+    #[tracing::instrument(
+        name = "Find an entity at a revision",
+        skip(transaction)
+    )]
+    pub async fn find_snapshot<T: Audited + DeserializeOwned>(
+        entity_id: Uuid,
+        revision: i32,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<T>, sqlx::Error> {
+        let snapshot = sqlx::query_scalar!(
+            r#"
+            SELECT snapshot
+            FROM audit_log
+            WHERE entity_type = $1 AND entity_id = $2 AND revision = $3
+            "#,
+            T::ENTITY_TYPE,
+            entity_id,
+            revision,
+        )
+        .fetch_optional(transaction)
+        .await?;
+
+        snapshot
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    }
+}
+
+fn serialization_error(e: serde_json::Error) -> sqlx::Error {
+    sqlx::Error::Protocol(format!("Failed to serialize an audited entity: {}", e))
+}
@@ -0,0 +1,7 @@
+mod actor;
+mod changes;
+mod entry;
+
+pub use actor::*;
+pub use changes::*;
+pub use entry::*;
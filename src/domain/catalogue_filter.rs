@@ -0,0 +1,11 @@
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+/// Restricts a walk over the catalogue to a single artist and/or a range of
+/// concert dates. Every bound is optional and inclusive.
+#[derive(Debug, Default, Clone, serde::Deserialize)]
+pub struct CatalogueFilter {
+    pub artist_id: Option<Uuid>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
@@ -0,0 +1,20 @@
+use super::{
+    ConcertVenue,
+    ConcertCity,
+    ConcertState,
+    ConcertDate,
+    ConcertCountry
+};
+
+/// A validated partial update of a concert, where `None` leaves the stored
+/// value unchanged.
+#[derive(Default)]
+pub struct ConcertPatch {
+    pub artist_id: Option<uuid::Uuid>,
+    pub venue: Option<ConcertVenue>,
+    pub city: Option<ConcertCity>,
+    /// `Some(None)` clears the state, which is optional for a concert
+    pub state: Option<Option<ConcertState>>,
+    pub date: Option<ConcertDate>,
+    pub country: Option<ConcertCountry>,
+}
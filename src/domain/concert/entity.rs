@@ -1,8 +1,21 @@
-use crate::domain::{NewConcert, UpdateConcert};
+use crate::domain::{
+    likely_same_place,
+    Actor,
+    AuditAction,
+    AuditEntry,
+    Audited,
+    CatalogueFilter,
+    ConcertPatch,
+    DomainError,
+    NewConcert,
+    Timeframe,
+    UpdateConcert,
+};
+use futures_util::stream::BoxStream;
 use sqlx::{Postgres, Transaction};
 
 
-#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, sqlx::FromRow, utoipa::ToSchema)]
 pub struct Concert {
     pub id: uuid::Uuid,
     pub artist_id: uuid::Uuid,
@@ -11,6 +24,8 @@ pub struct Concert {
     pub state: Option<String>,
     pub country: String,
     pub date: chrono::NaiveDate,
+    /// Incremented on every update, and the basis of the concert's `ETag`
+    pub version: i32,
 }
 
 impl Concert {
@@ -20,8 +35,9 @@ impl Concert {
     )]
     pub async fn insert(
         item: &NewConcert,
+        actor: &Actor,
         transaction: &mut Transaction<'_, Postgres>,
-    ) -> Result<Self, sqlx::Error> {
+    ) -> Result<Self, DomainError> {
         let concert_id = uuid::Uuid::new_v4();
         
         let entity = sqlx::query_as!(
@@ -29,7 +45,7 @@ impl Concert {
             r#"
             INSERT INTO concerts (id, artist_id, venue, city, state, country, date, created_at)
             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-            RETURNING id, artist_id, venue, city, state, country, date
+            RETURNING id, artist_id, venue, city, state, country, date, version
             "#,
             concert_id,
             &item.artist_id,
@@ -40,9 +56,10 @@ impl Concert {
             &item.date.as_ref(),
             chrono::Utc::now(),
         )
-        .fetch_one(transaction)
-        .await
-        .expect("Failed to insert concert");
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        AuditEntry::record(AuditAction::Created, None, &entity, actor, transaction).await?;
 
         Ok(entity)
     }
@@ -55,7 +72,7 @@ impl Concert {
         let entities = sqlx::query_as!(
             Self,
             r#"
-            SELECT id, artist_id, venue, city, state, country, date
+            SELECT id, artist_id, venue, city, state, country, date, version
             FROM concerts
             ORDER BY date
             "#,
@@ -68,6 +85,28 @@ impl Concert {
         Ok(entities)
     }
 
+    /// Stream the concerts matching `filter` row by row, ordered by date.
+    pub fn stream<'a>(
+        filter: &'a CatalogueFilter,
+        executor: impl sqlx::PgExecutor<'a> + 'a,
+    ) -> BoxStream<'a, Result<Self, sqlx::Error>> {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT id, artist_id, venue, city, state, country, date, version
+            FROM concerts
+            WHERE ($1::uuid IS NULL OR artist_id = $1)
+            AND ($2::date IS NULL OR date >= $2)
+            AND ($3::date IS NULL OR date <= $3)
+            ORDER BY date, id
+            "#,
+            filter.artist_id,
+            filter.from,
+            filter.to,
+        )
+        .fetch(executor)
+    }
+
     #[tracing::instrument(
         name = "Find a concert by id",
         skip(id, pool)
@@ -75,38 +114,332 @@ impl Concert {
     pub async fn find_by_id(
         id: uuid::Uuid,
         pool: &sqlx::PgPool,
-    ) -> Result<Option<Self>, sqlx::Error> {
+    ) -> Result<Option<Self>, DomainError> {
         let entity = sqlx::query_as!(
             Concert,
             r#"
-            SELECT id, artist_id, venue, city, state, country, date
+            SELECT id, artist_id, venue, city, state, country, date, version
             FROM concerts
             WHERE id = $1
             "#,
             id,
         )
         .fetch_optional(pool)
-        .await
-        .expect("Failed to find concert");
+        .await?;
 
         Ok(entity)
     }
 
+    /// Find the concerts of every artist in `artist_ids`, ordered by date
+    #[tracing::instrument(
+        name = "Find concerts by artist ids",
+        skip(artist_ids, pool)
+    )]
+    pub async fn find_by_artist_ids(
+        artist_ids: &[uuid::Uuid],
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let entities = sqlx::query_as!(
+            Concert,
+            r#"
+            SELECT id, artist_id, venue, city, state, country, date, version
+            FROM concerts
+            WHERE artist_id = ANY($1)
+            ORDER BY date
+            "#,
+            artist_ids,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entities)
+    }
+
+    /// Find the concerts of an artist in `timeframe` relative to `today`,
+    /// skipping the first `offset` and returning at most `limit`, or all of
+    /// the rest when `limit` is `None`
+    #[tracing::instrument(
+        name = "Find concerts by artist",
+        skip(pool)
+    )]
+    pub async fn find_by_artist(
+        artist_id: uuid::Uuid,
+        timeframe: Timeframe,
+        today: chrono::NaiveDate,
+        limit: Option<i64>,
+        offset: i64,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let entities = match timeframe {
+            Timeframe::All => {
+                sqlx::query_as!(
+                    Concert,
+                    r#"
+                    SELECT id, artist_id, venue, city, state, country, date, version
+                    FROM concerts
+                    WHERE artist_id = $1
+                    ORDER BY date, id
+                    LIMIT $2 OFFSET $3
+                    "#,
+                    artist_id,
+                    limit,
+                    offset,
+                )
+                .fetch_all(pool)
+                .await?
+            }
+            Timeframe::Upcoming => {
+                sqlx::query_as!(
+                    Concert,
+                    r#"
+                    SELECT id, artist_id, venue, city, state, country, date, version
+                    FROM concerts
+                    WHERE artist_id = $1 AND date >= $2
+                    ORDER BY date, id
+                    LIMIT $3 OFFSET $4
+                    "#,
+                    artist_id,
+                    today,
+                    limit,
+                    offset,
+                )
+                .fetch_all(pool)
+                .await?
+            }
+            Timeframe::Past => {
+                sqlx::query_as!(
+                    Concert,
+                    r#"
+                    SELECT id, artist_id, venue, city, state, country, date, version
+                    FROM concerts
+                    WHERE artist_id = $1 AND date < $2
+                    ORDER BY date DESC, id DESC
+                    LIMIT $3 OFFSET $4
+                    "#,
+                    artist_id,
+                    today,
+                    limit,
+                    offset,
+                )
+                .fetch_all(pool)
+                .await?
+            }
+        };
+
+        Ok(entities)
+    }
+
+    /// Count the concerts `find_by_artist` pages through
+    #[tracing::instrument(
+        name = "Count concerts by artist",
+        skip(pool)
+    )]
+    pub async fn count_by_artist(
+        artist_id: uuid::Uuid,
+        timeframe: Timeframe,
+        today: chrono::NaiveDate,
+        pool: &sqlx::PgPool,
+    ) -> Result<i64, sqlx::Error> {
+        let count = match timeframe {
+            Timeframe::All => {
+                sqlx::query_scalar!(
+                    r#"SELECT COUNT(*) AS "count!" FROM concerts WHERE artist_id = $1"#,
+                    artist_id,
+                )
+                .fetch_one(pool)
+                .await?
+            }
+            Timeframe::Upcoming => {
+                sqlx::query_scalar!(
+                    r#"SELECT COUNT(*) AS "count!" FROM concerts WHERE artist_id = $1 AND date >= $2"#,
+                    artist_id,
+                    today,
+                )
+                .fetch_one(pool)
+                .await?
+            }
+            Timeframe::Past => {
+                sqlx::query_scalar!(
+                    r#"SELECT COUNT(*) AS "count!" FROM concerts WHERE artist_id = $1 AND date < $2"#,
+                    artist_id,
+                    today,
+                )
+                .fetch_one(pool)
+                .await?
+            }
+        };
+
+        Ok(count)
+    }
+
+    /// Find the concerts an artist played on a given date
+    #[tracing::instrument(
+        name = "Find concerts by artist and date",
+        skip(executor)
+    )]
+    pub async fn find_by_artist_and_date(
+        artist_id: uuid::Uuid,
+        date: chrono::NaiveDate,
+        executor: impl sqlx::PgExecutor<'_>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let entities = sqlx::query_as!(
+            Concert,
+            r#"
+            SELECT id, artist_id, venue, city, state, country, date, version
+            FROM concerts
+            WHERE artist_id = $1 AND date = $2
+            "#,
+            artist_id,
+            date,
+        )
+        .fetch_all(executor)
+        .await?;
+
+        Ok(entities)
+    }
+
+    /// Find an existing concert that most likely describes the same show:
+    /// same artist, same date and a similar venue and city.
+    ///
+    /// Takes a lock on the artist and date that is held until `transaction`
+    /// ends, so that two requests creating the same show can't both find no
+    /// duplicate: insert the concert in the same transaction.
+    #[tracing::instrument(
+        name = "Find a likely duplicate concert",
+        skip(item, transaction)
+    )]
+    pub async fn find_likely_duplicate(
+        item: &NewConcert,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        Self::lock_and_find_duplicate(
+            item.artist_id,
+            *item.date.as_ref(),
+            item.venue.as_ref(),
+            item.city.as_ref(),
+            None,
+            transaction,
+        )
+        .await
+    }
+
+    /// Find another concert that most likely describes the same show as
+    /// `item` will once it is updated, locking like `find_likely_duplicate`.
+    #[tracing::instrument(
+        name = "Find a likely duplicate of an updated concert",
+        skip(item, transaction)
+    )]
+    pub async fn find_likely_duplicate_of_update(
+        item: &UpdateConcert,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        Self::lock_and_find_duplicate(
+            item.artist_id,
+            *item.date.as_ref(),
+            item.venue.as_ref(),
+            item.city.as_ref(),
+            Some(item.id),
+            transaction,
+        )
+        .await
+    }
+
+    /// Find another concert that most likely describes the same show as
+    /// `concert`, as saved in `transaction`, locking like
+    /// `find_likely_duplicate`.
+    #[tracing::instrument(
+        name = "Find a likely duplicate of a saved concert",
+        skip(concert, transaction)
+    )]
+    pub async fn find_likely_duplicate_of_saved(
+        concert: &Concert,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        Self::lock_and_find_duplicate(
+            concert.artist_id,
+            concert.date,
+            &concert.venue,
+            &concert.city,
+            Some(concert.id),
+            transaction,
+        )
+        .await
+    }
+
+    async fn lock_and_find_duplicate(
+        artist_id: uuid::Uuid,
+        date: chrono::NaiveDate,
+        venue: &str,
+        city: &str,
+        except: Option<uuid::Uuid>,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query!(
+            "SELECT pg_advisory_xact_lock(hashtextextended($1::uuid::text || $2::date::text, 0))",
+            artist_id,
+            date,
+        )
+        .execute(&mut *transaction)
+        .await?;
+        let candidates = Self::find_by_artist_and_date(artist_id, date, &mut *transaction).await?;
+
+        Ok(candidates.into_iter().find(|concert| {
+            Some(concert.id) != except && likely_same_place(&concert.venue, &concert.city, venue, city)
+        }))
+    }
+
+    /// Find every concert sharing its artist and date with at least one other
+    /// concert, ordered so that such groups are contiguous.
+    #[tracing::instrument(
+        name = "Find concerts sharing an artist and a date",
+        skip(pool)
+    )]
+    pub async fn find_sharing_artist_and_date(
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let entities = sqlx::query_as!(
+            Concert,
+            r#"
+            SELECT id, artist_id, venue, city, state, country, date, version
+            FROM concerts c
+            WHERE EXISTS (
+                SELECT 1 FROM concerts d
+                WHERE d.artist_id = c.artist_id AND d.date = c.date AND d.id <> c.id
+            )
+            ORDER BY artist_id, date, created_at, id
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entities)
+    }
+
+    /// Update a concert, provided its stored version is one of
+    /// `expected_versions`, or whatever its version when that is `None`.
+    ///
+    /// Fails with `DomainError::VersionMismatch` when someone else updated
+    /// the concert in the meantime.
     #[tracing::instrument(
         name = "Update Concert",
         skip(item, transaction)
     )]
     pub async fn update(
         item: &UpdateConcert,
+        expected_versions: Option<&[i32]>,
+        actor: &Actor,
         transaction: &mut Transaction<'_, Postgres>,
-    ) -> Result<Self, sqlx::Error> {
+    ) -> Result<Self, DomainError> {
+        let before = Self::lock(item.id, transaction).await?;
+
         let entity = sqlx::query_as!(
             Concert,
             r#"
             UPDATE concerts
-            SET artist_id = $1, venue = $2, city = $3, state = $4, country = $5, date = $6
-            WHERE id = $7
-            RETURNING id, artist_id, venue, city, state, country, date
+            SET artist_id = $1, venue = $2, city = $3, state = $4, country = $5, date = $6,
+                version = version + 1, updated_at = now()
+            WHERE id = $7 AND ($8::int4[] IS NULL OR version = ANY($8))
+            RETURNING id, artist_id, venue, city, state, country, date, version
             "#,
             &item.artist_id,
             &item.venue.as_ref(),
@@ -115,11 +448,147 @@ impl Concert {
             &item.country.as_ref(),
             &item.date.as_ref(),
             &item.id,
+            expected_versions,
         )
-        .fetch_one(transaction)
-        .await
-        .expect("Failed to update concert");
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(DomainError::VersionMismatch)?;
+
+        AuditEntry::record(AuditAction::Updated, Some(&before), &entity, actor, transaction).await?;
+
+        Ok(entity)
+    }
+
+    /// Update only the fields present in `patch`, with the same version
+    /// check as `update`.
+    #[tracing::instrument(
+        name = "Patch Concert",
+        skip(patch, transaction)
+    )]
+    pub async fn patch(
+        id: uuid::Uuid,
+        patch: &ConcertPatch,
+        expected_versions: Option<&[i32]>,
+        actor: &Actor,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, DomainError> {
+        let before = Self::lock(id, transaction).await?;
+
+        let entity = sqlx::query_as!(
+            Concert,
+            r#"
+            UPDATE concerts
+            SET artist_id = COALESCE($1, artist_id),
+                venue = COALESCE($2, venue),
+                city = COALESCE($3, city),
+                state = CASE WHEN $4 THEN $5 ELSE state END,
+                country = COALESCE($6, country),
+                date = COALESCE($7, date),
+                version = version + 1, updated_at = now()
+            WHERE id = $8 AND ($9::int4[] IS NULL OR version = ANY($9))
+            RETURNING id, artist_id, venue, city, state, country, date, version
+            "#,
+            patch.artist_id,
+            patch.venue.as_ref().map(AsRef::as_ref),
+            patch.city.as_ref().map(AsRef::as_ref),
+            patch.state.is_some(),
+            patch.state.as_ref().and_then(Option::as_ref).map(AsRef::as_ref),
+            patch.country.as_ref().map(AsRef::as_ref),
+            patch.date.as_ref().map(AsRef::as_ref),
+            id,
+            expected_versions,
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(DomainError::VersionMismatch)?;
+
+        AuditEntry::record(AuditAction::Updated, Some(&before), &entity, actor, transaction).await?;
+
+        Ok(entity)
+    }
+
+    /// Restore the fields the concert had at `revision`, with the same
+    /// version check as `update`.
+    ///
+    /// Fails with `DomainError::NotFound` when the concert has no such
+    /// revision.
+    #[tracing::instrument(
+        name = "Revert Concert",
+        skip(transaction)
+    )]
+    pub async fn revert(
+        id: uuid::Uuid,
+        revision: i32,
+        expected_versions: Option<&[i32]>,
+        actor: &Actor,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, DomainError> {
+        let before = Self::lock(id, transaction).await?;
+        let target = AuditEntry::find_snapshot::<Concert>(id, revision, transaction)
+            .await?
+            .ok_or(DomainError::NotFound)?;
+
+        let entity = sqlx::query_as!(
+            Concert,
+            r#"
+            UPDATE concerts
+            SET artist_id = $1, venue = $2, city = $3, state = $4, country = $5, date = $6,
+                version = version + 1, updated_at = now()
+            WHERE id = $7 AND ($8::int4[] IS NULL OR version = ANY($8))
+            RETURNING id, artist_id, venue, city, state, country, date, version
+            "#,
+            target.artist_id,
+            target.venue,
+            target.city,
+            target.state,
+            target.country,
+            target.date,
+            id,
+            expected_versions,
+        )
+        .fetch_optional(&mut *transaction)
+        .await?
+        .ok_or(DomainError::VersionMismatch)?;
+
+        let action = AuditAction::Reverted { revision };
+        AuditEntry::record(action, Some(&before), &entity, actor, transaction).await?;
 
         Ok(entity)
     }
+
+    /// Lock the concert for the rest of the transaction, so that the change
+    /// recorded in the audit log starts from what is actually stored.
+    ///
+    /// Fails with `DomainError::NotFound` when there is no such concert.
+    async fn lock(
+        id: uuid::Uuid,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, DomainError> {
+        let entity = sqlx::query_as!(
+            Concert,
+            r#"
+            SELECT id, artist_id, venue, city, state, country, date, version
+            FROM concerts
+            WHERE id = $1
+            FOR UPDATE
+            "#,
+            id,
+        )
+        .fetch_optional(transaction)
+        .await?;
+
+        entity.ok_or(DomainError::NotFound)
+    }
+}
+
+impl Audited for Concert {
+    const ENTITY_TYPE: &'static str = "concert";
+
+    fn id(&self) -> uuid::Uuid {
+        self.id
+    }
+
+    fn version(&self) -> i32 {
+        self.version
+    }
 }
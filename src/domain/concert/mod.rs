@@ -6,6 +6,9 @@ mod concert_state;
 mod concert_date;
 mod concert_country;
 mod update_concert;
+mod concert_patch;
+mod similarity;
+mod timeframe;
 
 pub use entity::*;
 pub use new_concert::*;
@@ -15,3 +18,6 @@ pub use concert_state::*;
 pub use concert_date::*;
 pub use concert_country::*;
 pub use update_concert::*;
+pub use concert_patch::*;
+pub use similarity::*;
+pub use timeframe::*;
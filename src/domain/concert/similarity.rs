@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+/// Venues or cities scoring at least this much are considered the same place.
+pub const SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// Lowercase, replace punctuation with spaces, collapse whitespace and drop a
+/// leading "the", so that "The Fillmore" and "fillmore" compare equal.
+pub fn normalise(s: &str) -> String {
+    let cleaned = s
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>();
+    let words = cleaned.split_whitespace().collect::<Vec<_>>();
+
+    match words.as_slice() {
+        ["the", rest @ ..] if !rest.is_empty() => rest.join(" "),
+        words => words.join(" "),
+    }
+}
+
+/// Trigram similarity in the style of Postgres' `pg_trgm`: each word is padded
+/// with two leading and one trailing space, and the score is the number of
+/// shared trigrams over the number of distinct trigrams in either string.
+pub fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let a = trigrams(&normalise(a));
+    let b = trigrams(&normalise(b));
+
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    let shared = a.intersection(&b).count();
+    let total = a.union(&b).count();
+
+    shared as f64 / total as f64
+}
+
+fn trigrams(s: &str) -> HashSet<[char; 3]> {
+    s.split_whitespace()
+        .flat_map(|word| {
+            let padded = format!("  {} ", word).chars().collect::<Vec<_>>();
+            padded
+                .windows(3)
+                .map(|w| [w[0], w[1], w[2]])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Whether two venue/city pairs most likely describe the same place.
+pub fn likely_same_place(venue_a: &str, city_a: &str, venue_b: &str, city_b: &str) -> bool {
+    let same_venue = normalise(venue_a) == normalise(venue_b)
+        || trigram_similarity(venue_a, venue_b) >= SIMILARITY_THRESHOLD;
+    let same_city = normalise(city_a) == normalise(city_b)
+        || trigram_similarity(city_a, city_b) >= SIMILARITY_THRESHOLD;
+
+    same_venue && same_city
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{likely_same_place, normalise, trigram_similarity};
+
+    #[test]
+    fn normalising_drops_case_punctuation_and_a_leading_article() {
+        assert_eq!("fillmore", normalise("The Fillmore"));
+        assert_eq!("red rocks amphitheatre", normalise("Red Rocks  Amphitheatre!"));
+        assert_eq!("the", normalise("The"));
+    }
+
+    #[test]
+    fn identical_strings_are_fully_similar() {
+        assert_eq!(1.0, trigram_similarity("The Fillmore", "fillmore"));
+    }
+
+    #[test]
+    fn unrelated_strings_are_not_similar() {
+        assert!(trigram_similarity("The Fillmore", "Red Rocks") < 0.1);
+    }
+
+    #[test]
+    fn small_typos_are_still_the_same_place() {
+        assert!(likely_same_place(
+            "Red Rocks Amphitheatre",
+            "Morrison",
+            "Red Rocks Amphitheater",
+            "Morrison",
+        ));
+    }
+
+    #[test]
+    fn the_same_venue_name_in_another_city_is_a_different_place() {
+        assert!(!likely_same_place(
+            "The Fillmore",
+            "San Francisco",
+            "The Fillmore",
+            "Detroit",
+        ));
+    }
+}
@@ -0,0 +1,12 @@
+/// Which of an artist's concerts to list, relative to a given day.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Timeframe {
+    /// Every concert, oldest first
+    #[default]
+    All,
+    /// Concerts on or after the day, soonest first
+    Upcoming,
+    /// Concerts before the day, most recent first
+    Past,
+}
@@ -0,0 +1,51 @@
+use sqlx::postgres::PgDatabaseError;
+
+/// Postgres error code for `unique_violation`
+const UNIQUE_VIOLATION: &str = "23505";
+/// Postgres error code for `foreign_key_violation`
+const FOREIGN_KEY_VIOLATION: &str = "23503";
+
+/// The ways a query against an entity table can fail that callers are
+/// expected to handle, rather than treat as a bug.
+#[derive(thiserror::Error, Debug)]
+pub enum DomainError {
+    /// A unique constraint rejected the write, e.g. an artist name that is
+    /// already taken
+    #[error("{0}")]
+    Conflict(String),
+    /// A foreign key rejected the write, e.g. a concert for an unknown artist
+    #[error("{0}")]
+    InvalidReference(String),
+    #[error("The record was not found")]
+    NotFound,
+    /// The record changed since the version the caller based its write on
+    #[error("The record was changed by someone else")]
+    VersionMismatch,
+    #[error(transparent)]
+    Unexpected(sqlx::Error),
+}
+
+impl From<sqlx::Error> for DomainError {
+    fn from(e: sqlx::Error) -> Self {
+        if let sqlx::Error::RowNotFound = e {
+            return Self::NotFound;
+        }
+
+        let (code, detail) = match e.as_database_error() {
+            Some(db) => match db.try_downcast_ref::<PgDatabaseError>() {
+                Some(pg) => (pg.code().to_string(), pg.detail().map(str::to_string)),
+                None => return Self::Unexpected(e),
+            },
+            None => return Self::Unexpected(e),
+        };
+
+        // Postgres' detail, e.g. `Key (name)=(Goose) already exists.`, names
+        // the offending column without exposing anything else of the schema.
+        let detail = detail.unwrap_or_else(|| e.to_string());
+        match code.as_str() {
+            UNIQUE_VIOLATION => Self::Conflict(detail),
+            FOREIGN_KEY_VIOLATION => Self::InvalidReference(detail),
+            _ => Self::Unexpected(e),
+        }
+    }
+}
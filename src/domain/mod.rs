@@ -1,5 +1,17 @@
 mod artist;
+mod audit;
+mod catalogue_filter;
 mod concert;
+mod error;
+mod song;
+mod submission;
+mod validation;
 
 pub use artist::*;
+pub use audit::*;
+pub use catalogue_filter::*;
 pub use concert::*;
+pub use error::*;
+pub use song::*;
+pub use submission::*;
+pub use validation::*;
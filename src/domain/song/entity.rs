@@ -0,0 +1,70 @@
+use crate::domain::NewSong;
+use sqlx::{Postgres, Transaction};
+
+/// A song played at a concert, in setlist order.
+#[derive(Debug, serde::Serialize, serde::Deserialize, sqlx::FromRow)]
+pub struct Song {
+    pub id: uuid::Uuid,
+    pub concert_id: uuid::Uuid,
+    pub set_number: i32,
+    pub set_name: Option<String>,
+    pub encore: Option<i32>,
+    pub position: i32,
+    pub title: String,
+}
+
+impl Song {
+    #[tracing::instrument(
+        name = "Add a song to a concert",
+        skip(item, transaction)
+    )]
+    pub async fn insert(
+        item: &NewSong,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, sqlx::Error> {
+        let entity = sqlx::query_as!(
+            Song,
+            r#"
+            INSERT INTO songs (id, concert_id, set_number, set_name, encore, position, title, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id, concert_id, set_number, set_name, encore, position, title
+            "#,
+            uuid::Uuid::new_v4(),
+            &item.concert_id,
+            item.set_number,
+            item.set_name,
+            item.encore,
+            item.position,
+            &item.title.as_ref(),
+            chrono::Utc::now(),
+        )
+        .fetch_one(transaction)
+        .await?;
+
+        Ok(entity)
+    }
+
+    #[tracing::instrument(
+        name = "Find the songs of a concert",
+        skip(pool)
+    )]
+    pub async fn find_by_concert(
+        concert_id: uuid::Uuid,
+        pool: &sqlx::PgPool,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let entities = sqlx::query_as!(
+            Song,
+            r#"
+            SELECT id, concert_id, set_number, set_name, encore, position, title
+            FROM songs
+            WHERE concert_id = $1
+            ORDER BY set_number, position
+            "#,
+            concert_id,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entities)
+    }
+}
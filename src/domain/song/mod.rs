@@ -0,0 +1,7 @@
+mod entity;
+mod new_song;
+mod song_title;
+
+pub use entity::*;
+pub use new_song::*;
+pub use song_title::*;
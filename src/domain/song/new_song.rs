@@ -0,0 +1,10 @@
+use super::SongTitle;
+
+pub struct NewSong {
+    pub concert_id: uuid::Uuid,
+    pub set_number: i32,
+    pub set_name: Option<String>,
+    pub encore: Option<i32>,
+    pub position: i32,
+    pub title: SongTitle,
+}
@@ -0,0 +1,25 @@
+use unicode_segmentation::UnicodeSegmentation;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct SongTitle(String);
+
+impl SongTitle {
+    pub fn parse(s: String) -> Result<Self, String> {
+        let is_empty_or_whitespace = s.trim().is_empty();
+        let is_too_long = s.graphemes(true).count() > 256;
+
+        if is_empty_or_whitespace || is_too_long {
+            Err(format!("{} is not a valid song title", s))
+        }
+        else {
+            Ok(Self(s))
+        }
+    }
+}
+
+impl AsRef<str> for SongTitle {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,176 @@
+use crate::domain::{Actor, DomainError, NewSubmission};
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[sqlx(type_name = "submission_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionKind {
+    /// A `CreateConcertRequest`
+    NewConcert,
+    /// A `routes::BodyData`, the body of `PUT /artists/{id}`
+    UpdateArtist,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, sqlx::Type, utoipa::ToSchema)]
+#[sqlx(type_name = "submission_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct Submission {
+    pub id: Uuid,
+    pub kind: SubmissionKind,
+    pub target_id: Option<Uuid>,
+    pub target_version: Option<i32>,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+    pub submitted_by: String,
+    pub status: SubmissionStatus,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The concert or artist the approved change created or updated
+    pub result_id: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Submission {
+    #[tracing::instrument(
+        name = "Saving a new submission",
+        skip(item, pool)
+    )]
+    pub async fn insert(
+        item: &NewSubmission,
+        submitted_by: &Actor,
+        pool: &PgPool,
+    ) -> Result<Self, DomainError> {
+        let entity = sqlx::query_as!(
+            Submission,
+            r#"
+            INSERT INTO submissions (id, kind, target_id, target_version, payload, submitted_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, kind as "kind: SubmissionKind", target_id, target_version, payload,
+                submitted_by, status as "status: SubmissionStatus", reviewed_by, reviewed_at,
+                result_id, created_at
+            "#,
+            Uuid::new_v4(),
+            item.kind as SubmissionKind,
+            item.target_id,
+            item.target_version,
+            item.payload,
+            submitted_by.as_ref(),
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(entity)
+    }
+
+    #[tracing::instrument(
+        name = "Find a submission by id",
+        skip(pool)
+    )]
+    pub async fn find_by_id(id: Uuid, pool: &PgPool) -> Result<Option<Self>, DomainError> {
+        let entity = sqlx::query_as!(
+            Submission,
+            r#"
+            SELECT id, kind as "kind: SubmissionKind", target_id, target_version, payload,
+                submitted_by, status as "status: SubmissionStatus", reviewed_by, reviewed_at,
+                result_id, created_at
+            FROM submissions
+            WHERE id = $1
+            "#,
+            id,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(entity)
+    }
+
+    /// The submissions waiting for a moderator, oldest first
+    #[tracing::instrument(
+        name = "Find pending submissions",
+        skip(pool)
+    )]
+    pub async fn find_pending(pool: &PgPool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Submission,
+            r#"
+            SELECT id, kind as "kind: SubmissionKind", target_id, target_version, payload,
+                submitted_by, status as "status: SubmissionStatus", reviewed_by, reviewed_at,
+                result_id, created_at
+            FROM submissions
+            WHERE status = 'pending'
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Lock a pending submission for review, so that two moderators cannot
+    /// both apply it.
+    ///
+    /// Fails with `DomainError::NotFound` when there is no such submission
+    /// or it was already reviewed.
+    #[tracing::instrument(
+        name = "Lock a pending submission",
+        skip(transaction)
+    )]
+    pub async fn lock_pending(
+        id: Uuid,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<Self, DomainError> {
+        let entity = sqlx::query_as!(
+            Submission,
+            r#"
+            SELECT id, kind as "kind: SubmissionKind", target_id, target_version, payload,
+                submitted_by, status as "status: SubmissionStatus", reviewed_by, reviewed_at,
+                result_id, created_at
+            FROM submissions
+            WHERE id = $1 AND status = 'pending'
+            FOR UPDATE
+            "#,
+            id,
+        )
+        .fetch_optional(transaction)
+        .await?;
+
+        entity.ok_or(DomainError::NotFound)
+    }
+
+    /// Record a moderator's decision on a submission locked with
+    /// `lock_pending`
+    #[tracing::instrument(
+        name = "Review a submission",
+        skip(transaction)
+    )]
+    pub async fn review(
+        id: Uuid,
+        status: SubmissionStatus,
+        reviewed_by: &str,
+        result_id: Option<Uuid>,
+        transaction: &mut Transaction<'_, Postgres>,
+    ) -> Result<(), DomainError> {
+        sqlx::query!(
+            r#"
+            UPDATE submissions
+            SET status = $2, reviewed_by = $3, reviewed_at = now(), result_id = $4
+            WHERE id = $1
+            "#,
+            id,
+            status as SubmissionStatus,
+            reviewed_by,
+            result_id,
+        )
+        .execute(transaction)
+        .await?;
+
+        Ok(())
+    }
+}
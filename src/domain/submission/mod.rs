@@ -0,0 +1,5 @@
+mod entity;
+mod new_submission;
+
+pub use entity::*;
+pub use new_submission::*;
@@ -0,0 +1,13 @@
+use crate::domain::SubmissionKind;
+
+/// A change proposed by someone without edit rights, waiting for a
+/// moderator.
+pub struct NewSubmission {
+    pub kind: SubmissionKind,
+    /// The artist being updated, for `SubmissionKind::UpdateArtist`
+    pub target_id: Option<uuid::Uuid>,
+    /// The version of the artist the change was based on
+    pub target_version: Option<i32>,
+    /// The request body, validated but stored as submitted
+    pub payload: serde_json::Value,
+}
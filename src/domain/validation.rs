@@ -0,0 +1,86 @@
+/// A validation failure attributed to a single field of a payload.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Every validation failure found in a payload, rather than only the first.
+#[derive(Debug, Default)]
+pub struct ValidationErrors(Vec<FieldError>);
+
+impl ValidationErrors {
+    /// A single failure, for checks that only involve one field.
+    pub fn field(field: &str, message: impl Into<String>) -> Self {
+        let mut errors = Self::default();
+        errors.push(field, message);
+        errors
+    }
+
+    pub fn push(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Record the failure of `result` against `field`, returning the parsed
+    /// value when there is one.
+    pub fn check<T>(&mut self, field: &str, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.push(field, message);
+                None
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+
+    /// The messages recorded against `field`, for showing next to it.
+    pub fn messages_for(&self, field: &str) -> Vec<&str> {
+        self.0
+            .iter()
+            .filter(|e| e.field == field)
+            .map(|e| e.message.as_str())
+            .collect()
+    }
+}
+
+impl std::fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages = self
+            .0
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect::<Vec<_>>();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidationErrors;
+
+    #[test]
+    fn every_failure_is_kept_in_order() {
+        let mut errors = ValidationErrors::default();
+
+        assert_eq!(Some(1), errors.check("a", Ok::<_, String>(1)));
+        assert_eq!(None, errors.check::<i32>("b", Err("b is wrong".into())));
+        assert_eq!(None, errors.check::<i32>("c", Err("c is wrong".into())));
+
+        let fields = errors.errors().iter().map(|e| e.field.as_str()).collect::<Vec<_>>();
+        assert_eq!(vec!["b", "c"], fields);
+        assert_eq!("b: b is wrong; c: c is wrong", errors.to_string());
+    }
+}
@@ -0,0 +1,18 @@
+use crate::features::Feature;
+use crate::routes::{ProblemCode, ProblemDetails};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+
+#[derive(thiserror::Error, Debug)]
+#[error("The {} feature is switched off", .0.as_str())]
+pub struct FeatureDisabled(pub Feature);
+
+impl ResponseError for FeatureDisabled {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::NOT_FOUND
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        ProblemDetails::new(self.status_code(), ProblemCode::FeatureDisabled, self.to_string()).into()
+    }
+}
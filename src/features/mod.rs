@@ -0,0 +1,5 @@
+mod error;
+mod toggles;
+
+pub use error::*;
+pub use toggles::*;
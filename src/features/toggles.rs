@@ -0,0 +1,55 @@
+use crate::configuration::FeatureFlags;
+use crate::features::FeatureDisabled;
+use std::sync::{Arc, RwLock};
+
+/// An endpoint, or group of endpoints, behind a flag in `FeatureFlags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Imports,
+    Submissions,
+}
+
+impl Feature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Imports => "imports",
+            Self::Submissions => "submissions",
+        }
+    }
+}
+
+/// The feature flags currently in effect, shared by every worker and
+/// replaced as a whole when the configuration is reloaded.
+#[derive(Clone)]
+pub struct Features(Arc<RwLock<FeatureFlags>>);
+
+impl Features {
+    pub fn new(flags: FeatureFlags) -> Self {
+        Self(Arc::new(RwLock::new(flags)))
+    }
+
+    pub fn current(&self) -> FeatureFlags {
+        *self.0.read().unwrap()
+    }
+
+    pub fn replace(&self, flags: FeatureFlags) {
+        *self.0.write().unwrap() = flags;
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        let flags = self.current();
+        match feature {
+            Feature::Imports => flags.imports,
+            Feature::Submissions => flags.submissions,
+        }
+    }
+
+    /// Fails when `feature` is switched off.
+    pub fn require(&self, feature: Feature) -> Result<(), FeatureDisabled> {
+        if self.is_enabled(feature) {
+            Ok(())
+        } else {
+            Err(FeatureDisabled(feature))
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::domain::{DomainError, ValidationErrors};
+use crate::rate_limit::seconds;
+use async_graphql::{Error, ErrorExtensions, Value};
+use std::time::Duration;
+
+/// A payload that failed the same validation as the REST endpoints, with
+/// every failing field listed under the `fields` extension.
+pub fn validation_error(errors: ValidationErrors) -> Error {
+    let fields = serde_json::to_value(errors.errors())
+        .ok()
+        .and_then(|fields| Value::from_json(fields).ok())
+        .unwrap_or_default();
+
+    Error::new(errors.to_string()).extend_with(|_, e| {
+        e.set("code", "VALIDATION_ERROR");
+        e.set("fields", fields.clone());
+    })
+}
+
+/// Log `error` and hide its details from the client, as the REST endpoints
+/// do for a 500.
+pub fn unexpected_error(context: &'static str, error: impl std::fmt::Debug) -> Error {
+    tracing::error!(error.cause_chain = ?error, "{}", context);
+    Error::new(context).extend_with(|_, e| e.set("code", "INTERNAL_SERVER_ERROR"))
+}
+
+/// A concert that looks like the one with `existing_id`, saved only when
+/// `force` is set.
+pub fn duplicate_error(existing_id: uuid::Uuid) -> Error {
+    Error::new(format!("This concert looks like a duplicate of concert {}", existing_id)).extend_with(|_, e| {
+        e.set("code", "DUPLICATE");
+        e.set("existingId", existing_id.to_string());
+    })
+}
+
+/// Report conflicts, unknown references and missing records to the client
+/// with the same distinctions as the REST endpoints.
+pub fn domain_error(context: &'static str, error: DomainError) -> Error {
+    let code = match &error {
+        DomainError::Conflict(_) => "CONFLICT",
+        DomainError::InvalidReference(_) => "INVALID_REFERENCE",
+        DomainError::NotFound => "NOT_FOUND",
+        DomainError::VersionMismatch => "PRECONDITION_FAILED",
+        DomainError::Unexpected(_) => return unexpected_error(context, error),
+    };
+
+    Error::new(error.to_string()).extend_with(|_, e| e.set("code", code))
+}
+
+/// A client over the limit of the route it bypassed, told when to retry as
+/// `429 Too Many Requests` would in `Retry-After`.
+pub fn rate_limited_error(retry_after: Duration) -> Error {
+    let retry_after = seconds(retry_after);
+    Error::new(format!("Too many requests, retry in {} seconds", retry_after)).extend_with(|_, e| {
+        e.set("code", "RATE_LIMITED");
+        e.set("retryAfter", retry_after);
+    })
+}
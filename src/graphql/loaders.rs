@@ -0,0 +1,43 @@
+use crate::domain::{Artist, Concert};
+use async_graphql::dataloader::Loader;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Batches `concert.artist` lookups into a single `WHERE id = ANY(...)`
+/// query per request.
+pub struct ArtistLoader(pub PgPool);
+
+#[async_graphql::async_trait::async_trait]
+impl Loader<Uuid> for ArtistLoader {
+    type Value = Artist;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let artists = Artist::find_by_ids(keys, &self.0).await?;
+
+        Ok(artists.into_iter().map(|artist| (artist.id, artist)).collect())
+    }
+}
+
+/// Batches `artist.concerts` lookups into a single
+/// `WHERE artist_id = ANY(...)` query per request.
+pub struct ConcertsByArtistLoader(pub PgPool);
+
+#[async_graphql::async_trait::async_trait]
+impl Loader<Uuid> for ConcertsByArtistLoader {
+    type Value = Vec<Concert>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, Self::Value>, Self::Error> {
+        let concerts = Concert::find_by_artist_ids(keys, &self.0).await?;
+
+        let mut by_artist: HashMap<Uuid, Self::Value> = HashMap::new();
+        for concert in concerts {
+            by_artist.entry(concert.artist_id).or_default().push(concert);
+        }
+
+        Ok(by_artist)
+    }
+}
@@ -0,0 +1,13 @@
+mod errors;
+mod loaders;
+mod mutation;
+mod objects;
+mod query;
+mod schema;
+
+pub use errors::*;
+pub use loaders::*;
+pub use mutation::*;
+pub use objects::*;
+pub use query::*;
+pub use schema::*;
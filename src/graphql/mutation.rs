@@ -0,0 +1,172 @@
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Artist, Audited, Concert, NewArtist, NewConcert, UpdateArtist, UpdateConcert};
+use crate::graphql::{domain_error, duplicate_error, rate_limited_error, unexpected_error, validation_error, ArtistNode, ConcertNode};
+use crate::metrics::count_created;
+use crate::rate_limit::RateLimiter;
+use crate::routes::{BodyData, CreateArtistRequest, CreateConcertRequest, UpdateConcertRequest};
+use async_graphql::{Context, Object, Result};
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Counts the creates of one GraphQL request against the limits of
+/// `POST /artists` and `POST /concerts`, so that the stricter limits on
+/// creates can't be bypassed through `POST /graphql`.
+pub struct CreateLimits {
+    limiter: Arc<RateLimiter>,
+    client: String,
+}
+
+impl CreateLimits {
+    /// `client` as told apart by `rate_limit::client_key`.
+    pub fn new(limiter: Arc<RateLimiter>, client: String) -> Self {
+        Self { limiter, client }
+    }
+
+    async fn check(&self, path: &str) -> Result<()> {
+        match self.limiter.check_or_allow("POST", path, &self.client).await {
+            Some(decision) if !decision.allowed => {
+                tracing::info!(client = %self.client, route = %path, "Rate limit exceeded");
+                Err(rate_limited_error(decision.retry_after))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_artist(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateArtistRequest,
+    ) -> Result<ArtistNode> {
+        ctx.data_unchecked::<CreateLimits>().check("/artists").await?;
+        let new_artist = NewArtist::try_from(input).map_err(validation_error)?;
+
+        let actor = ctx.data_unchecked::<Actor>();
+        let mut transaction = ctx
+            .data_unchecked::<PgPool>()
+            .begin()
+            .await
+            .map_err(|e| unexpected_error("Failed to acquire a Postgres connection", e))?;
+        let artist = Artist::insert(&new_artist, actor, &mut transaction)
+            .await
+            .map_err(|e| domain_error("Failed to create a new artist", e))?;
+        transaction
+            .commit()
+            .await
+            .map_err(|e| unexpected_error("Failed to commit the transaction", e))?;
+        count_created(Artist::ENTITY_TYPE, 1);
+        ctx.data_unchecked::<ResponseCache>().invalidate().await;
+
+        Ok(ArtistNode(artist))
+    }
+
+    /// Update an artist, refusing to when it has changed since `version`,
+    /// the version it was read at
+    async fn update_artist(
+        &self,
+        ctx: &Context<'_>,
+        input: BodyData,
+        version: i32,
+    ) -> Result<ArtistNode> {
+        let artist = UpdateArtist::try_from(input).map_err(validation_error)?;
+
+        let actor = ctx.data_unchecked::<Actor>();
+        let mut transaction = ctx
+            .data_unchecked::<PgPool>()
+            .begin()
+            .await
+            .map_err(|e| unexpected_error("Failed to acquire a Postgres connection", e))?;
+        let expected_versions = [version];
+        let artist = Artist::update(&artist, Some(&expected_versions), actor, &mut transaction)
+            .await
+            .map_err(|e| domain_error("Failed to update the artist", e))?;
+        transaction
+            .commit()
+            .await
+            .map_err(|e| unexpected_error("Failed to commit the transaction", e))?;
+        ctx.data_unchecked::<ResponseCache>().invalidate().await;
+
+        Ok(ArtistNode(artist))
+    }
+
+    /// Create a concert, refusing likely duplicates unless `force` is set
+    async fn create_concert(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateConcertRequest,
+        #[graphql(default)] force: bool,
+    ) -> Result<ConcertNode> {
+        ctx.data_unchecked::<CreateLimits>().check("/concerts").await?;
+        let new_concert = NewConcert::try_from(input).map_err(validation_error)?;
+        let pool = ctx.data_unchecked::<PgPool>();
+
+        let actor = ctx.data_unchecked::<Actor>();
+        let mut transaction = pool
+            .begin()
+            .await
+            .map_err(|e| unexpected_error("Failed to acquire a Postgres connection", e))?;
+        if !force {
+            let duplicate = Concert::find_likely_duplicate(&new_concert, &mut transaction)
+                .await
+                .map_err(|e| unexpected_error("Failed to look for a duplicate concert", e))?;
+            if let Some(duplicate) = duplicate {
+                return Err(duplicate_error(duplicate.id));
+            }
+        }
+
+        let concert = Concert::insert(&new_concert, actor, &mut transaction)
+            .await
+            .map_err(|e| domain_error("Failed to insert a new concert", e))?;
+        transaction
+            .commit()
+            .await
+            .map_err(|e| unexpected_error("Failed to commit the transaction", e))?;
+        count_created(Concert::ENTITY_TYPE, 1);
+        ctx.data_unchecked::<ResponseCache>().invalidate().await;
+
+        Ok(ConcertNode(concert))
+    }
+
+    /// Update a concert, refusing to when it has changed since `version`,
+    /// the version it was read at, or when it would look like a duplicate
+    /// unless `force` is set
+    async fn update_concert(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateConcertRequest,
+        version: i32,
+        #[graphql(default)] force: bool,
+    ) -> Result<ConcertNode> {
+        let concert = UpdateConcert::try_from(input).map_err(validation_error)?;
+
+        let actor = ctx.data_unchecked::<Actor>();
+        let mut transaction = ctx
+            .data_unchecked::<PgPool>()
+            .begin()
+            .await
+            .map_err(|e| unexpected_error("Failed to acquire a Postgres connection", e))?;
+        if !force {
+            let duplicate = Concert::find_likely_duplicate_of_update(&concert, &mut transaction)
+                .await
+                .map_err(|e| unexpected_error("Failed to look for a duplicate concert", e))?;
+            if let Some(duplicate) = duplicate {
+                return Err(duplicate_error(duplicate.id));
+            }
+        }
+        let expected_versions = [version];
+        let concert = Concert::update(&concert, Some(&expected_versions), actor, &mut transaction)
+            .await
+            .map_err(|e| domain_error("Failed to update the concert", e))?;
+        transaction
+            .commit()
+            .await
+            .map_err(|e| unexpected_error("Failed to commit the transaction", e))?;
+        ctx.data_unchecked::<ResponseCache>().invalidate().await;
+
+        Ok(ConcertNode(concert))
+    }
+}
@@ -0,0 +1,89 @@
+use crate::domain::{Artist, Concert};
+use crate::graphql::{ArtistLoader, ConcertsByArtistLoader};
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{Context, Object, Result};
+use uuid::Uuid;
+
+pub struct ArtistNode(pub Artist);
+
+#[Object(name = "Artist")]
+impl ArtistNode {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn sort_name(&self) -> &str {
+        &self.0.sort_name
+    }
+
+    async fn disambiguation(&self) -> &str {
+        &self.0.disambiguation
+    }
+
+    async fn mbid(&self) -> Option<Uuid> {
+        self.0.mbid
+    }
+
+    /// Incremented on every update; pass it back when updating the artist
+    async fn version(&self) -> i32 {
+        self.0.version
+    }
+
+    /// The concerts played by this artist, ordered by date
+    async fn concerts(&self, ctx: &Context<'_>) -> Result<Vec<ConcertNode>> {
+        let concerts = ctx
+            .data_unchecked::<DataLoader<ConcertsByArtistLoader>>()
+            .load_one(self.0.id)
+            .await?
+            .unwrap_or_default();
+
+        Ok(concerts.into_iter().map(ConcertNode).collect())
+    }
+}
+
+pub struct ConcertNode(pub Concert);
+
+#[Object(name = "Concert")]
+impl ConcertNode {
+    async fn id(&self) -> Uuid {
+        self.0.id
+    }
+
+    async fn venue(&self) -> &str {
+        &self.0.venue
+    }
+
+    async fn city(&self) -> &str {
+        &self.0.city
+    }
+
+    async fn state(&self) -> Option<&str> {
+        self.0.state.as_deref()
+    }
+
+    async fn country(&self) -> &str {
+        &self.0.country
+    }
+
+    async fn date(&self) -> chrono::NaiveDate {
+        self.0.date
+    }
+
+    /// Incremented on every update; pass it back when updating the concert
+    async fn version(&self) -> i32 {
+        self.0.version
+    }
+
+    async fn artist(&self, ctx: &Context<'_>) -> Result<Option<ArtistNode>> {
+        let artist = ctx
+            .data_unchecked::<DataLoader<ArtistLoader>>()
+            .load_one(self.0.artist_id)
+            .await?;
+
+        Ok(artist.map(ArtistNode))
+    }
+}
@@ -0,0 +1,42 @@
+use crate::domain::{Artist, Concert};
+use crate::graphql::{domain_error, unexpected_error, ArtistNode, ConcertNode};
+use async_graphql::{Context, Object, Result};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn artist(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<ArtistNode>> {
+        let artist = Artist::find_by_id(id, ctx.data_unchecked::<PgPool>())
+            .await
+            .map_err(|e| domain_error("Failed to fetch the artist", e))?;
+
+        Ok(artist.map(ArtistNode))
+    }
+
+    async fn artists(&self, ctx: &Context<'_>) -> Result<Vec<ArtistNode>> {
+        let artists = Artist::find_all(ctx.data_unchecked::<PgPool>())
+            .await
+            .map_err(|e| unexpected_error("Failed to fetch the artists", e))?;
+
+        Ok(artists.into_iter().map(ArtistNode).collect())
+    }
+
+    async fn concert(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<ConcertNode>> {
+        let concert = Concert::find_by_id(id, ctx.data_unchecked::<PgPool>())
+            .await
+            .map_err(|e| domain_error("Failed to fetch the concert", e))?;
+
+        Ok(concert.map(ConcertNode))
+    }
+
+    async fn concerts(&self, ctx: &Context<'_>) -> Result<Vec<ConcertNode>> {
+        let concerts = Concert::find_all(ctx.data_unchecked::<PgPool>())
+            .await
+            .map_err(|e| unexpected_error("Failed to fetch the concerts", e))?;
+
+        Ok(concerts.into_iter().map(ConcertNode).collect())
+    }
+}
@@ -0,0 +1,31 @@
+use crate::cache::ResponseCache;
+use crate::graphql::{ArtistLoader, ConcertsByArtistLoader, MutationRoot, QueryRoot};
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{EmptySubscription, Request, Schema};
+use sqlx::PgPool;
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Queries nested deeper than this are rejected before any resolver runs.
+const MAX_DEPTH: usize = 8;
+
+pub fn build_schema(pool: PgPool, cache: ResponseCache) -> AppSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(pool)
+        .data(cache)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+}
+
+/// Attach fresh data loaders to `request`.
+///
+/// Loaders cache what they fetch, so they are created per request rather
+/// than shared through the schema, where the cache would outlive any update.
+pub fn with_loaders(request: Request, pool: &PgPool) -> Request {
+    request
+        .data(DataLoader::new(ArtistLoader(pool.clone()), tokio::spawn))
+        .data(DataLoader::new(
+            ConcertsByArtistLoader(pool.clone()),
+            tokio::spawn,
+        ))
+}
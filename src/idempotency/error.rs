@@ -0,0 +1,38 @@
+use crate::routes::{error_chain_fmt, ProblemCode, ProblemDetails};
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+
+#[derive(thiserror::Error)]
+pub enum IdempotencyError {
+    #[error("{0}")]
+    InvalidKey(String),
+    #[error("The idempotency key was already used for a different request")]
+    KeyReused,
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for IdempotencyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for IdempotencyError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::InvalidKey(_) => StatusCode::BAD_REQUEST,
+            Self::KeyReused => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let code = match self {
+            Self::InvalidKey(_) => ProblemCode::InvalidIdempotencyKey,
+            Self::KeyReused => ProblemCode::IdempotencyKeyReused,
+            Self::UnexpectedError(_) => return ProblemDetails::unexpected().into(),
+        };
+        ProblemDetails::new(self.status_code(), code, self.to_string()).into()
+    }
+}
@@ -0,0 +1,158 @@
+use crate::configuration::IdempotencySettings;
+use crate::idempotency::IdempotencyError;
+use crate::rate_limit::client_key;
+use actix_web::dev::Payload;
+use actix_web::{web, FromRequest, HttpRequest};
+use sha2::{Digest, Sha256};
+use std::future::{ready, Ready};
+
+/// A client-chosen key identifying one logical request across retries.
+#[derive(Debug)]
+pub struct IdempotencyKey(String);
+
+impl TryFrom<String> for IdempotencyKey {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if s.is_empty() {
+            return Err("The idempotency key cannot be empty".into());
+        }
+        let max_length = 255;
+        if s.chars().count() >= max_length {
+            return Err(format!(
+                "The idempotency key must be shorter than {} characters",
+                max_length
+            ));
+        }
+        Ok(Self(s))
+    }
+}
+
+impl AsRef<str> for IdempotencyKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// What the request says its client is called, taken from `X-Client-Id`.
+///
+/// Any client can send any value, so it is only a hint: it is what changes
+/// are attributed to, but not what keeps clients apart.
+#[derive(Debug)]
+pub struct ClientId(String);
+
+impl ClientId {
+    const ANONYMOUS: &'static str = "anonymous";
+
+    pub(crate) fn from_request(req: &HttpRequest) -> Self {
+        let client_id = req
+            .headers()
+            .get("X-Client-Id")
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or(Self::ANONYMOUS);
+        Self(client_id.to_string())
+    }
+}
+
+impl AsRef<str> for ClientId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Who an idempotency key belongs to, so that two clients picking the same
+/// key do not see each other's responses.
+///
+/// Keys belong to the moderator the request is logged in as, or else to its
+/// IP address, as rate limits do. `X-Client-Id` then tells apart the clients
+/// sharing one of those, e.g. behind one NAT, but a client can't use it to
+/// reach into the keys of another address.
+#[derive(Debug)]
+pub struct KeyOwner(String);
+
+impl KeyOwner {
+    pub(crate) fn from_request(req: &HttpRequest) -> Self {
+        Self(format!(
+            "{}/{}",
+            client_key(req),
+            ClientId::from_request(req).as_ref()
+        ))
+    }
+}
+
+impl AsRef<str> for KeyOwner {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// The `Idempotency-Key` of a request, if it sent one, along with what is
+/// needed to look up and save its response.
+#[derive(Debug)]
+pub struct Idempotency {
+    pub(crate) key: Option<(KeyOwner, IdempotencyKey)>,
+    pub(crate) expiry: std::time::Duration,
+}
+
+impl FromRequest for Idempotency {
+    type Error = IdempotencyError;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let expiry = req
+            .app_data::<web::Data<IdempotencySettings>>()
+            .map(|settings| settings.expiry())
+            .unwrap_or_else(|| IdempotencySettings::default().expiry());
+
+        let key = match req.headers().get("Idempotency-Key") {
+            None => None,
+            Some(value) => {
+                let key = value
+                    .to_str()
+                    .map_err(|_| "The idempotency key must be visible ASCII".to_string())
+                    .and_then(|value| IdempotencyKey::try_from(value.to_string()));
+                match key {
+                    Ok(key) => Some((KeyOwner::from_request(req), key)),
+                    Err(e) => return ready(Err(IdempotencyError::InvalidKey(e))),
+                }
+            }
+        };
+
+        ready(Ok(Self { key, expiry }))
+    }
+}
+
+/// A digest of the request, used to spot a key being reused for a different
+/// request.
+pub fn request_hash(request: &impl serde::Serialize) -> Result<String, anyhow::Error> {
+    let request = serde_json::to_vec(request)?;
+    Ok(hex::encode(Sha256::digest(request)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdempotencyKey;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn an_empty_key_is_rejected() {
+        assert_err!(IdempotencyKey::try_from("".to_string()));
+    }
+
+    #[test]
+    fn a_key_of_255_characters_is_rejected() {
+        assert_err!(IdempotencyKey::try_from("a".repeat(255)));
+    }
+
+    #[test]
+    fn the_length_is_counted_in_characters() {
+        assert_ok!(IdempotencyKey::try_from("é".repeat(200)));
+    }
+
+    #[test]
+    fn a_uuid_is_accepted() {
+        assert_ok!(IdempotencyKey::try_from(uuid::Uuid::new_v4().to_string()));
+    }
+}
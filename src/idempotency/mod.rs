@@ -0,0 +1,7 @@
+mod error;
+mod key;
+mod persistence;
+
+pub use error::*;
+pub use key::*;
+pub use persistence::*;
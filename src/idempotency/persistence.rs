@@ -0,0 +1,214 @@
+use crate::idempotency::{Idempotency, IdempotencyError};
+use actix_web::body::to_bytes;
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use anyhow::Context;
+use sqlx::postgres::PgHasArrayType;
+use sqlx::{PgPool, Postgres, Transaction};
+
+#[derive(Debug, sqlx::Type)]
+#[sqlx(type_name = "header_pair")]
+struct HeaderPairRecord {
+    name: String,
+    value: Vec<u8>,
+}
+
+impl PgHasArrayType for HeaderPairRecord {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        sqlx::postgres::PgTypeInfo::with_name("_header_pair")
+    }
+}
+
+/// Marks responses replayed from an earlier request with the same key.
+const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+pub enum NextAction {
+    /// Handle the request inside this transaction, then pass the response to
+    /// [`Idempotency::save_response`] before committing.
+    StartProcessing(Box<Transaction<'static, Postgres>>),
+    ReturnSavedResponse(HttpResponse),
+}
+
+impl Idempotency {
+    /// Begin the transaction a create runs in, claiming the idempotency key
+    /// first when the request sent one.
+    ///
+    /// A retry that arrives while the first request is still running waits
+    /// on the key's row until that transaction commits or rolls back.
+    #[tracing::instrument(name = "Claiming the idempotency key", skip(self, pool))]
+    pub async fn begin(
+        &self,
+        pool: &PgPool,
+        request_hash: &str,
+    ) -> Result<NextAction, IdempotencyError> {
+        let mut transaction = pool
+            .begin()
+            .await
+            .context("Failed to acquire a Postgres connection from the pool")?;
+        let (owner, key) = match &self.key {
+            Some(key) => key,
+            None => return Ok(NextAction::StartProcessing(Box::new(transaction))),
+        };
+
+        // Only this key is looked at here; the rest expire in
+        // `delete_expired_keys`, off the request path.
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency
+            WHERE client_id = $1 AND idempotency_key = $2
+            AND created_at < now() - make_interval(secs => $3)
+            "#,
+            owner.as_ref(),
+            key.as_ref(),
+            self.expiry.as_secs_f64(),
+        )
+        .execute(&mut transaction)
+        .await
+        .context("Failed to delete the expired idempotency key")?;
+
+        let inserted = sqlx::query!(
+            r#"
+            INSERT INTO idempotency (client_id, idempotency_key, request_hash)
+            VALUES ($1, $2, $3)
+            ON CONFLICT DO NOTHING
+            "#,
+            owner.as_ref(),
+            key.as_ref(),
+            request_hash,
+        )
+        .execute(&mut transaction)
+        .await
+        .context("Failed to insert the idempotency key")?
+        .rows_affected();
+
+        if inserted > 0 {
+            return Ok(NextAction::StartProcessing(Box::new(transaction)));
+        }
+
+        let saved = sqlx::query!(
+            r#"
+            SELECT
+                request_hash,
+                response_status_code as "response_status_code!",
+                response_headers as "response_headers!: Vec<HeaderPairRecord>",
+                response_body as "response_body!"
+            FROM idempotency
+            WHERE client_id = $1 AND idempotency_key = $2
+            "#,
+            owner.as_ref(),
+            key.as_ref(),
+        )
+        .fetch_one(&mut transaction)
+        .await
+        .context("Failed to retrieve the saved response")?;
+
+        if saved.request_hash != request_hash {
+            return Err(IdempotencyError::KeyReused);
+        }
+
+        let status_code = StatusCode::from_u16(saved.response_status_code as u16)
+            .context("The saved response has an invalid status code")?;
+        let mut response = HttpResponse::build(status_code);
+        for HeaderPairRecord { name, value } in saved.response_headers {
+            response.append_header((name, value));
+        }
+        response.insert_header((REPLAYED_HEADER, "true"));
+
+        Ok(NextAction::ReturnSavedResponse(response.body(saved.response_body)))
+    }
+
+    /// Save `response` against the idempotency key, if the request sent one,
+    /// in the transaction returned by [`Idempotency::begin`].
+    #[tracing::instrument(name = "Saving the response for the idempotency key", skip_all)]
+    pub async fn save_response(
+        &self,
+        transaction: &mut Transaction<'static, Postgres>,
+        response: HttpResponse,
+    ) -> Result<HttpResponse, IdempotencyError> {
+        let (owner, key) = match &self.key {
+            Some(key) => key,
+            None => return Ok(response),
+        };
+
+        let (head, body) = response.into_parts();
+        let body = to_bytes(body)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .context("Failed to read the response body")?;
+        let status_code = head.status().as_u16() as i16;
+        let headers: Vec<_> = head
+            .headers()
+            .iter()
+            .map(|(name, value)| HeaderPairRecord {
+                name: name.as_str().to_owned(),
+                value: value.as_bytes().to_owned(),
+            })
+            .collect();
+
+        // `query_unchecked!` because the macros cannot check composite types.
+        sqlx::query_unchecked!(
+            r#"
+            UPDATE idempotency
+            SET
+                response_status_code = $3,
+                response_headers = $4,
+                response_body = $5
+            WHERE client_id = $1 AND idempotency_key = $2
+            "#,
+            owner.as_ref(),
+            key.as_ref(),
+            status_code,
+            headers,
+            body.as_ref(),
+        )
+        .execute(transaction)
+        .await
+        .context("Failed to save the response for the idempotency key")?;
+
+        Ok(head.set_body(body).map_into_boxed_body())
+    }
+}
+
+/// How often `delete_expired_keys_periodically` runs
+const CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Delete the keys saved longer than `expiry` ago, returning how many.
+#[tracing::instrument(name = "Deleting expired idempotency keys", skip(pool))]
+pub async fn delete_expired_keys(pool: &PgPool, expiry: std::time::Duration) -> Result<u64, sqlx::Error> {
+    let deleted = sqlx::query!(
+        r#"
+        DELETE FROM idempotency
+        WHERE created_at < now() - make_interval(secs => $1)
+        "#,
+        expiry.as_secs_f64(),
+    )
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    Ok(deleted)
+}
+
+/// Run `delete_expired_keys` every `CLEANUP_INTERVAL` until `stop`
+/// completes, logging rather than giving up on failures. A deletion already
+/// running when `stop` completes is finished first.
+pub async fn delete_expired_keys_periodically(
+    pool: PgPool,
+    expiry: std::time::Duration,
+    stop: impl std::future::Future<Output = ()>,
+) {
+    let start = tokio::time::Instant::now() + CLEANUP_INTERVAL;
+    let mut interval = tokio::time::interval_at(start, CLEANUP_INTERVAL);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    tokio::pin!(stop);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = &mut stop => return,
+        }
+        match delete_expired_keys(&pool, expiry).await {
+            Ok(deleted) => tracing::info!(deleted, "Deleted expired idempotency keys"),
+            Err(e) => tracing::error!(error.cause_chain = ?e, "Failed to delete expired idempotency keys"),
+        }
+    }
+}
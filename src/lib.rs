@@ -1,5 +1,17 @@
+pub mod authentication;
+pub mod background;
+pub mod cache;
 pub mod domain;
+pub mod features;
+pub mod graphql;
+pub mod idempotency;
+pub mod metrics;
+pub mod musicbrainz;
+pub mod openapi;
+pub mod rate_limit;
 pub mod routes;
+pub mod session_state;
 pub mod startup;
 pub mod configuration;
 pub mod telemetry;
+pub mod utils;
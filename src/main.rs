@@ -1,15 +1,93 @@
-use allbands::configuration::get_configuration;
-use allbands::startup::Application;
-use allbands::telemetry::{get_subscriber, init_subscriber};
+use allbands::authentication::create_moderator;
+use allbands::cache::ResponseCache;
+use allbands::configuration::{get_configuration, Settings};
+use allbands::musicbrainz::{import_artists, ArtistDump};
+use allbands::startup::{get_connection_pool, Application};
+use anyhow::Context;
+use allbands::telemetry::{get_subscriber, get_tracer, init_subscriber};
+use secrecy::{ExposeSecret, Secret};
+use std::io::Write;
+
+const USAGE: &str = "Usage: allbands [--check-config | --import-musicbrainz <file> | --create-moderator <name>]";
+
+/// What to do, as given on the command line. Without arguments, the server
+/// is started.
+enum Command {
+    Serve,
+    CheckConfig,
+    ImportMusicBrainz(String),
+    CreateModerator(String),
+}
+
+impl Command {
+    /// `None` for anything but the exact forms in `USAGE`.
+    fn parse(args: &[String]) -> Option<Self> {
+        match args {
+            [] => Some(Self::Serve),
+            [flag] if flag == "--check-config" => Some(Self::CheckConfig),
+            [flag, path] if flag == "--import-musicbrainz" => Some(Self::ImportMusicBrainz(path.clone())),
+            [flag, name] if flag == "--create-moderator" => Some(Self::CreateModerator(name.clone())),
+            _ => None,
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
-    let subscriber = get_subscriber("allbands".into(), "info".into(), std::io::stdout);
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let command = match Command::parse(&args) {
+        Some(command) => command,
+        None => {
+            writeln!(std::io::stderr(), "{}", USAGE)?;
+            std::process::exit(2);
+        }
+    };
+    let configuration = get_configuration().context("Failed to get configuration")?;
+
+    if let Command::CheckConfig = command {
+        // The configuration is valid, or `get_configuration` would have
+        // failed. Print what it resolved to, secrets aside.
+        writeln!(std::io::stdout(), "{}", serde_json::to_string_pretty(&configuration)?)?;
+        return Ok(());
+    }
+
+    let tracer = get_tracer(&configuration.telemetry)?;
+    let subscriber = get_subscriber("allbands".into(), "info".into(), std::io::stdout, tracer);
     init_subscriber(subscriber);
 
-    let configuration = get_configuration().expect("Failed to get configuration");
-    let application = Application::build(configuration).await?;
-    application.run_until_stopped().await?;
+    let result = run(command, configuration).await;
+    // Export the spans still waiting in the batch, whichever command ran.
+    tracing::info!("Flushing pending spans");
+    opentelemetry::global::shutdown_tracer_provider();
+
+    result
+}
+
+async fn run(command: Command, configuration: Settings) -> Result<(), anyhow::Error> {
+    match command {
+        Command::ImportMusicBrainz(path) => {
+            let pool = get_connection_pool(&configuration.database);
+            let summary = import_artists(&ArtistDump::new(path), &pool).await?;
+            let redis_client = redis::Client::open(configuration.redis_uri.expose_secret().as_str())?;
+            ResponseCache::new(configuration.cache, redis_client).await?.invalidate().await;
+            writeln!(std::io::stdout(), "{}", serde_json::to_string_pretty(&summary)?)?;
+        }
+        Command::CreateModerator(name) => {
+            // Read the password from stdin, so that it stays out of the
+            // shell history and the process list.
+            let mut password = String::new();
+            std::io::stdin().read_line(&mut password)?;
+            let password = Secret::new(password.trim_end_matches(['\r', '\n']).to_string());
+            let pool = get_connection_pool(&configuration.database);
+            let id = create_moderator(&name, password, &pool).await?;
+            writeln!(std::io::stdout(), "Created moderator {} ({})", name, id)?;
+        }
+        Command::Serve => {
+            let application = Application::build(configuration).await?;
+            application.run_until_stopped().await?;
+        }
+        Command::CheckConfig => unreachable!("The configuration is checked before tracing is set up"),
+    }
 
     Ok(())
 }
@@ -0,0 +1,84 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram_vec,
+    register_int_counter_vec,
+    register_int_gauge,
+    HistogramVec,
+    IntCounterVec,
+    IntGauge,
+};
+use sqlx::PgPool;
+
+pub static HTTP_REQUESTS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "http_requests_total",
+        "HTTP requests served, by route template and status",
+        &["method", "route", "status"]
+    )
+    .expect("Failed to register http_requests_total")
+});
+
+pub static HTTP_REQUEST_DURATION_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        "http_request_duration_seconds",
+        "Time taken to serve HTTP requests, by route template",
+        &["method", "route"]
+    )
+    .expect("Failed to register http_request_duration_seconds")
+});
+
+pub static DB_POOL_CONNECTIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("db_pool_connections", "Connections open in the Postgres pool")
+        .expect("Failed to register db_pool_connections")
+});
+
+pub static DB_POOL_IDLE_CONNECTIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("db_pool_idle_connections", "Open connections not in use by a request")
+        .expect("Failed to register db_pool_idle_connections")
+});
+
+pub static DB_POOL_MAX_CONNECTIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        "db_pool_max_connections",
+        "Connections the Postgres pool may open; requests wait for one once every connection is in use"
+    )
+    .expect("Failed to register db_pool_max_connections")
+});
+
+pub static REDIS_SESSION_ERRORS_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "redis_session_errors_total",
+        "Failures of the Redis session store, by operation",
+        &["operation"]
+    )
+    .expect("Failed to register redis_session_errors_total")
+});
+
+pub static ENTITIES_CREATED_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        "entities_created_total",
+        "Artists and concerts created, by entity type",
+        &["entity_type"]
+    )
+    .expect("Failed to register entities_created_total")
+});
+
+/// Copy the state of `pool` into the pool gauges; `db_pool_max_connections`
+/// is set once, when the pool is built.
+///
+/// sqlx only exposes the pool's current state, so it is read when the
+/// metrics are scraped.
+pub fn observe_pool(pool: &PgPool) {
+    DB_POOL_CONNECTIONS.set(i64::from(pool.size()));
+    DB_POOL_IDLE_CONNECTIONS.set(pool.num_idle() as i64);
+}
+
+/// Count `count` artists or concerts, by their `ENTITY_TYPE`, as created.
+///
+/// Called once the transaction creating them has committed, so that rolled
+/// back creations aren't counted.
+pub fn count_created(entity_type: &str, count: usize) {
+    ENTITIES_CREATED_TOTAL
+        .with_label_values(&[entity_type])
+        .inc_by(count as u64);
+}
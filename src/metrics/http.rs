@@ -0,0 +1,36 @@
+use crate::metrics::{HTTP_REQUESTS_TOTAL, HTTP_REQUEST_DURATION_SECONDS};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use std::time::Instant;
+
+/// Times a request from when it reaches the application until its response
+/// is ready.
+pub struct HttpTimer {
+    method: String,
+    route: String,
+    started: Instant,
+}
+
+impl HttpTimer {
+    /// Label requests with the route template, e.g. `/concerts/{id}`, so
+    /// that every concert does not get a series of its own.
+    pub fn start(req: &ServiceRequest) -> Self {
+        Self {
+            method: req.method().to_string(),
+            route: req.match_pattern().unwrap_or_else(|| "unmatched".to_string()),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn observe<B>(self, response: &Result<ServiceResponse<B>, actix_web::Error>) {
+        let status = match response {
+            Ok(response) => response.status(),
+            Err(e) => e.as_response_error().status_code(),
+        };
+        HTTP_REQUEST_DURATION_SECONDS
+            .with_label_values(&[&self.method, &self.route])
+            .observe(self.started.elapsed().as_secs_f64());
+        HTTP_REQUESTS_TOTAL
+            .with_label_values(&[&self.method, &self.route, status.as_str()])
+            .inc();
+    }
+}
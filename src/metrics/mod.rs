@@ -0,0 +1,7 @@
+mod collectors;
+mod http;
+mod session_store;
+
+pub use collectors::*;
+pub use http::*;
+pub use session_store::*;
@@ -0,0 +1,55 @@
+use crate::metrics::REDIS_SESSION_ERRORS_TOTAL;
+use actix_session::storage::{LoadError, SaveError, SessionKey, SessionStore, UpdateError};
+use actix_web::cookie::time::Duration;
+use std::collections::HashMap;
+
+/// A session store counting the failures of the one it wraps in
+/// `redis_session_errors_total`.
+#[derive(Clone)]
+pub struct CountingSessionStore<S>(pub S);
+
+impl<S> CountingSessionStore<S> {
+    fn count<T, E>(&self, operation: &str, result: Result<T, E>) -> Result<T, E> {
+        if result.is_err() {
+            REDIS_SESSION_ERRORS_TOTAL.with_label_values(&[operation]).inc();
+        }
+        result
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl<S: SessionStore> SessionStore for CountingSessionStore<S> {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<HashMap<String, String>>, LoadError> {
+        let result = self.0.load(session_key).await;
+        self.count("load", result)
+    }
+
+    async fn save(
+        &self,
+        session_state: HashMap<String, String>,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let result = self.0.save(session_state, ttl).await;
+        self.count("save", result)
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: HashMap<String, String>,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let result = self.0.update(session_key, session_state, ttl).await;
+        self.count("update", result)
+    }
+
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> Result<(), anyhow::Error> {
+        let result = self.0.update_ttl(session_key, ttl).await;
+        self.count("update_ttl", result)
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> Result<(), anyhow::Error> {
+        let result = self.0.delete(session_key).await;
+        self.count("delete", result)
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// The subset of a MusicBrainz artist entity mirrored in the `artists` table.
+///
+/// Dumps carry many more fields (type, area, life-span, aliases...) which are
+/// ignored.
+#[derive(serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct MusicBrainzArtist {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(rename = "sort-name")]
+    pub sort_name: String,
+    #[serde(default)]
+    pub disambiguation: String,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum DumpError {
+    #[error("Failed to read the dump")]
+    Io(#[from] std::io::Error),
+    #[error("Line {line} is not a valid artist entity")]
+    Malformed {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+/// A MusicBrainz JSON artist dump on the local filesystem.
+///
+/// The JSON dumps store one entity per line, so the file is read lazily and
+/// never held in memory as a whole.
+#[derive(Debug, Clone)]
+pub struct ArtistDump {
+    path: PathBuf,
+}
+
+impl ArtistDump {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Iterate over every artist in the dump, in file order.
+    pub fn artists(
+        &self,
+    ) -> Result<impl Iterator<Item = Result<MusicBrainzArtist, DumpError>>, DumpError> {
+        let reader = BufReader::new(File::open(&self.path)?);
+
+        Ok(reader
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .map(|(index, line)| parse_line(index + 1, &line?)))
+    }
+
+    /// Read the dump once, recording where each artist's line starts, so
+    /// that looking an artist up afterwards reads a single line.
+    ///
+    /// This reads the whole file: it blocks and should be run off the async
+    /// executor.
+    pub fn index(self) -> Result<IndexedArtistDump, DumpError> {
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        let mut lines = HashMap::new();
+        let mut line = String::new();
+        let mut offset = 0;
+        for number in 1.. {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+            if !line.trim().is_empty() {
+                let artist = parse_line(number, &line)?;
+                lines.insert(artist.id, (number, offset));
+            }
+            offset += read as u64;
+        }
+
+        Ok(IndexedArtistDump { dump: self, lines })
+    }
+}
+
+/// An `ArtistDump` with the number and byte offset of each artist's line,
+/// built once by `ArtistDump::index`.
+#[derive(Debug)]
+pub struct IndexedArtistDump {
+    dump: ArtistDump,
+    lines: HashMap<Uuid, (usize, u64)>,
+}
+
+impl IndexedArtistDump {
+    /// How many artists the dump holds
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Read the line of the artist with the given MBID.
+    ///
+    /// This seeks in the file: it blocks and should be run off the async
+    /// executor.
+    pub fn find(&self, mbid: Uuid) -> Result<Option<MusicBrainzArtist>, DumpError> {
+        let Some(&(number, offset)) = self.lines.get(&mbid) else {
+            return Ok(None);
+        };
+        let mut reader = BufReader::new(File::open(self.dump.path())?);
+        reader.seek(SeekFrom::Start(offset))?;
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let artist = parse_line(number, &line)?;
+
+        // The file may have been replaced since it was indexed.
+        Ok(Some(artist).filter(|artist| artist.id == mbid))
+    }
+}
+
+fn parse_line(line: usize, content: &str) -> Result<MusicBrainzArtist, DumpError> {
+    serde_json::from_str(content).map_err(|source| DumpError::Malformed { line, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_line, ArtistDump};
+    use claims::{assert_err, assert_none, assert_ok, assert_some};
+
+    #[test]
+    fn a_dump_line_is_parsed_ignoring_extra_fields() {
+        let line = r#"{"id":"cc197bad-dc9c-440d-a5b5-d52ba2e14234","name":"Coldplay","sort-name":"Coldplay","disambiguation":"","type":"Group","life-span":{"begin":"1996"}}"#;
+        let artist = assert_ok!(parse_line(1, line));
+        assert_eq!("Coldplay", artist.name);
+        assert_eq!("Coldplay", artist.sort_name);
+    }
+
+    #[test]
+    fn a_missing_disambiguation_defaults_to_empty() {
+        let line = r#"{"id":"cc197bad-dc9c-440d-a5b5-d52ba2e14234","name":"Coldplay","sort-name":"Coldplay"}"#;
+        let artist = assert_ok!(parse_line(1, line));
+        assert_eq!("", artist.disambiguation);
+    }
+
+    #[test]
+    fn a_line_without_an_mbid_is_rejected() {
+        let line = r#"{"name":"Coldplay","sort-name":"Coldplay"}"#;
+        assert_err!(parse_line(1, line));
+    }
+
+    #[test]
+    fn an_indexed_dump_finds_artists_by_mbid() {
+        let dump = assert_ok!(ArtistDump::new("tests/fixtures/musicbrainz/artist").index());
+        assert_eq!(3, dump.len());
+
+        let mbid = "83b9cbe7-9857-49e2-ab8e-b57b01038103".parse().unwrap();
+        let artist = assert_some!(assert_ok!(dump.find(mbid)));
+        assert_eq!("Pearl Jam", artist.name);
+        assert_none!(assert_ok!(dump.find(uuid::Uuid::new_v4())));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::domain::{Actor, Artist, ArtistName, Audited, DomainError, NewArtist, Upserted};
+use crate::metrics::count_created;
+use crate::musicbrainz::{ArtistDump, MusicBrainzArtist};
+use anyhow::Context;
+use sqlx::PgPool;
+
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+}
+
+impl TryFrom<MusicBrainzArtist> for NewArtist {
+    type Error = String;
+
+    fn try_from(value: MusicBrainzArtist) -> Result<Self, Self::Error> {
+        let name = ArtistName::parse(value.name)?;
+
+        Ok(Self {
+            name,
+            sort_name: value.sort_name,
+            disambiguation: value.disambiguation,
+            mbid: Some(value.id),
+        })
+    }
+}
+
+/// Create or update an `Artist` for every entity in a MusicBrainz dump.
+///
+/// Each artist is written in its own transaction: entities whose name fails
+/// validation or clashes with a different artist already stored are logged
+/// and skipped rather than aborting the whole import. Any other failure to
+/// write an artist stops the import, with the artists before it kept.
+#[tracing::instrument(
+    name = "Importing artists from a MusicBrainz dump",
+    skip(dump, pool),
+    fields(path = %dump.path().display())
+)]
+pub async fn import_artists(
+    dump: &ArtistDump,
+    pool: &PgPool,
+) -> Result<ImportSummary, anyhow::Error> {
+    let mut summary = ImportSummary::default();
+    let actor = Actor::new("musicbrainz-import");
+
+    for entity in dump.artists()? {
+        let entity = entity?;
+        let mbid = entity.id;
+
+        let new_artist = match NewArtist::try_from(entity) {
+            Ok(new_artist) => new_artist,
+            Err(e) => {
+                tracing::warn!(%mbid, error = %e, "Skipping MusicBrainz artist");
+                summary.skipped += 1;
+                continue;
+            }
+        };
+
+        let mut transaction = pool
+            .begin()
+            .await
+            .context("Failed to acquire a Postgres connection from the pool")?;
+
+        let result = Artist::upsert_by_mbid(mbid, &new_artist, &actor, &mut transaction)
+            .await
+            .map_err(DomainError::from);
+        match result {
+            Ok((_, upserted)) => {
+                transaction
+                    .commit()
+                    .await
+                    .context("Failed to commit the transaction")?;
+                match upserted {
+                    Upserted::Created => {
+                        count_created(Artist::ENTITY_TYPE, 1);
+                        summary.created += 1;
+                    }
+                    Upserted::Updated => summary.updated += 1,
+                    Upserted::Unchanged => summary.unchanged += 1,
+                }
+            }
+            Err(e @ (DomainError::Conflict(_) | DomainError::InvalidReference(_))) => {
+                tracing::warn!(%mbid, error = %e, "Skipping MusicBrainz artist");
+                summary.skipped += 1;
+            }
+            Err(e) => {
+                return Err(anyhow::Error::new(e))
+                    .with_context(|| format!("Failed to import MusicBrainz artist {}", mbid));
+            }
+        }
+    }
+
+    Ok(summary)
+}
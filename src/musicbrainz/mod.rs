@@ -0,0 +1,5 @@
+mod dump;
+mod import;
+
+pub use dump::*;
+pub use import::*;
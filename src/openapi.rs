@@ -0,0 +1,165 @@
+use crate::domain::{Artist, AuditEntry, Concert, FieldError, Submission, SubmissionKind, SubmissionStatus, Timeframe};
+use crate::routes::{
+    self,
+    CreateArtistRequest,
+    ConcertPage,
+    BodyData,
+    PatchArtistRequest,
+    CreateConcertRequest,
+    UpdateConcertRequest,
+    PatchConcertRequest,
+    LikelyDuplicate,
+    ImportMode,
+    ImportReport,
+    RowReport,
+    RowStatus,
+    SetlistImportReport,
+    SetlistReport,
+    SetlistStatus,
+    ProblemCode,
+    ProblemDetails,
+    LoginForm,
+    ArtistForm,
+    Readiness,
+    DependencyCheck,
+    CheckStatus,
+    ConcertForm,
+};
+use crate::authentication::CsrfForm;
+use crate::configuration::RateLimitSettings;
+use utoipa::openapi::{Content, Ref, ResponseBuilder};
+use utoipa::OpenApi;
+
+/// The OpenAPI 3 description of every route registered in
+/// `startup::routes`, generated from the `#[utoipa::path]` attribute on each
+/// handler. Served through `api_doc`, which adds the rate limits.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "All Bands",
+        description = "Artists, the concerts they played and the songs they played at them",
+    ),
+    paths(
+        routes::health_check,
+        routes::readiness_check,
+        routes::metrics,
+        routes::openapi_json,
+        routes::api_docs,
+        routes::swagger_ui_bundle,
+        routes::swagger_ui_css,
+        routes::artists_dashboard,
+        routes::create_artist,
+        routes::artist_dashboard,
+        routes::update_artist,
+        routes::patch_artist,
+        routes::get_artist_concerts,
+        routes::get_artist_history,
+        routes::revert_artist,
+        routes::create_concert,
+        routes::get_concerts,
+        routes::get_concert_duplicates,
+        routes::get_concert,
+        routes::update_concert,
+        routes::patch_concert,
+        routes::get_concert_history,
+        routes::revert_concert,
+        routes::submit_concert,
+        routes::submit_artist_update,
+        routes::get_submission,
+        routes::login_form,
+        routes::login,
+        routes::log_out,
+        routes::moderation_queue,
+        routes::approve_submission,
+        routes::reject_submission,
+        routes::admin_artists,
+        routes::admin_create_artist,
+        routes::new_artist_form,
+        routes::edit_artist_form,
+        routes::admin_update_artist,
+        routes::admin_concerts,
+        routes::admin_create_concert,
+        routes::new_concert_form,
+        routes::edit_concert_form,
+        routes::admin_update_concert,
+        routes::import_concerts,
+        routes::import_setlistfm,
+        routes::export_catalogue,
+        routes::graphql,
+        routes::graphiql,
+    ),
+    components(schemas(
+        Artist,
+        Concert,
+        ConcertPage,
+        Timeframe,
+        AuditEntry,
+        CreateArtistRequest,
+        BodyData,
+        PatchArtistRequest,
+        CreateConcertRequest,
+        UpdateConcertRequest,
+        PatchConcertRequest,
+        LikelyDuplicate,
+        ImportMode,
+        ImportReport,
+        RowReport,
+        RowStatus,
+        SetlistImportReport,
+        SetlistReport,
+        SetlistStatus,
+        ProblemCode,
+        ProblemDetails,
+        FieldError,
+        Submission,
+        SubmissionKind,
+        SubmissionStatus,
+        LoginForm,
+        CsrfForm,
+        ArtistForm,
+        ConcertForm,
+        Readiness,
+        DependencyCheck,
+        CheckStatus,
+    )),
+    tags(
+        (name = "health", description = "`/health` for liveness, `/health/ready` for whether Postgres, Redis and the migrations are in place"),
+        (name = "metrics"),
+        (name = "docs"),
+        (name = "artists"),
+        (name = "concerts"),
+        (name = "submissions", description = "Changes proposed by the public, applied once a moderator approves them"),
+        (name = "moderation"),
+        (name = "admin", description = "HTML forms for moderators to create and edit artists and concerts"),
+        (name = "import"),
+        (name = "export"),
+        (name = "graphql"),
+    )
+)]
+pub struct ApiDoc;
+
+/// The OpenAPI document as served: `ApiDoc` with `429 Too Many Requests`
+/// added to every operation that `rate_limit` limits, rather than
+/// repeating it in each `#[utoipa::path]`.
+pub fn api_doc(rate_limit: &RateLimitSettings) -> utoipa::openapi::OpenApi {
+    let mut openapi = ApiDoc::openapi();
+    if !rate_limit.enabled {
+        return openapi;
+    }
+    let response = ResponseBuilder::new()
+        .description("The client sent more requests than the route allows; `Retry-After` says when to try again")
+        .content("application/problem+json", Content::new(Ref::from_schema_name("ProblemDetails")))
+        .build();
+    for (path, item) in openapi.paths.paths.iter_mut() {
+        if rate_limit.exempt_routes.contains(path) {
+            continue;
+        }
+        for operation in item.operations.values_mut() {
+            operation
+                .responses
+                .responses
+                .insert("429".to_string(), response.clone().into());
+        }
+    }
+    openapi
+}
@@ -0,0 +1,169 @@
+use crate::configuration::RateLimitSettings;
+use redis::aio::ConnectionManager;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The outcome of counting one request against its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Until the current window ends
+    pub reset: Duration,
+    /// Until a request would be let through again
+    pub retry_after: Duration,
+}
+
+/// Counts requests per client in Redis, so that the limits hold across every
+/// instance of the application.
+///
+/// Requests are counted in fixed windows, and the count over the sliding
+/// window ending now is estimated from the current window and the part of
+/// the previous one that still overlaps it. This needs two counters per
+/// client rather than a log of their requests. Rejected requests are counted
+/// too, so that a client retrying in a loop stays limited.
+pub struct RateLimiter {
+    settings: RateLimitSettings,
+    connection: ConnectionManager,
+}
+
+impl RateLimiter {
+    pub async fn new(settings: RateLimitSettings, client: redis::Client) -> Result<Self, redis::RedisError> {
+        Ok(Self {
+            settings,
+            connection: ConnectionManager::new(client).await?,
+        })
+    }
+
+    pub fn settings(&self) -> &RateLimitSettings {
+        &self.settings
+    }
+
+    /// Count a request like `check`, unless rate limiting is off or the route
+    /// is exempt. `None` when the request isn't limited, including when Redis
+    /// cannot be reached or doesn't answer in time, as requests are let
+    /// through rather than failed then.
+    pub async fn check_or_allow(&self, method: &str, path: &str, client: &str) -> Option<Decision> {
+        if !self.settings.enabled || self.settings.exempt_routes.iter().any(|route| route == path) {
+            return None;
+        }
+        match tokio::time::timeout(self.settings.timeout(), self.check(method, path, client)).await {
+            Ok(Ok(decision)) => Some(decision),
+            Ok(Err(e)) => {
+                tracing::warn!(error.cause_chain = ?e, "Failed to check the rate limit, letting the request through");
+                None
+            }
+            Err(_) => {
+                tracing::warn!("Timed out checking the rate limit, letting the request through");
+                None
+            }
+        }
+    }
+
+    /// Count a request from `client` to the route with `method` and template
+    /// `path`. Routes without a limit of their own share one window.
+    pub async fn check(&self, method: &str, path: &str, client: &str) -> Result<Decision, redis::RedisError> {
+        let (bucket, limit) = match self.settings.route_limit(method, path) {
+            Some(route) => (format!("{} {}", route.method.to_uppercase(), route.path), route.limit),
+            None => ("default".to_string(), self.settings.default_limit),
+        };
+        let window = self.settings.window().as_millis().max(1) as u64;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let index = now / window;
+        let key = |index: u64| format!("{}:{}:{}:{}", self.settings.key_prefix, bucket, client, index);
+
+        let (current, previous): (u64, Option<u64>) = redis::pipe()
+            .atomic()
+            .incr(key(index), 1)
+            // Kept until the next window no longer overlaps this one.
+            .pexpire(key(index), (2 * window) as usize)
+            .ignore()
+            .get(key(index - 1))
+            .query_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(decide(
+            limit,
+            previous.unwrap_or(0),
+            current,
+            Duration::from_millis(now % window),
+            Duration::from_millis(window),
+        ))
+    }
+}
+
+/// Whether the `current` request of this window, with `previous` requests in
+/// the window before and `elapsed` into this one, is within `limit`.
+fn decide(limit: u32, previous: u64, current: u64, elapsed: Duration, window: Duration) -> Decision {
+    let window_ms = window.as_millis() as f64;
+    let elapsed_ms = elapsed.as_millis() as f64;
+    let limit_f = f64::from(limit);
+    let overlap = 1.0 - elapsed_ms / window_ms;
+    let estimate = previous as f64 * overlap + current as f64;
+    let allowed = estimate <= limit_f;
+
+    // The previous window slides out linearly: find when the estimate for
+    // one more request falls back within the limit.
+    let retry_after_ms = if allowed {
+        0.0
+    } else if (current as f64) < limit_f {
+        // `previous * (1 - (elapsed + d) / window) + current + 1 <= limit`
+        let room = limit_f - current as f64 - 1.0;
+        (window_ms * (1.0 - room / previous as f64) - elapsed_ms).max(0.0)
+    } else {
+        // This window alone is over the limit: wait for the next one, and
+        // for enough of this one to slide out of it.
+        let room = (limit_f - 1.0).max(0.0);
+        (window_ms - elapsed_ms) + window_ms * (1.0 - room / current as f64)
+    };
+
+    Decision {
+        allowed,
+        limit,
+        remaining: (limit_f - estimate.ceil()).max(0.0) as u32,
+        reset: window.saturating_sub(elapsed),
+        retry_after: Duration::from_millis(retry_after_ms.round() as u64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decide;
+    use std::time::Duration;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn requests_within_the_limit_are_allowed() {
+        let decision = decide(10, 0, 1, Duration::from_secs(15), WINDOW);
+
+        assert!(decision.allowed);
+        assert_eq!(9, decision.remaining);
+        assert_eq!(Duration::from_secs(45), decision.reset);
+    }
+
+    #[test]
+    fn the_previous_window_counts_for_the_part_still_overlapping() {
+        // Halfway through, 5 of the previous 10 requests are still counted.
+        let decision = decide(10, 10, 6, Duration::from_secs(30), WINDOW);
+
+        assert!(!decision.allowed);
+        assert_eq!(0, decision.remaining);
+        // One more request fits once only 3 of the previous requests are
+        // still counted, 12 seconds later.
+        assert_eq!(Duration::from_secs(12), decision.retry_after);
+    }
+
+    #[test]
+    fn a_full_window_waits_for_the_next_one() {
+        let decision = decide(2, 0, 3, Duration::from_secs(45), WINDOW);
+
+        assert!(!decision.allowed);
+        // 15 seconds to the next window, then 40 more for the 3 requests of
+        // this one to count as at most 1.
+        assert_eq!(Duration::from_secs(55), decision.retry_after);
+    }
+}
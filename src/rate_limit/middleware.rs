@@ -0,0 +1,153 @@
+use crate::rate_limit::{Decision, RateLimiter};
+use crate::routes::{ProblemCode, ProblemDetails};
+use crate::session_state::TypedSession;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER};
+use actix_web::http::StatusCode;
+use actix_web::web::Data;
+use actix_web::{FromRequest, HttpRequest, HttpResponse};
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Rejects clients that sent more requests than their route allows with
+/// `429 Too Many Requests`, and tells every client where it stands in
+/// `RateLimit-*` headers.
+///
+/// Clients are told apart by the moderator they are logged in as, or else
+/// by their IP address; `X-Client-Id` is not used, as any client can pick a
+/// new one. Must be wrapped by `SessionMiddleware`. When Redis cannot be
+/// reached, or doesn't answer within `rate_limit.timeout_milliseconds`,
+/// requests are let through rather than failed.
+pub struct RateLimit(Arc<RateLimiter>);
+
+impl RateLimit {
+    pub fn new(limiter: Arc<RateLimiter>) -> Self {
+        Self(limiter)
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            limiter: self.0.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let path = req.match_pattern().unwrap_or_default();
+            let client = client_key(req.request());
+            let decision = match limiter.check_or_allow(req.method().as_str(), &path, &client).await {
+                Some(decision) => decision,
+                None => return Ok(service.call(req).await?.map_into_left_body()),
+            };
+
+            if !decision.allowed {
+                tracing::info!(client = %client, route = %path, "Rate limit exceeded");
+                let problem = ProblemDetails::new(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    ProblemCode::RateLimited,
+                    format!(
+                        "Too many requests, retry in {} seconds",
+                        seconds(decision.retry_after)
+                    ),
+                );
+                let mut response: HttpResponse = problem.into();
+                insert_headers(response.headers_mut(), &decision);
+                response
+                    .headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from(seconds(decision.retry_after)));
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+
+            let mut response = service.call(req).await?;
+            insert_headers(response.headers_mut(), &decision);
+            Ok(response.map_into_left_body())
+        })
+    }
+}
+
+/// Whether the client's address is taken from the `Forwarded` or
+/// `X-Forwarded-For` header set by a reverse proxy, rather than from the
+/// connection. Registered as application data from
+/// `application.trust_proxy_headers`.
+#[derive(Clone, Copy, Debug)]
+pub struct TrustProxyHeaders(pub bool);
+
+/// Who sent the request: the moderator it is logged in as, or else its IP
+/// address. Unlike `X-Client-Id`, a client can't pick a new one at will, as
+/// long as proxy headers are only trusted behind a proxy.
+pub fn client_key(req: &HttpRequest) -> String {
+    let moderator_id = TypedSession::extract(req)
+        .into_inner()
+        .ok()
+        .and_then(|session| session.get_moderator_id().ok().flatten());
+    if let Some(moderator_id) = moderator_id {
+        return format!("moderator:{}", moderator_id);
+    }
+    let trust_proxy_headers = req
+        .app_data::<Data<TrustProxyHeaders>>()
+        .is_some_and(|trust| trust.0);
+    let address = if trust_proxy_headers {
+        req.connection_info().realip_remote_addr().map(str::to_owned)
+    } else {
+        req.peer_addr().map(|address| address.ip().to_string())
+    };
+    match address {
+        Some(address) => format!("ip:{}", address),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Rounded up, so that clients waiting that long are not turned away once
+/// more.
+pub(crate) fn seconds(duration: Duration) -> u64 {
+    (duration.as_millis() as u64).div_ceil(1000)
+}
+
+fn insert_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert(HeaderName::from_static("ratelimit-limit"), HeaderValue::from(decision.limit));
+    headers.insert(
+        HeaderName::from_static("ratelimit-remaining"),
+        HeaderValue::from(decision.remaining),
+    );
+    headers.insert(
+        HeaderName::from_static("ratelimit-reset"),
+        HeaderValue::from(seconds(decision.reset)),
+    );
+}
@@ -0,0 +1,5 @@
+mod limiter;
+mod middleware;
+
+pub use limiter::*;
+pub use middleware::*;
@@ -0,0 +1,17 @@
+use crate::domain::Actor;
+use crate::idempotency::ClientId;
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use std::convert::Infallible;
+use std::future::{ready, Ready};
+
+/// Changes made over HTTP are attributed to the `X-Client-Id` of the
+/// request, or to `anonymous` when it has none.
+impl FromRequest for Actor {
+    type Error = Infallible;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(Actor::new(ClientId::from_request(req).as_ref())))
+    }
+}
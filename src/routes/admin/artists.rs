@@ -0,0 +1,299 @@
+use crate::authentication::{reject_forged_requests, Moderator};
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Artist, Audited, NewArtist, UpdateArtist, ValidationErrors};
+use crate::metrics::count_created;
+use crate::routes::{form_error, required_version, BodyData, CreateArtistRequest, FormPage};
+use crate::session_state::TypedSession;
+use crate::utils::{e500, render, see_other};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::{FlashMessage, IncomingFlashMessages};
+use anyhow::Context;
+use askama::Template;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// The fields of the artist form, as posted and as shown again when they
+/// fail validation.
+#[derive(serde::Deserialize, Default, utoipa::ToSchema)]
+pub struct ArtistForm {
+    #[serde(default)]
+    pub csrf_token: String,
+    /// The version the form was rendered from, absent for a new artist
+    pub version: Option<i32>,
+    pub name: String,
+    pub sort_name: String,
+    pub disambiguation: String,
+}
+
+impl From<Artist> for ArtistForm {
+    fn from(artist: Artist) -> Self {
+        Self {
+            csrf_token: String::new(),
+            version: Some(artist.version),
+            name: artist.name,
+            sort_name: artist.sort_name,
+            disambiguation: artist.disambiguation,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/artists.html")]
+struct ArtistsPage {
+    username: String,
+    csrf_token: String,
+    flash_messages: IncomingFlashMessages,
+    artists: Vec<Artist>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/artist_form.html")]
+struct ArtistFormPage {
+    username: String,
+    csrf_token: String,
+    flash_messages: IncomingFlashMessages,
+    heading: String,
+    action: String,
+    form: ArtistForm,
+    errors: ValidationErrors,
+    form_error: Option<String>,
+}
+
+impl FormPage for ArtistFormPage {
+    const ENTITY: &'static str = "artist";
+
+    fn set_form_error(&mut self, message: String) {
+        self.form_error = Some(message);
+    }
+}
+
+impl ArtistFormPage {
+    fn new(moderator: Moderator, csrf_token: String, flash_messages: IncomingFlashMessages, id: Option<Uuid>, form: ArtistForm) -> Self {
+        let (heading, action) = match id {
+            Some(id) => (format!("Edit {}", form.name), format!("/admin/artists/{}", id)),
+            None => ("New artist".to_string(), "/admin/artists".to_string()),
+        };
+        Self {
+            username: moderator.username,
+            csrf_token,
+            flash_messages,
+            heading,
+            action,
+            form,
+            errors: ValidationErrors::default(),
+            form_error: None,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/artists",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every artist, linking to a form to edit them", content_type = "text/html"),
+        (status = 303, description = "Not logged in, redirecting to `/login`"),
+    )
+)]
+#[tracing::instrument(name = "Admin list of artists", skip(session, flash_messages, pool))]
+pub async fn admin_artists(
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let artists = Artist::find_all(&pool).await.map_err(e500)?;
+
+    render(StatusCode::OK, &ArtistsPage {
+        username: moderator.username,
+        csrf_token: session.csrf_token().map_err(e500)?,
+        flash_messages,
+        artists,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/artists/new",
+    tag = "admin",
+    responses(
+        (status = 200, description = "A form to create an artist", content_type = "text/html"),
+        (status = 303, description = "Not logged in, redirecting to `/login`"),
+    )
+)]
+#[tracing::instrument(name = "Admin new artist form", skip(session, flash_messages))]
+pub async fn new_artist_form(
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+) -> Result<HttpResponse, actix_web::Error> {
+    let csrf_token = session.csrf_token().map_err(e500)?;
+    render(
+        StatusCode::OK,
+        &ArtistFormPage::new(moderator, csrf_token, flash_messages, None, ArtistForm::default()),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/artists",
+    tag = "admin",
+    request_body(content = ArtistForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "The artist was created, redirecting to `/admin/artists`"),
+        (status = 400, description = "The form is shown again with what failed validation", content_type = "text/html"),
+        (status = 403, description = "The form did not carry the session's CSRF token"),
+        (status = 409, description = "The form is shown again, as another artist has this name", content_type = "text/html"),
+    )
+)]
+#[tracing::instrument(name = "Admin create artist", skip(session, flash_messages, form, pool, cache))]
+pub async fn admin_create_artist(
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    form: web::Form<ArtistForm>,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    reject_forged_requests(&session, &form.csrf_token)?;
+    let csrf_token = session.csrf_token().map_err(e500)?;
+
+    let request = CreateArtistRequest {
+        name: form.name.clone(),
+        sort_name: Some(form.sort_name.clone()),
+        disambiguation: Some(form.disambiguation.clone()),
+        mbid: None,
+    };
+    let actor = Actor::new(moderator.username.clone());
+    let mut page = ArtistFormPage::new(moderator, csrf_token, flash_messages, None, form);
+
+    let new_artist = match NewArtist::try_from(request) {
+        Ok(new_artist) => new_artist,
+        Err(errors) => {
+            page.errors = errors;
+            return render(StatusCode::BAD_REQUEST, &page);
+        }
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")
+        .map_err(e500)?;
+    match Artist::insert(&new_artist, &actor, &mut transaction).await {
+        Ok(_) => {}
+        Err(e) => return form_error(page, e),
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction")
+        .map_err(e500)?;
+    count_created(Artist::ENTITY_TYPE, 1);
+    cache.invalidate().await;
+
+    FlashMessage::info(format!("Created {}.", new_artist.name.as_ref())).send();
+    Ok(see_other("/admin/artists"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/artists/{id}/edit",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+    ),
+    responses(
+        (status = 200, description = "A form to edit the artist", content_type = "text/html"),
+        (status = 303, description = "Not logged in, redirecting to `/login`"),
+        (status = 404, description = "Artist not found"),
+    )
+)]
+#[tracing::instrument(name = "Admin edit artist form", skip(session, flash_messages, pool))]
+pub async fn edit_artist_form(
+    id: web::Path<Uuid>,
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let artist = Artist::find_by_id(*id, &pool)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Artist not found"))?;
+    let csrf_token = session.csrf_token().map_err(e500)?;
+
+    render(
+        StatusCode::OK,
+        &ArtistFormPage::new(moderator, csrf_token, flash_messages, Some(*id), artist.into()),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/artists/{id}",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+    ),
+    request_body(content = ArtistForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "The artist was updated, redirecting to `/admin/artists`"),
+        (status = 400, description = "The form is shown again with what failed validation, or did not carry the version it was rendered from", content_type = "text/html"),
+        (status = 403, description = "The form did not carry the session's CSRF token"),
+        (status = 409, description = "The form is shown again, as the artist changed since it was rendered", content_type = "text/html"),
+    )
+)]
+#[tracing::instrument(name = "Admin update artist", skip(session, flash_messages, form, pool, cache))]
+pub async fn admin_update_artist(
+    id: web::Path<Uuid>,
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    form: web::Form<ArtistForm>,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    reject_forged_requests(&session, &form.csrf_token)?;
+    let csrf_token = session.csrf_token().map_err(e500)?;
+
+    let request = BodyData {
+        id: *id,
+        name: form.name.clone(),
+        sort_name: form.sort_name.clone(),
+        disambiguation: form.disambiguation.clone(),
+    };
+    let expected_versions = [required_version(form.version)?];
+    let actor = Actor::new(moderator.username.clone());
+    let mut page = ArtistFormPage::new(moderator, csrf_token, flash_messages, Some(*id), form);
+
+    let update = match UpdateArtist::try_from(request) {
+        Ok(update) => update,
+        Err(errors) => {
+            page.errors = errors;
+            return render(StatusCode::BAD_REQUEST, &page);
+        }
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")
+        .map_err(e500)?;
+    let artist = match Artist::update(&update, Some(&expected_versions), &actor, &mut transaction).await {
+        Ok(artist) => artist,
+        Err(e) => return form_error(page, e),
+    };
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction")
+        .map_err(e500)?;
+    cache.invalidate().await;
+
+    FlashMessage::info(format!("Updated {}.", artist.name)).send();
+    Ok(see_other("/admin/artists"))
+}
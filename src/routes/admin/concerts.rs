@@ -0,0 +1,383 @@
+use crate::authentication::{reject_forged_requests, Moderator};
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Artist, Audited, Concert, NewConcert, UpdateConcert, ValidationErrors};
+use crate::metrics::count_created;
+use crate::routes::{form_error, required_version, CreateConcertRequest, FormPage, UpdateConcertRequest};
+use crate::session_state::TypedSession;
+use crate::utils::{e500, render, see_other};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::{FlashMessage, IncomingFlashMessages};
+use anyhow::Context;
+use askama::Template;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The fields of the concert form, as posted and as shown again when they
+/// fail validation.
+#[derive(serde::Deserialize, Default, utoipa::ToSchema)]
+pub struct ConcertForm {
+    #[serde(default)]
+    pub csrf_token: String,
+    /// The version the form was rendered from, absent for a new concert
+    pub version: Option<i32>,
+    pub artist_id: Option<Uuid>,
+    pub venue: String,
+    pub city: String,
+    #[serde(default)]
+    pub state: String,
+    pub country: String,
+    pub date: String,
+    /// Save the concert even if it looks like a duplicate of another
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl From<Concert> for ConcertForm {
+    fn from(concert: Concert) -> Self {
+        Self {
+            csrf_token: String::new(),
+            version: Some(concert.version),
+            artist_id: Some(concert.artist_id),
+            venue: concert.venue,
+            city: concert.city,
+            state: concert.state.unwrap_or_default(),
+            country: concert.country,
+            date: concert.date.to_string(),
+            force: false,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "admin/concerts.html")]
+struct ConcertsPage {
+    username: String,
+    csrf_token: String,
+    flash_messages: IncomingFlashMessages,
+    /// Each concert with the name of its artist
+    concerts: Vec<(Concert, String)>,
+}
+
+#[derive(Template)]
+#[template(path = "admin/concert_form.html")]
+struct ConcertFormPage {
+    username: String,
+    csrf_token: String,
+    flash_messages: IncomingFlashMessages,
+    heading: String,
+    action: String,
+    artists: Vec<Artist>,
+    form: ConcertForm,
+    errors: ValidationErrors,
+    form_error: Option<String>,
+}
+
+impl FormPage for ConcertFormPage {
+    const ENTITY: &'static str = "concert";
+
+    fn set_form_error(&mut self, message: String) {
+        self.form_error = Some(message);
+    }
+}
+
+impl ConcertFormPage {
+    fn new(
+        moderator: Moderator,
+        csrf_token: String,
+        flash_messages: IncomingFlashMessages,
+        artists: Vec<Artist>,
+        id: Option<Uuid>,
+        form: ConcertForm,
+    ) -> Self {
+        let (heading, action) = match id {
+            Some(id) => ("Edit concert".to_string(), format!("/admin/concerts/{}", id)),
+            None => ("New concert".to_string(), "/admin/concerts".to_string()),
+        };
+        Self {
+            username: moderator.username,
+            csrf_token,
+            flash_messages,
+            heading,
+            action,
+            artists,
+            form,
+            errors: ValidationErrors::default(),
+            form_error: None,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/concerts",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every concert, linking to a form to edit them", content_type = "text/html"),
+        (status = 303, description = "Not logged in, redirecting to `/login`"),
+    )
+)]
+#[tracing::instrument(name = "Admin list of concerts", skip(session, flash_messages, pool))]
+pub async fn admin_concerts(
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let artist_names = Artist::find_all(&pool)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|artist| (artist.id, artist.name))
+        .collect::<HashMap<_, _>>();
+    let concerts = Concert::find_all(&pool)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|concert| {
+            let artist_name = artist_names.get(&concert.artist_id).cloned().unwrap_or_default();
+            (concert, artist_name)
+        })
+        .collect();
+
+    render(StatusCode::OK, &ConcertsPage {
+        username: moderator.username,
+        csrf_token: session.csrf_token().map_err(e500)?,
+        flash_messages,
+        concerts,
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/concerts/new",
+    tag = "admin",
+    responses(
+        (status = 200, description = "A form to create a concert", content_type = "text/html"),
+        (status = 303, description = "Not logged in, redirecting to `/login`"),
+    )
+)]
+#[tracing::instrument(name = "Admin new concert form", skip(session, flash_messages, pool))]
+pub async fn new_concert_form(
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let artists = Artist::find_all(&pool).await.map_err(e500)?;
+    let csrf_token = session.csrf_token().map_err(e500)?;
+
+    render(
+        StatusCode::OK,
+        &ConcertFormPage::new(moderator, csrf_token, flash_messages, artists, None, ConcertForm::default()),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/concerts",
+    tag = "admin",
+    request_body(content = ConcertForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "The concert was created, redirecting to `/admin/concerts`"),
+        (status = 400, description = "The form is shown again with what failed validation", content_type = "text/html"),
+        (status = 403, description = "The form did not carry the session's CSRF token"),
+        (status = 409, description = "The form is shown again, as the concert looks like a duplicate of another", content_type = "text/html"),
+    )
+)]
+#[tracing::instrument(name = "Admin create concert", skip(session, flash_messages, form, pool, cache))]
+pub async fn admin_create_concert(
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    form: web::Form<ConcertForm>,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    reject_forged_requests(&session, &form.csrf_token)?;
+    let csrf_token = session.csrf_token().map_err(e500)?;
+    let artists = Artist::find_all(&pool).await.map_err(e500)?;
+
+    let request = CreateConcertRequest {
+        artist_id: form.artist_id.unwrap_or_default(),
+        venue: form.venue.clone(),
+        city: form.city.clone(),
+        state: form.state.clone(),
+        country: form.country.clone(),
+        date: form.date.clone(),
+    };
+    let artist_chosen = form.artist_id.is_some();
+    let force = form.force;
+    let actor = Actor::new(moderator.username.clone());
+    let mut page = ConcertFormPage::new(moderator, csrf_token, flash_messages, artists, None, form);
+
+    let new_concert = match (NewConcert::try_from(request), artist_chosen) {
+        (Ok(new_concert), true) => new_concert,
+        (result, _) => {
+            page.errors = result.err().unwrap_or_default();
+            if !artist_chosen {
+                page.errors.push("artist_id", "An artist is required");
+            }
+            return render(StatusCode::BAD_REQUEST, &page);
+        }
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")
+        .map_err(e500)?;
+    if !force {
+        let duplicate = Concert::find_likely_duplicate(&new_concert, &mut transaction)
+            .await
+            .context("Failed to look for a duplicate concert")
+            .map_err(e500)?;
+        if let Some(duplicate) = duplicate {
+            return duplicate_error(page, duplicate);
+        }
+    }
+    if let Err(e) = Concert::insert(&new_concert, &actor, &mut transaction).await {
+        return form_error(page, e);
+    }
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction")
+        .map_err(e500)?;
+    count_created(Concert::ENTITY_TYPE, 1);
+    cache.invalidate().await;
+
+    FlashMessage::info(format!("Created the concert at {}.", new_concert.venue.as_ref())).send();
+    Ok(see_other("/admin/concerts"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/concerts/{id}/edit",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "The concert id"),
+    ),
+    responses(
+        (status = 200, description = "A form to edit the concert", content_type = "text/html"),
+        (status = 303, description = "Not logged in, redirecting to `/login`"),
+        (status = 404, description = "Concert not found"),
+    )
+)]
+#[tracing::instrument(name = "Admin edit concert form", skip(session, flash_messages, pool))]
+pub async fn edit_concert_form(
+    id: web::Path<Uuid>,
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let concert = Concert::find_by_id(*id, &pool)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Concert not found"))?;
+    let artists = Artist::find_all(&pool).await.map_err(e500)?;
+    let csrf_token = session.csrf_token().map_err(e500)?;
+
+    render(
+        StatusCode::OK,
+        &ConcertFormPage::new(moderator, csrf_token, flash_messages, artists, Some(*id), concert.into()),
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/concerts/{id}",
+    tag = "admin",
+    params(
+        ("id" = Uuid, Path, description = "The concert id"),
+    ),
+    request_body(content = ConcertForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "The concert was updated, redirecting to `/admin/concerts`"),
+        (status = 400, description = "The form is shown again with what failed validation, or did not carry the version it was rendered from", content_type = "text/html"),
+        (status = 403, description = "The form did not carry the session's CSRF token"),
+        (status = 409, description = "The form is shown again, as the concert changed since it was rendered or looks like a duplicate of another", content_type = "text/html"),
+    )
+)]
+#[tracing::instrument(name = "Admin update concert", skip(session, flash_messages, form, pool, cache))]
+pub async fn admin_update_concert(
+    id: web::Path<Uuid>,
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    form: web::Form<ConcertForm>,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    reject_forged_requests(&session, &form.csrf_token)?;
+    let csrf_token = session.csrf_token().map_err(e500)?;
+    let artists = Artist::find_all(&pool).await.map_err(e500)?;
+
+    let request = UpdateConcertRequest {
+        id: *id,
+        artist_id: form.artist_id.unwrap_or_default(),
+        venue: form.venue.clone(),
+        city: form.city.clone(),
+        state: form.state.clone(),
+        date: form.date.clone(),
+        country: form.country.clone(),
+    };
+    let artist_chosen = form.artist_id.is_some();
+    let expected_versions = [required_version(form.version)?];
+    let force = form.force;
+    let actor = Actor::new(moderator.username.clone());
+    let mut page = ConcertFormPage::new(moderator, csrf_token, flash_messages, artists, Some(*id), form);
+
+    let update = match (UpdateConcert::try_from(request), artist_chosen) {
+        (Ok(update), true) => update,
+        (result, _) => {
+            page.errors = result.err().unwrap_or_default();
+            if !artist_chosen {
+                page.errors.push("artist_id", "An artist is required");
+            }
+            return render(StatusCode::BAD_REQUEST, &page);
+        }
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")
+        .map_err(e500)?;
+    if !force {
+        let duplicate = Concert::find_likely_duplicate_of_update(&update, &mut transaction)
+            .await
+            .context("Failed to look for a duplicate concert")
+            .map_err(e500)?;
+        if let Some(duplicate) = duplicate {
+            return duplicate_error(page, duplicate);
+        }
+    }
+    let concert = match Concert::update(&update, Some(&expected_versions), &actor, &mut transaction).await {
+        Ok(concert) => concert,
+        Err(e) => return form_error(page, e),
+    };
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction")
+        .map_err(e500)?;
+    cache.invalidate().await;
+
+    FlashMessage::info(format!("Updated the concert at {}.", concert.venue)).send();
+    Ok(see_other("/admin/concerts"))
+}
+
+/// Show the form again, asking the moderator to confirm that the concert
+/// isn't `duplicate` before saving it.
+fn duplicate_error(mut page: ConcertFormPage, duplicate: Concert) -> Result<HttpResponse, actix_web::Error> {
+    page.form_error = Some(format!(
+        "This concert looks like a duplicate of the one at {} on {}. Tick \"Save anyway\" if it is a different show.",
+        duplicate.venue, duplicate.date
+    ));
+    render(StatusCode::CONFLICT, &page)
+}
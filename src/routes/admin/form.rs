@@ -0,0 +1,40 @@
+use crate::domain::DomainError;
+use crate::utils::{e500, render};
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use askama::Template;
+
+/// A form page that can be shown again with why its write was refused.
+pub trait FormPage: Template {
+    /// What the form edits, e.g. `artist`, as named in its messages
+    const ENTITY: &'static str;
+
+    fn set_form_error(&mut self, message: String);
+}
+
+/// Show the form again with why the write was refused, or fail the request
+/// when it was not something the moderator can fix.
+pub fn form_error<P: FormPage>(mut page: P, e: DomainError) -> Result<HttpResponse, actix_web::Error> {
+    let status = match &e {
+        DomainError::Conflict(_) | DomainError::VersionMismatch => StatusCode::CONFLICT,
+        DomainError::NotFound => StatusCode::NOT_FOUND,
+        DomainError::InvalidReference(_) => StatusCode::BAD_REQUEST,
+        DomainError::Unexpected(_) => return Err(e500(e)),
+    };
+    page.set_form_error(match e {
+        DomainError::VersionMismatch => format!(
+            "The {} was changed by someone else, reload the form to see their changes.",
+            P::ENTITY
+        ),
+        e => e.to_string(),
+    });
+    render(status, &page)
+}
+
+/// The version an edit form was rendered from, which updates must carry so
+/// that they can't overwrite changes made since.
+pub fn required_version(version: Option<i32>) -> Result<i32, actix_web::Error> {
+    version.ok_or_else(|| {
+        actix_web::error::ErrorBadRequest("The form did not carry the version it was rendered from, reload it")
+    })
+}
@@ -0,0 +1,7 @@
+mod artists;
+mod concerts;
+mod form;
+
+pub use artists::*;
+pub use concerts::*;
+pub use form::*;
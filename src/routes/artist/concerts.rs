@@ -0,0 +1,104 @@
+use crate::domain::{Artist, Concert, Timeframe, ValidationErrors};
+use crate::routes::GetArtistError;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+const MAX_PER_PAGE: u32 = 100;
+
+#[derive(serde::Deserialize, Debug, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ArtistConcertsParameters {
+    /// Which concerts to list: `all`, `upcoming` or `past`
+    #[serde(default)]
+    #[param(inline)]
+    pub when: Timeframe,
+    /// The page to return, starting at 1
+    #[serde(default = "default_page")]
+    pub page: u32,
+    /// How many concerts a page holds, at most 100
+    #[serde(default = "default_per_page")]
+    pub per_page: u32,
+}
+
+fn default_page() -> u32 {
+    1
+}
+
+fn default_per_page() -> u32 {
+    20
+}
+
+impl ArtistConcertsParameters {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::default();
+        if self.page == 0 {
+            errors.push("page", "The page must be at least 1");
+        }
+        if self.per_page == 0 || self.per_page > MAX_PER_PAGE {
+            errors.push("per_page", format!("The page size must be between 1 and {}", MAX_PER_PAGE));
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+/// One page of an artist's concerts
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct ConcertPage {
+    pub concerts: Vec<Concert>,
+    pub page: u32,
+    pub per_page: u32,
+    /// How many concerts there are across all pages
+    pub total: i64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/artists/{id}/concerts",
+    tag = "artists",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+        ArtistConcertsParameters,
+    ),
+    responses(
+        (status = 200, description = "A page of the artist's concerts. Upcoming concerts are listed soonest first, past ones most recent first, and all of them oldest first", body = ConcertPage),
+        (status = 400, description = "The page or page size is out of range", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Artist not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Get the concerts of an artist",
+    skip(pool)
+)]
+pub async fn get_artist_concerts(
+    id: web::Path<Uuid>,
+    parameters: web::Query<ArtistConcertsParameters>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, GetArtistError> {
+    parameters.validate().map_err(GetArtistError::ValidationError)?;
+
+    Artist::find_by_id(*id, &pool)
+        .await?
+        .ok_or(GetArtistError::NotFoundError)?;
+
+    let today = chrono::Utc::now().date_naive();
+    let limit = i64::from(parameters.per_page);
+    let offset = i64::from(parameters.page - 1) * limit;
+
+    let concerts = Concert::find_by_artist(*id, parameters.when, today, Some(limit), offset, &pool)
+        .await
+        .context("Failed to get the concerts of the artist")?;
+    let total = Concert::count_by_artist(*id, parameters.when, today, &pool)
+        .await
+        .context("Failed to count the concerts of the artist")?;
+
+    Ok(HttpResponse::Ok().json(ConcertPage {
+        concerts,
+        page: parameters.page,
+        per_page: parameters.per_page,
+        total,
+    }))
+}
@@ -1,44 +1,42 @@
-use actix_web::{web, http::header::ContentType};
-use actix_web::HttpResponse;
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpRequest, HttpResponse};
+use askama::Template;
+use crate::cache::{CachedResponse, ResponseCache};
 use crate::domain::Artist;
+use crate::utils::e500;
 use sqlx::PgPool;
 
+#[derive(Template)]
+#[template(path = "artists/dashboard.html")]
+struct ArtistsDashboard {
+    artists: Vec<Artist>,
+}
 
-
+#[utoipa::path(
+    get,
+    path = "/artists/dashboard",
+    tag = "artists",
+    responses(
+        (status = 200, description = "An HTML page listing every artist", content_type = "text/html"),
+    )
+)]
 pub async fn artists_dashboard(
+    req: HttpRequest,
     pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, actix_web::Error> {
-    let artists = Artist::find_all(&pool)
-        .await
-        .expect("Failed to retrieve artists");
+    let page = cache
+        .get_or_load("artists/dashboard", || async {
+            let artists = Artist::find_all(&pool).await.map_err(e500)?;
+            let body = ArtistsDashboard { artists }.render().map_err(e500)?;
 
-    let artist_list = artists
-        .iter()
-        .map(|artist| {
-            format!(
-                r#"<li><a href="/artists/{id}">{name}</a></li>"#,
-                id = artist.id,
-                name = artist.name
-            )
+            Ok::<_, actix_web::Error>(CachedResponse {
+                content_type: ContentType::html().to_string(),
+                etag: None,
+                body,
+            })
         })
-        .collect::<Vec<String>>()
-        .join("\n");
+        .await?;
 
-    Ok(HttpResponse::Ok()
-       .content_type(ContentType::html())
-       .body(format!(
-            r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta http-equiv="content-type" content="text/html; charset=utf-8">
-    <title>Artist Dashboard</title>
-</head>
-<body>
-    <h1>Artists</h1>
-    <ul>
-        {artist_list}
-    </ul>
-</body>
-            "#,
-        )))
+    Ok(cache.respond(&req, page))
 }
@@ -1,8 +1,12 @@
-use crate::domain::{Artist, Concert};
-use crate::routes::error_chain_fmt;
-use actix_web::{web, ResponseError, HttpResponse, http::header::ContentType};
+use crate::cache::{CachedResponse, ResponseCache};
+use crate::domain::{Artist, Concert, DomainError, Timeframe, ValidationErrors};
+use crate::routes::{entity_tag, error_chain_fmt, ProblemDetails};
+use actix_web::http::header::{ContentType, EntityTag};
+use actix_web::{web, ResponseError, HttpRequest, HttpResponse};
 use anyhow::Context;
+use askama::Template;
 use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
 use sqlx::PgPool;
 use uuid::Uuid;
 
@@ -10,8 +14,12 @@ use uuid::Uuid;
 pub enum GetArtistError {
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
     #[error("Artist not found")]
     NotFoundError,
+    #[error("{0}")]
+    ValidationError(ValidationErrors),
 }
 
 impl std::fmt::Debug for GetArtistError {
@@ -25,73 +33,106 @@ impl ResponseError for GetArtistError {
         match self {
             Self::NotFoundError => StatusCode::NOT_FOUND,
             Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::DomainError(e) => e.status_code(),
+            Self::ValidationError(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::NotFoundError => ProblemDetails::not_found(self.to_string()).into(),
+            Self::UnexpectedError(_) => ProblemDetails::unexpected().into(),
+            Self::DomainError(e) => e.error_response(),
+            Self::ValidationError(errors) => ProblemDetails::validation(errors).into(),
         }
     }
 }
 
+#[derive(Template)]
+#[template(path = "artists/artist.html")]
+struct ArtistPage {
+    artist: Artist,
+    upcoming: Vec<Concert>,
+    past: Vec<Concert>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/artists/{id}",
+    tag = "artists",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+        ("If-None-Match" = Option<String>, Header, description = "The `ETag` of a copy the client already has"),
+    ),
+    responses(
+        (status = 200, description = "An HTML page describing the artist and their upcoming and past concerts", content_type = "text/html",
+            headers(("ETag" = String, description = "The artist's version, followed by a digest of their concerts"))),
+        (status = 304, description = "The client's copy is current"),
+        (status = 404, description = "Artist not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 #[tracing::instrument(
     name = "Get an artist",
-    skip(id, pool)
+    skip(req, id, pool, cache)
 )]
 pub async fn artist_dashboard(
+    req: HttpRequest,
     id: web::Path<String>,
-    pool: web::Data<PgPool>
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
 ) ->Result<HttpResponse, GetArtistError> {
     let id = Uuid::parse_str(&id)
         .map_err(|_| GetArtistError::NotFoundError)?;
-    
-    let result = Artist::find_by_id(id, &pool)
-        .await
-        .context("Failed to get artist")?;
 
-    if result.is_none() {
-        return Err(GetArtistError::NotFoundError);
-    }
+    // Concerts move from upcoming to past at midnight, so each day gets its
+    // own cache entry.
+    let today = chrono::Utc::now().date_naive();
 
-    let artist = result.unwrap(); 
-    let artist_name = artist.name;
-    let _artist_id = artist.id;
-    let artist_sort_name = artist.sort_name;
-    let artist_disambiguation = artist.disambiguation;
+    let page = cache
+        .get_or_load(&format!("artists/{}/{}", id, today), || async {
+            let artist = Artist::find_by_id(id, &pool)
+                .await?
+                .ok_or(GetArtistError::NotFoundError)?;
 
-    let concerts = Concert::find_all(&pool)
-        .await
-        .context("Failed to get concerts")?;
-
-    let concert_list = concerts
-        .into_iter()
-        .filter(|concert| concert.artist_id == id)
-        .map(|concert| {
-            format!(
-                r#"<li><a href="/concerts/{id}">{date} {venue}</a></li>"#,
-                id = concert.id,
-                date = concert.date,
-                venue = concert.venue
-            )
+            let upcoming = Concert::find_by_artist(id, Timeframe::Upcoming, today, None, 0, &pool)
+                .await
+                .context("Failed to get upcoming concerts")?;
+            let past = Concert::find_by_artist(id, Timeframe::Past, today, None, 0, &pool)
+                .await
+                .context("Failed to get past concerts")?;
+
+            let tag = dashboard_tag(&artist, today, upcoming.iter().chain(&past));
+            let body = ArtistPage { artist, upcoming, past }.render().context("Failed to render the artist page")?;
+
+            Ok::<_, GetArtistError>(CachedResponse {
+                content_type: ContentType::html().to_string(),
+                etag: Some(tag.tag().to_string()),
+                body,
+            })
         })
-        .collect::<Vec<String>>()
-        .join("\n");
-
-    Ok(HttpResponse::Ok()
-       .content_type(ContentType::html())
-       .body(format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta http-equiv="Content-Type" content="text/html; charset=utf-8"/>
-    <title>{artist_name}</title>
-</head>
-<body>
-    <h1>{artist_name}</h1>
-    <h2>{artist_sort_name}</h2>
-    <h3>{artist_disambiguation}</h3>
-    <ul>
-        {concert_list}
-    </ul>
-</body>
-</html>
-        "#
-        )))
+        .await?;
+
+    Ok(cache.respond(&req, page))
+}
+
+/// The dashboard lists the artist's concerts too, so its tag changes when
+/// any of them is added or updated, not only when the artist is. It covers
+/// the day as well, since that decides which concerts are upcoming.
+fn dashboard_tag<'a>(
+    artist: &Artist,
+    today: chrono::NaiveDate,
+    concerts: impl Iterator<Item = &'a Concert>,
+) -> EntityTag {
+    let mut hasher = Sha256::new();
+    hasher.update(today.to_string().as_bytes());
+    for concert in concerts {
+        hasher.update(concert.id.as_bytes());
+        hasher.update(concert.version.to_be_bytes());
+    }
+    let digest = hex::encode(&hasher.finalize()[..8]);
+
+    EntityTag::new_strong(format!("{}-{}", entity_tag(artist.version).tag(), digest))
 }
 
 #[tracing::instrument(
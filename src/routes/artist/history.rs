@@ -0,0 +1,85 @@
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Artist, AuditEntry, Audited};
+use crate::routes::{entity_tag, expected_versions, GetArtistError, UpdateArtistError};
+use actix_web::http::header::ETag;
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[utoipa::path(
+    get,
+    path = "/artists/{id}/history",
+    tag = "artists",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+    ),
+    responses(
+        (status = 200, description = "Every change to the artist, oldest first", body = [AuditEntry]),
+        (status = 404, description = "Artist not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Get the history of an artist",
+    skip(pool)
+)]
+pub async fn get_artist_history(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, GetArtistError> {
+    Artist::find_by_id(*id, &pool)
+        .await?
+        .ok_or(GetArtistError::NotFoundError)?;
+
+    let history = AuditEntry::find_by_entity(Artist::ENTITY_TYPE, *id, &pool)
+        .await
+        .context("Failed to get the history of the artist")?;
+
+    Ok(HttpResponse::Ok().json(history))
+}
+
+#[utoipa::path(
+    post,
+    path = "/artists/{id}/history/{revision}/revert",
+    tag = "artists",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+        ("revision" = i32, Path, description = "The revision to restore"),
+        ("If-Match" = String, Header, description = "The `ETag` of the version being updated, or `*`"),
+    ),
+    responses(
+        (status = 200, description = "The artist was restored to the revision, as a new version", body = Artist,
+            headers(("ETag" = String, description = "The entity tag of the new version"))),
+        (status = 404, description = "Artist or revision not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Another artist has since taken the name", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The artist changed since the version in `If-Match`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "The `If-Match` header is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Reverting an artist to a revision",
+    skip(req, pool, cache)
+)]
+pub async fn revert_artist(
+    req: HttpRequest,
+    path: web::Path<(Uuid, i32)>,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, UpdateArtistError> {
+    let (id, revision) = path.into_inner();
+    let expected_versions = expected_versions(&req)?;
+
+    let mut transaction = pool.begin().await.context("Failed to acquire a Postgres connection")?;
+
+    let result = Artist::revert(id, revision, expected_versions.as_deref(), &actor, &mut transaction).await?;
+
+    transaction.commit().await.context("Failed to commit transaction")?;
+    cache.invalidate().await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(result.version)))
+        .json(result))
+}
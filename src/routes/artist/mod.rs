@@ -1,10 +1,16 @@
 mod post;
 mod get;
 mod put;
+mod patch;
+mod history;
 mod dashboard;
+mod concerts;
 
 pub use post::*;
 pub use get::*;
 pub use put::*;
+pub use patch::*;
+pub use history::*;
 pub use dashboard::*;
+pub use concerts::*;
 
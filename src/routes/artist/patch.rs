@@ -0,0 +1,96 @@
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Artist, ArtistName, ArtistPatch, ValidationErrors};
+use crate::routes::{
+    entity_tag,
+    expected_versions,
+    nullable,
+    required,
+    UpdateArtistError,
+};
+use actix_web::http::header::ETag;
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use sqlx::PgPool;
+
+/// An RFC 7396 merge patch of an artist: only the members present are
+/// changed.
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PatchArtistRequest {
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub sort_name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub disambiguation: Option<Option<String>>,
+}
+
+impl TryFrom<PatchArtistRequest> for ArtistPatch {
+    type Error = ValidationErrors;
+
+    fn try_from(value: PatchArtistRequest) -> Result<Self, Self::Error> {
+        let mut errors = ValidationErrors::default();
+        let patch = Self {
+            name: required(&mut errors, "name", value.name, ArtistName::parse),
+            sort_name: required(&mut errors, "sort_name", value.sort_name, Ok),
+            disambiguation: required(&mut errors, "disambiguation", value.disambiguation, Ok),
+        };
+
+        if errors.is_empty() {
+            Ok(patch)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/artists/{id}",
+    tag = "artists",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+        ("If-Match" = String, Header, description = "The `ETag` of the version being updated, or `*`"),
+    ),
+    request_body(content = PatchArtistRequest, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "The artist was updated", body = Artist,
+            headers(("ETag" = String, description = "The entity tag of the new version"))),
+        (status = 400, description = "A member failed validation or removes a required field", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Artist not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Another artist already has this name", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The artist changed since the version in `If-Match`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "The `If-Match` header is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Patching an artist in the database",
+    skip(req, patch, pool, cache)
+)]
+pub async fn patch_artist(
+    req: HttpRequest,
+    id: web::Path<uuid::Uuid>,
+    patch: web::Json<PatchArtistRequest>,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, UpdateArtistError> {
+    let patch = ArtistPatch::try_from(patch.into_inner())
+        .map_err(UpdateArtistError::ValidationError)?;
+    let expected_versions = expected_versions(&req)?;
+
+    let mut transaction = pool.begin().await.context("Failed to acquire a Postgres connection")?;
+
+    let result = Artist::patch(*id, &patch, expected_versions.as_deref(), &actor, &mut transaction).await?;
+
+    transaction.commit().await.context("Failed to commit transaction")?;
+    cache.invalidate().await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(result.version)))
+        .json(result))
+}
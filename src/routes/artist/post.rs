@@ -1,34 +1,60 @@
-use crate::domain::{Artist, ArtistName, NewArtist};
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Artist, ArtistName, Audited, DomainError, NewArtist, ValidationErrors};
+use crate::idempotency::{request_hash, Idempotency, IdempotencyError, NextAction};
+use crate::metrics::count_created;
+use crate::musicbrainz::IndexedArtistDump;
+use crate::routes::{entity_tag, ProblemDetails};
+use crate::telemetry::spawn_blocking_with_tracing;
+use actix_web::http::header::ETag;
 use actix_web::{web, HttpResponse, ResponseError};
 use reqwest::StatusCode;
 use sqlx::PgPool;
 use anyhow::Context;
 
-#[derive(serde::Deserialize)]
-pub struct BodyData {
-    name: String,
-    sort_name: String,
-    disambiguation: String,
+#[derive(serde::Deserialize, serde::Serialize, utoipa::ToSchema, async_graphql::InputObject)]
+#[graphql(name = "CreateArtistInput")]
+pub struct CreateArtistRequest {
+    pub name: String,
+    pub sort_name: Option<String>,
+    pub disambiguation: Option<String>,
+    pub mbid: Option<uuid::Uuid>,
 }
 
-impl TryFrom<BodyData> for NewArtist {
-    type Error = String;
+impl TryFrom<CreateArtistRequest> for NewArtist {
+    type Error = ValidationErrors;
 
-    fn try_from(value: BodyData) -> Result<Self, Self::Error> {
-        let name = ArtistName::parse(value.name)?;
-        let sort_name = value.sort_name;
-        let disambiguation = value.disambiguation;
+    fn try_from(value: CreateArtistRequest) -> Result<Self, Self::Error> {
+        let mut errors = ValidationErrors::default();
+        let name = errors.check("name", ArtistName::parse(value.name));
+        let sort_name = errors.check(
+            "sort_name",
+            value.sort_name.ok_or_else(|| "A sort name is required".to_string()),
+        );
+        let disambiguation = errors.check(
+            "disambiguation",
+            value.disambiguation.ok_or_else(|| "A disambiguation is required".to_string()),
+        );
+        let mbid = value.mbid;
 
-        Ok(Self{ name, sort_name, disambiguation })
+        match (name, sort_name, disambiguation) {
+            (Some(name), Some(sort_name), Some(disambiguation)) => {
+                Ok(Self{ name, sort_name, disambiguation, mbid })
+            }
+            _ => Err(errors),
+        }
     }
 }
 
 #[derive(thiserror::Error)]
 pub enum ArtistError {
     #[error("{0}")]
-    ValidationError(String),
+    ValidationError(ValidationErrors),
     #[error("{0}")]
-    UnexpectedError(#[from] anyhow::Error)
+    UnexpectedError(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
+    #[error(transparent)]
+    IdempotencyError(#[from] IdempotencyError),
 }
 
 impl std::fmt::Debug for ArtistError {
@@ -42,6 +68,17 @@ impl ResponseError for ArtistError {
         match self {
             ArtistError::ValidationError(_) => StatusCode::BAD_REQUEST,
             ArtistError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ArtistError::DomainError(e) => e.status_code(),
+            ArtistError::IdempotencyError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            ArtistError::ValidationError(errors) => ProblemDetails::validation(errors).into(),
+            ArtistError::UnexpectedError(_) => ProblemDetails::unexpected().into(),
+            ArtistError::DomainError(e) => e.error_response(),
+            ArtistError::IdempotencyError(e) => e.error_response(),
         }
     }
 }
@@ -61,27 +98,83 @@ pub fn error_chain_fmt(
     Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/artists",
+    tag = "artists",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Replay the saved response when the request is retried with the same key"),
+        ("X-Client-Id" = Option<String>, Header, description = "Who the change is attributed to; a hint telling apart clients sharing an IP address for `Idempotency-Key`"),
+    ),
+    request_body = CreateArtistRequest,
+    responses(
+        (status = 201, description = "The artist was created", body = Artist),
+        (status = 400, description = "The payload failed validation or the idempotency key is invalid", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "An artist with this name or MBID already exists", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "The idempotency key was used for a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 #[tracing::instrument(
     name = "Add new artist",
-    skip(body, pool),
+    skip(body, pool, dump, cache),
 )]
 pub async fn create_artist(
-    body: web::Json<NewArtist>,
+    body: web::Json<CreateArtistRequest>,
+    idempotency: Idempotency,
+    actor: Actor,
     pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+    dump: Option<web::Data<IndexedArtistDump>>,
 ) -> Result<HttpResponse, ArtistError> {
-    let mut transaction = pool
-        .begin()
-        .await
-        .context("Failed to acquire a Postgres transaction from the database")?;
+    let mut body = body.into_inner();
+    let request_hash = request_hash(&body)?;
+    if let (Some(mbid), Some(dump)) = (body.mbid, dump) {
+        prefill_from_dump(&mut body, mbid, dump.into_inner()).await?;
+    }
+    let new_artist = NewArtist::try_from(body).map_err(ArtistError::ValidationError)?;
 
-    let created = Artist::insert(&body, &mut transaction)
-        .await
-        .context("Failed to create a new artist")?;
+    let mut transaction = match idempotency.begin(&pool, &request_hash).await? {
+        NextAction::StartProcessing(transaction) => *transaction,
+        NextAction::ReturnSavedResponse(response) => return Ok(response),
+    };
+
+    let created = Artist::insert(&new_artist, &actor, &mut transaction).await?;
+
+    let response = HttpResponse::Created()
+        .insert_header(ETag(entity_tag(created.version)))
+        .json(created);
+    let response = idempotency.save_response(&mut transaction, response).await?;
 
     transaction.commit()
         .await
         .context("Failed to commit the Postgres transaction")?;
+    count_created(Artist::ENTITY_TYPE, 1);
+    cache.invalidate().await;
 
-    Ok(HttpResponse::Created().json(created))
+    Ok(response)
 }
 
+/// Fill in the sort name and disambiguation the client left out with the
+/// values MusicBrainz holds for `mbid`.
+async fn prefill_from_dump(
+    body: &mut CreateArtistRequest,
+    mbid: uuid::Uuid,
+    dump: std::sync::Arc<IndexedArtistDump>,
+) -> Result<(), ArtistError> {
+    let entity = spawn_blocking_with_tracing(move || dump.find(mbid))
+        .await
+        .context("Failed to spawn the MusicBrainz dump lookup")?
+        .context("Failed to read the MusicBrainz dump")?
+        .ok_or_else(|| {
+            ArtistError::ValidationError(ValidationErrors::field(
+                "mbid",
+                format!("{} is not a known MusicBrainz artist", mbid),
+            ))
+        })?;
+
+    body.sort_name.get_or_insert(entity.sort_name);
+    body.disambiguation.get_or_insert(entity.disambiguation);
+
+    Ok(())
+}
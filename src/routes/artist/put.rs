@@ -1,46 +1,67 @@
+use crate::cache::ResponseCache;
 use crate::domain::{
+    Actor,
     Artist, 
     ArtistName, 
+    DomainError,
     UpdateArtist, 
+    ValidationErrors,
 };
-use crate::routes::error_chain_fmt;
-use actix_web::{web, HttpResponse, ResponseError};
+use crate::routes::{
+    entity_tag,
+    error_chain_fmt,
+    expected_versions,
+    PreconditionError,
+    ProblemDetails,
+};
+use actix_web::http::header::ETag;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
 use reqwest::StatusCode;
 use sqlx::PgPool;
 use anyhow::Context;
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, serde::Serialize, utoipa::ToSchema, async_graphql::InputObject)]
+#[schema(as = UpdateArtistRequest)]
+#[graphql(name = "UpdateArtistInput")]
 pub struct BodyData {
-    id: uuid::Uuid,
-    name: String,
-    sort_name: String,
-    disambiguation: String,
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub sort_name: String,
+    pub disambiguation: String,
 }
 
 impl TryFrom<BodyData> for UpdateArtist {
-    type Error = String;
+    type Error = ValidationErrors;
 
     fn try_from(value: BodyData) -> Result<Self, Self::Error> {
+        let mut errors = ValidationErrors::default();
         let id = value.id;
-        let name = ArtistName::parse(value.name)?;
+        let name = errors.check("name", ArtistName::parse(value.name));
         let sort_name = value.sort_name;
         let disambiguation = value.disambiguation;
 
-        Ok(Self{ 
-            id,
-            name, 
-            sort_name, 
-            disambiguation, 
-        })
+        match name {
+            Some(name) => Ok(Self{ 
+                id,
+                name, 
+                sort_name, 
+                disambiguation, 
+            }),
+            None => Err(errors),
+        }
     }
 }
 
 #[derive(thiserror::Error)]
 pub enum UpdateArtistError {
     #[error("{0}")]
-    ValidationError(String),
+    ValidationError(ValidationErrors),
     #[error("{0}")]
-    UnexpectedError(#[from] anyhow::Error)
+    UnexpectedError(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
+    #[error(transparent)]
+    PreconditionError(#[from] PreconditionError),
 }
 
 impl std::fmt::Debug for UpdateArtistError {
@@ -54,35 +75,73 @@ impl ResponseError for UpdateArtistError {
         match self {
             UpdateArtistError::ValidationError(_) => StatusCode::BAD_REQUEST,
             UpdateArtistError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            UpdateArtistError::DomainError(e) => e.status_code(),
+            UpdateArtistError::PreconditionError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            UpdateArtistError::ValidationError(errors) => ProblemDetails::validation(errors).into(),
+            UpdateArtistError::UnexpectedError(_) => ProblemDetails::unexpected().into(),
+            UpdateArtistError::DomainError(e) => e.error_response(),
+            UpdateArtistError::PreconditionError(e) => e.error_response(),
         }
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/artists/{id}",
+    tag = "artists",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+        ("If-Match" = String, Header, description = "The `ETag` of the version being updated, or `*`"),
+    ),
+    request_body = UpdateArtistRequest,
+    responses(
+        (status = 200, description = "The artist was updated", body = Artist,
+            headers(("ETag" = String, description = "The entity tag of the new version"))),
+        (status = 400, description = "The payload failed validation or its id does not match the path", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Artist not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Another artist already has this name", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The artist changed since the version in `If-Match`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "The `If-Match` header is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 #[tracing::instrument(
     name = "Updating an artist in the database",
-    skip(artist, pool)
+    skip(req, artist, pool, cache)
 )]
 pub async fn update_artist(
+    req: HttpRequest,
     id: web::Path<uuid::Uuid>,
     artist: web::Json<BodyData>,
+    actor: Actor,
     pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, UpdateArtistError> {
     let artist = UpdateArtist::try_from(artist.into_inner())
         .map_err(UpdateArtistError::ValidationError)?;
     if artist.id != *id {
-        return Err(UpdateArtistError::ValidationError(
-            "The artist id in the path does not match the artist id in the body".to_string(),
-        ));
+        return Err(UpdateArtistError::ValidationError(ValidationErrors::field(
+            "id",
+            "The artist id in the path does not match the artist id in the body",
+        )));
     }
 
+    let expected_versions = expected_versions(&req)?;
+
     let mut transaction = pool.begin().await.context("Failed to acquire a Postgres connection")?;
 
-    let result = Artist::update(&artist, &mut transaction)
-        .await
-        .context("Failed to insert artist")?;
+    let result = Artist::update(&artist, expected_versions.as_deref(), &actor, &mut transaction).await?;
 
     transaction.commit().await.context("Failed to commit transaction")?;
+    cache.invalidate().await;
 
-    Ok(HttpResponse::Ok().json(result))
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(result.version)))
+        .json(result))
 }
 
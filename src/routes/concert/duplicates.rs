@@ -0,0 +1,63 @@
+use crate::domain::{likely_same_place, trigram_similarity, Concert};
+use crate::routes::GetConcertError;
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+
+/// A concert that most likely describes the same show as another one.
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct LikelyDuplicate {
+    pub artist_id: uuid::Uuid,
+    pub date: chrono::NaiveDate,
+    pub concert_id: uuid::Uuid,
+    pub duplicate_of: uuid::Uuid,
+    pub venue_similarity: f64,
+    pub city_similarity: f64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/concerts/duplicates",
+    tag = "concerts",
+    responses(
+        (status = 200, description = "Concerts that most likely duplicate an earlier one", body = [LikelyDuplicate]),
+        (status = 500, description = "Unexpected error"),
+    )
+)]
+#[tracing::instrument(
+    name = "Reporting likely duplicate concerts",
+    skip(pool)
+)]
+pub async fn get_concert_duplicates(
+    pool: web::Data<sqlx::PgPool>,
+) -> Result<HttpResponse, GetConcertError> {
+    let concerts = Concert::find_sharing_artist_and_date(&pool)
+        .await
+        .context("Failed to fetch concerts sharing an artist and a date")?;
+
+    let mut duplicates = Vec::new();
+    for (index, concert) in concerts.iter().enumerate() {
+        // Concerts are ordered by artist and date, so any earlier show on the
+        // same day by the same artist sits right before this one.
+        let same_day = concerts[..index]
+            .iter()
+            .rev()
+            .take_while(|other| other.artist_id == concert.artist_id && other.date == concert.date)
+            .collect::<Vec<_>>();
+        let original = same_day.into_iter().rev().find(|original| {
+            likely_same_place(&original.venue, &original.city, &concert.venue, &concert.city)
+        });
+
+        if let Some(original) = original {
+            duplicates.push(LikelyDuplicate {
+                artist_id: concert.artist_id,
+                date: concert.date,
+                concert_id: concert.id,
+                duplicate_of: original.id,
+                venue_similarity: trigram_similarity(&original.venue, &concert.venue),
+                city_similarity: trigram_similarity(&original.city, &concert.city),
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(duplicates))
+}
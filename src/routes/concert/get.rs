@@ -1,8 +1,10 @@
-use actix_web::{web, ResponseError, HttpResponse};
+use actix_web::http::header::{ContentType, ETag};
+use actix_web::{web, ResponseError, HttpRequest, HttpResponse};
 use reqwest::StatusCode;
 use anyhow::Context;
 
-use crate::{routes::error_chain_fmt, domain::Concert};
+use crate::{routes::{entity_tag, error_chain_fmt, is_fresh, ProblemDetails}, domain::{Concert, DomainError}};
+use crate::cache::{CachedResponse, ResponseCache};
 
 
 
@@ -12,6 +14,8 @@ pub enum GetConcertError {
     NotFoundError,
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
 }
 
 impl std::fmt::Debug for GetConcertError {
@@ -25,44 +29,91 @@ impl ResponseError for GetConcertError {
         match self {
             GetConcertError::NotFoundError => StatusCode::NOT_FOUND,
             GetConcertError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            GetConcertError::DomainError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::NotFoundError => ProblemDetails::not_found(self.to_string()).into(),
+            Self::UnexpectedError(_) => ProblemDetails::unexpected().into(),
+            Self::DomainError(e) => e.error_response(),
         }
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/concerts/{id}",
+    tag = "concerts",
+    params(
+        ("id" = Uuid, Path, description = "The concert id"),
+        ("If-None-Match" = Option<String>, Header, description = "The `ETag` of a copy the client already has"),
+    ),
+    responses(
+        (status = 200, description = "The concert", body = Concert,
+            headers(("ETag" = String, description = "The entity tag of this version"))),
+        (status = 304, description = "The client's copy is current"),
+        (status = 404, description = "Concert not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 #[tracing::instrument(
     name = "Getting a concert", 
-    skip(id, pool)
+    skip(req, id, pool)
 )]
 pub async fn get_concert(
+    req: HttpRequest,
     id: web::Path<String>,
     pool: web::Data<sqlx::PgPool>,
 ) -> Result<HttpResponse, GetConcertError> {
     let id = uuid::Uuid::parse_str(&id.into_inner())
         .context("Failed to parse concert ID")?;
 
-    let concert = Concert::find_by_id(id, &pool)
-        .await
-        .context("Failed to find concert")?;
+    let concert = Concert::find_by_id(id, &pool).await?;
 
-    match concert {
-        Some(concert) => Ok(HttpResponse::Ok().json(concert)),
-        None => Err(GetConcertError::NotFoundError),
+    let concert = concert.ok_or(GetConcertError::NotFoundError)?;
+
+    let tag = entity_tag(concert.version);
+    if is_fresh(&req, &tag) {
+        return Ok(HttpResponse::NotModified().insert_header(ETag(tag)).finish());
     }
+
+    Ok(HttpResponse::Ok().insert_header(ETag(tag)).json(concert))
 }
 
+#[utoipa::path(
+    get,
+    path = "/concerts",
+    tag = "concerts",
+    responses(
+        (status = 200, description = "Every concert, ordered by date", body = [Concert]),
+        (status = 500, description = "Unexpected error"),
+    )
+)]
 #[tracing::instrument(
     name = "Getting all concerts", 
-    skip(pool)
+    skip(req, pool, cache)
 )]
 pub async fn get_concerts(
+    req: HttpRequest,
     pool: web::Data<sqlx::PgPool>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, GetConcertError> {
-    let concerts = Concert::find_all(&pool).await;
+    let concerts = cache
+        .get_or_load("concerts", || async {
+            let concerts = Concert::find_all(&pool).await.map_err(|_| {
+                GetConcertError::UnexpectedError(anyhow::Error::msg("Failed to fetch concerts"))
+            })?;
+            let body = serde_json::to_string(&concerts).context("Failed to serialize the concerts")?;
 
-    match concerts {
-        Ok(concerts) => Ok(HttpResponse::Ok().json(&concerts)),
-        Err(_) => Err(GetConcertError::UnexpectedError(
-            anyhow::Error::msg("Failed to fetch concerts")
-        )),
-    }
+            Ok::<_, GetConcertError>(CachedResponse {
+                content_type: ContentType::json().to_string(),
+                etag: None,
+                body,
+            })
+        })
+        .await?;
+
+    Ok(cache.respond(&req, concerts))
 }
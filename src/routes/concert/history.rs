@@ -0,0 +1,85 @@
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Concert, AuditEntry, Audited};
+use crate::routes::{entity_tag, expected_versions, GetConcertError, UpdateConcertError};
+use actix_web::http::header::ETag;
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[utoipa::path(
+    get,
+    path = "/concerts/{id}/history",
+    tag = "concerts",
+    params(
+        ("id" = Uuid, Path, description = "The concert id"),
+    ),
+    responses(
+        (status = 200, description = "Every change to the concert, oldest first", body = [AuditEntry]),
+        (status = 404, description = "Concert not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Get the history of a concert",
+    skip(pool)
+)]
+pub async fn get_concert_history(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, GetConcertError> {
+    Concert::find_by_id(*id, &pool)
+        .await?
+        .ok_or(GetConcertError::NotFoundError)?;
+
+    let history = AuditEntry::find_by_entity(Concert::ENTITY_TYPE, *id, &pool)
+        .await
+        .context("Failed to get the history of the concert")?;
+
+    Ok(HttpResponse::Ok().json(history))
+}
+
+#[utoipa::path(
+    post,
+    path = "/concerts/{id}/history/{revision}/revert",
+    tag = "concerts",
+    params(
+        ("id" = Uuid, Path, description = "The concert id"),
+        ("revision" = i32, Path, description = "The revision to restore"),
+        ("If-Match" = String, Header, description = "The `ETag` of the version being updated, or `*`"),
+    ),
+    responses(
+        (status = 200, description = "The concert was restored to the revision, as a new version", body = Concert,
+            headers(("ETag" = String, description = "The entity tag of the new version"))),
+        (status = 404, description = "Concert or revision not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The concert changed since the version in `If-Match`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "The artist of the revision no longer exists", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "The `If-Match` header is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Reverting a concert to a revision",
+    skip(req, pool, cache)
+)]
+pub async fn revert_concert(
+    req: HttpRequest,
+    path: web::Path<(Uuid, i32)>,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, UpdateConcertError> {
+    let (id, revision) = path.into_inner();
+    let expected_versions = expected_versions(&req)?;
+
+    let mut transaction = pool.begin().await.context("Failed to acquire a Postgres connection")?;
+
+    let result = Concert::revert(id, revision, expected_versions.as_deref(), &actor, &mut transaction).await?;
+
+    transaction.commit().await.context("Failed to commit transaction")?;
+    cache.invalidate().await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(result.version)))
+        .json(result))
+}
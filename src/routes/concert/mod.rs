@@ -1,7 +1,13 @@
 mod post;
 mod get;
 mod put;
+mod patch;
+mod history;
+mod duplicates;
 
 pub use post::*;
 pub use get::*;
 pub use put::*;
+pub use patch::*;
+pub use history::*;
+pub use duplicates::*;
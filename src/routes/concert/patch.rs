@@ -0,0 +1,136 @@
+use crate::cache::ResponseCache;
+use crate::domain::{
+    Actor,
+    Concert,
+    ConcertCity,
+    ConcertCountry,
+    ConcertDate,
+    ConcertPatch,
+    ConcertState,
+    ConcertVenue,
+    ValidationErrors,
+};
+use crate::routes::{
+    entity_tag,
+    expected_versions,
+    nullable,
+    optional,
+    required,
+    UpdateConcertError,
+    UpdateConcertParameters,
+};
+use actix_web::http::header::ETag;
+use actix_web::{web, HttpRequest, HttpResponse};
+use anyhow::Context;
+use sqlx::PgPool;
+
+/// An RFC 7396 merge patch of a concert: only the members present are
+/// changed, and `state` may be removed with `null`.
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PatchConcertRequest {
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<Uuid>)]
+    pub artist_id: Option<Option<uuid::Uuid>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub venue: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub city: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub state: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub country: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    #[schema(value_type = Option<String>)]
+    pub date: Option<Option<String>>,
+}
+
+impl TryFrom<PatchConcertRequest> for ConcertPatch {
+    type Error = ValidationErrors;
+
+    fn try_from(value: PatchConcertRequest) -> Result<Self, Self::Error> {
+        let mut errors = ValidationErrors::default();
+        let patch = Self {
+            artist_id: required(&mut errors, "artist_id", value.artist_id, Ok),
+            venue: required(&mut errors, "venue", value.venue, ConcertVenue::parse),
+            city: required(&mut errors, "city", value.city, ConcertCity::parse),
+            state: optional(&mut errors, "state", value.state, ConcertState::parse),
+            country: required(&mut errors, "country", value.country, ConcertCountry::parse),
+            date: required(&mut errors, "date", value.date, ConcertDate::parse),
+        };
+
+        if errors.is_empty() {
+            Ok(patch)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[utoipa::path(
+    patch,
+    path = "/concerts/{id}",
+    tag = "concerts",
+    params(
+        ("id" = Uuid, Path, description = "The concert id"),
+        ("If-Match" = String, Header, description = "The `ETag` of the version being updated, or `*`"),
+        UpdateConcertParameters,
+    ),
+    request_body(content = PatchConcertRequest, content_type = "application/merge-patch+json"),
+    responses(
+        (status = 200, description = "The concert was updated", body = Concert,
+            headers(("ETag" = String, description = "The entity tag of the new version"))),
+        (status = 400, description = "A member failed validation or removes a required field", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Concert not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The patched concert looks like a duplicate of `existing_id`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The concert changed since the version in `If-Match`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "The artist does not exist", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "The `If-Match` header is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Patching a concert in the database",
+    skip(req, patch, pool, cache)
+)]
+pub async fn patch_concert(
+    req: HttpRequest,
+    id: web::Path<uuid::Uuid>,
+    patch: web::Json<PatchConcertRequest>,
+    parameters: web::Query<UpdateConcertParameters>,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, UpdateConcertError> {
+    let patch = ConcertPatch::try_from(patch.into_inner())
+        .map_err(UpdateConcertError::ValidationError)?;
+    let expected_versions = expected_versions(&req)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+
+    let result = Concert::patch(*id, &patch, expected_versions.as_deref(), &actor, &mut transaction).await?;
+    // Checked once patched, as only then are all the fields known. Returning
+    // rolls the patch back.
+    if !parameters.force {
+        let duplicate = Concert::find_likely_duplicate_of_saved(&result, &mut transaction)
+            .await
+            .context("Failed to look for a duplicate concert")?;
+        if let Some(duplicate) = duplicate {
+            return Err(UpdateConcertError::DuplicateError(duplicate.id));
+        }
+    }
+
+    transaction.commit().await.context("Failed to commit the transaction")?;
+    cache.invalidate().await;
+
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(result.version)))
+        .json(result))
+}
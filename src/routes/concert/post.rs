@@ -1,21 +1,30 @@
+use crate::cache::ResponseCache;
 use crate::{
     domain::{
+        Actor,
+        Audited,
         Concert, 
+        DomainError,
         NewConcert, 
         ConcertVenue,
         ConcertCity,
         ConcertState,
         ConcertDate,
         ConcertCountry,
+        ValidationErrors,
     }, 
-    routes::error_chain_fmt
+    idempotency::{request_hash, Idempotency, IdempotencyError, NextAction},
+    metrics::count_created,
+    routes::{entity_tag, error_chain_fmt, ProblemCode, ProblemDetails},
 };
+use actix_web::http::header::ETag;
 use actix_web::{web, HttpResponse, ResponseError};
 use reqwest::StatusCode;
 use sqlx::PgPool;
 use anyhow::Context;
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, serde::Serialize, utoipa::ToSchema, async_graphql::InputObject)]
+#[graphql(name = "CreateConcertInput")]
 pub struct CreateConcertRequest {
     pub artist_id: uuid::Uuid,
     pub venue: String,
@@ -26,33 +35,51 @@ pub struct CreateConcertRequest {
 }
 
 impl TryFrom<CreateConcertRequest> for NewConcert {
-    type Error = String;
+    type Error = ValidationErrors;
 
     fn try_from(value: CreateConcertRequest) -> Result<Self, Self::Error> {
+        let mut errors = ValidationErrors::default();
         let artist_id = value.artist_id;
-        let venue = ConcertVenue::parse(value.venue)?;
-        let city = ConcertCity::parse(value.city)?;
-        let state = ConcertState::parse(value.state)?;
-        let country = ConcertCountry::parse(value.country)?;
-        let date = ConcertDate::parse(value.date)?;
-
-        Ok(Self {
-            artist_id,
-            venue,
-            city,
-            state,
-            country,
-            date,
-        })
+        let venue = errors.check("venue", ConcertVenue::parse(value.venue));
+        let city = errors.check("city", ConcertCity::parse(value.city));
+        let state = errors.check("state", ConcertState::parse(value.state));
+        let country = errors.check("country", ConcertCountry::parse(value.country));
+        let date = errors.check("date", ConcertDate::parse(value.date));
+
+        match (venue, city, state, country, date) {
+            (Some(venue), Some(city), Some(state), Some(country), Some(date)) => Ok(Self {
+                artist_id,
+                venue,
+                city,
+                state,
+                country,
+                date,
+            }),
+            _ => Err(errors),
+        }
     }
 }
 
+#[derive(serde::Deserialize, Debug, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateConcertParameters {
+    /// Create the concert even if it looks like a duplicate
+    #[serde(default)]
+    pub force: bool,
+}
+
 #[derive(thiserror::Error)]
 pub enum CreateConcertError {
     #[error("{0}")]
     UnexpectedError(#[from] anyhow::Error),
     #[error("{0}")]
-    ValidationError(String),
+    ValidationError(ValidationErrors),
+    #[error("This concert looks like a duplicate of concert {0}")]
+    DuplicateError(uuid::Uuid),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
+    #[error(transparent)]
+    IdempotencyError(#[from] IdempotencyError),
 }
 
 impl std::fmt::Debug for CreateConcertError {
@@ -66,30 +93,90 @@ impl ResponseError for CreateConcertError {
         match self {
             Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::ValidationError(_) => StatusCode::BAD_REQUEST,
+            Self::DuplicateError(_) => StatusCode::CONFLICT,
+            Self::DomainError(e) => e.status_code(),
+            Self::IdempotencyError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::UnexpectedError(_) => ProblemDetails::unexpected().into(),
+            Self::ValidationError(errors) => ProblemDetails::validation(errors).into(),
+            Self::DuplicateError(existing_id) => ProblemDetails {
+                existing_id: Some(*existing_id),
+                ..ProblemDetails::new(
+                    self.status_code(),
+                    ProblemCode::DuplicateConcert,
+                    self.to_string(),
+                )
+            }
+            .into(),
+            Self::DomainError(e) => e.error_response(),
+            Self::IdempotencyError(e) => e.error_response(),
         }
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/concerts",
+    tag = "concerts",
+    params(
+        CreateConcertParameters,
+        ("Idempotency-Key" = Option<String>, Header, description = "Replay the saved response when the request is retried with the same key"),
+        ("X-Client-Id" = Option<String>, Header, description = "Who the change is attributed to; a hint telling apart clients sharing an IP address for `Idempotency-Key`"),
+    ),
+    request_body = CreateConcertRequest,
+    responses(
+        (status = 201, description = "The concert was created", body = Concert),
+        (status = 400, description = "The payload failed validation or the idempotency key is invalid", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The concert looks like a duplicate of `existing_id`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "The artist does not exist, or the idempotency key was used for a different request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 #[tracing::instrument(
     name = "Adding a new concert",
-    skip(body, pool),
+    skip(body, pool, cache),
 )]
 pub async fn create_concert(
     body: web::Json<CreateConcertRequest>,
+    parameters: web::Query<CreateConcertParameters>,
+    idempotency: Idempotency,
+    actor: Actor,
     pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, CreateConcertError> {
+    let request_hash = request_hash(&(&body.0, parameters.force))?;
     let new_concert = body.0.try_into().map_err(CreateConcertError::ValidationError)?;
 
-    let mut transaction = pool
-        .begin()
-        .await
-        .context("Failed to acquire a Postgres connection from the pool")?;
+    // Claim the key first, so that a retry replays the saved response rather
+    // than finding the concert it created as a duplicate.
+    let mut transaction = match idempotency.begin(&pool, &request_hash).await? {
+        NextAction::StartProcessing(transaction) => *transaction,
+        NextAction::ReturnSavedResponse(response) => return Ok(response),
+    };
+
+    if !parameters.force {
+        let duplicate = Concert::find_likely_duplicate(&new_concert, &mut transaction)
+            .await
+            .context("Failed to look for a duplicate concert")?;
+        if let Some(duplicate) = duplicate {
+            return Err(CreateConcertError::DuplicateError(duplicate.id));
+        }
+    }
+
+    let concert = Concert::insert(&new_concert, &actor, &mut transaction).await?;
 
-    let concert = Concert::insert(&new_concert, &mut transaction)
-        .await
-        .context("Failed to insert a new concert")?;
+    let response = HttpResponse::Created()
+        .insert_header(ETag(entity_tag(concert.version)))
+        .json(&concert);
+    let response = idempotency.save_response(&mut transaction, response).await?;
 
     transaction.commit().await.context("Failed to commit transaction")?;
+    count_created(Concert::ENTITY_TYPE, 1);
+    cache.invalidate().await;
 
-    Ok(HttpResponse::Created().json(&concert))
+    Ok(response)
 }
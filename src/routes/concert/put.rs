@@ -1,19 +1,32 @@
+use crate::cache::ResponseCache;
 use crate::domain::{
+    Actor,
     Concert,
+    DomainError,
     UpdateConcert,
     ConcertDate,
     ConcertState,
     ConcertCity,
     ConcertVenue,
     ConcertCountry,
+    ValidationErrors,
 };
-use crate::routes::error_chain_fmt;
-use actix_web::{web, HttpResponse, ResponseError};
+use crate::routes::{
+    entity_tag,
+    error_chain_fmt,
+    expected_versions,
+    PreconditionError,
+    ProblemCode,
+    ProblemDetails,
+};
+use actix_web::http::header::ETag;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
 use sqlx::PgPool;
 use reqwest::StatusCode;
 use anyhow::Context;
 
-#[derive(serde::Deserialize)]
+#[derive(serde::Deserialize, utoipa::ToSchema, async_graphql::InputObject)]
+#[graphql(name = "UpdateConcertInput")]
 pub struct UpdateConcertRequest {
     pub id: uuid::Uuid,
     pub artist_id: uuid::Uuid,
@@ -25,35 +38,53 @@ pub struct UpdateConcertRequest {
 }
 
 impl TryFrom<UpdateConcertRequest> for UpdateConcert {
-    type Error = String;
+    type Error = ValidationErrors;
 
     fn try_from(
         request: UpdateConcertRequest
     ) -> Result<Self, Self::Error> {
-        let venue = ConcertVenue::parse(request.venue)?;
-        let city = ConcertCity::parse(request.city)?;
-        let state = ConcertState::parse(request.state)?;
-        let date = ConcertDate::parse(request.date)?;
-        let country = ConcertCountry::parse(request.country)?;
-
-        Ok(Self {
-            id: request.id,
-            artist_id: request.artist_id,
-            venue,
-            city,
-            state,
-            date,
-            country,
-        })
+        let mut errors = ValidationErrors::default();
+        let venue = errors.check("venue", ConcertVenue::parse(request.venue));
+        let city = errors.check("city", ConcertCity::parse(request.city));
+        let state = errors.check("state", ConcertState::parse(request.state));
+        let date = errors.check("date", ConcertDate::parse(request.date));
+        let country = errors.check("country", ConcertCountry::parse(request.country));
+
+        match (venue, city, state, date, country) {
+            (Some(venue), Some(city), Some(state), Some(date), Some(country)) => Ok(Self {
+                id: request.id,
+                artist_id: request.artist_id,
+                venue,
+                city,
+                state,
+                date,
+                country,
+            }),
+            _ => Err(errors),
+        }
     }
 }
 
+#[derive(serde::Deserialize, Debug, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UpdateConcertParameters {
+    /// Save the concert even if it then looks like a duplicate
+    #[serde(default)]
+    pub force: bool,
+}
+
 #[derive(thiserror::Error)]
 pub enum UpdateConcertError { 
     #[error("{0}")]
-    ValidationError(String),
+    ValidationError(ValidationErrors),
+    #[error("This concert looks like a duplicate of concert {0}")]
+    DuplicateError(uuid::Uuid),
     #[error(transparent)]
     Unexpected(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
+    #[error(transparent)]
+    PreconditionError(#[from] PreconditionError),
 }
 
 impl std::fmt::Debug for UpdateConcertError {
@@ -66,38 +97,98 @@ impl ResponseError for UpdateConcertError {
     fn status_code(&self) -> StatusCode {
         match self {
             UpdateConcertError::ValidationError(_) => StatusCode::BAD_REQUEST,
+            UpdateConcertError::DuplicateError(_) => StatusCode::CONFLICT,
             UpdateConcertError::Unexpected(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            UpdateConcertError::DomainError(e) => e.status_code(),
+            UpdateConcertError::PreconditionError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            UpdateConcertError::ValidationError(errors) => ProblemDetails::validation(errors).into(),
+            UpdateConcertError::DuplicateError(existing_id) => ProblemDetails {
+                existing_id: Some(*existing_id),
+                ..ProblemDetails::new(
+                    self.status_code(),
+                    ProblemCode::DuplicateConcert,
+                    self.to_string(),
+                )
+            }
+            .into(),
+            UpdateConcertError::Unexpected(_) => ProblemDetails::unexpected().into(),
+            UpdateConcertError::DomainError(e) => e.error_response(),
+            UpdateConcertError::PreconditionError(e) => e.error_response(),
         }
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/concerts/{id}",
+    tag = "concerts",
+    params(
+        ("id" = Uuid, Path, description = "The concert id"),
+        ("If-Match" = String, Header, description = "The `ETag` of the version being updated, or `*`"),
+        UpdateConcertParameters,
+    ),
+    request_body = UpdateConcertRequest,
+    responses(
+        (status = 200, description = "The concert was updated", body = Concert,
+            headers(("ETag" = String, description = "The entity tag of the new version"))),
+        (status = 400, description = "The payload failed validation or its id does not match the path", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Concert not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The updated concert looks like a duplicate of `existing_id`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 412, description = "The concert changed since the version in `If-Match`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "The artist does not exist", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 428, description = "The `If-Match` header is missing", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
 #[tracing::instrument(
     name = "Updating a concert in the database",
-    skip(pool, item)
+    skip(req, pool, item, cache)
 )]
 pub async fn update_concert(
+    req: HttpRequest,
     id: web::Path<uuid::Uuid>,
     item: web::Json<UpdateConcertRequest>,
-    pool: web::Data<PgPool>
+    parameters: web::Query<UpdateConcertParameters>,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
 ) -> Result<HttpResponse, UpdateConcertError> {
     let concert = UpdateConcert::try_from(item.into_inner())
         .map_err(UpdateConcertError::ValidationError)?;
 
     if concert.id != *id {
-        return Err(UpdateConcertError::ValidationError(
-            "The object id does not match the id in the URL".to_string()
-        ));
+        return Err(UpdateConcertError::ValidationError(ValidationErrors::field(
+            "id",
+            "The object id does not match the id in the URL",
+        )));
     }
 
+    let expected_versions = expected_versions(&req)?;
+
     let mut transaction = pool.begin()
         .await
         .context("Failed to acquire a Postgres connection from the pool")?;
 
-    let result = Concert::update(&concert, &mut transaction)
-        .await
-        .context("Failed to update the concert in the database")?;
+    if !parameters.force {
+        let duplicate = Concert::find_likely_duplicate_of_update(&concert, &mut transaction)
+            .await
+            .context("Failed to look for a duplicate concert")?;
+        if let Some(duplicate) = duplicate {
+            return Err(UpdateConcertError::DuplicateError(duplicate.id));
+        }
+    }
+
+    let result = Concert::update(&concert, expected_versions.as_deref(), &actor, &mut transaction).await?;
 
     transaction.commit().await.context("Failed to commit the transaction")?;
+    cache.invalidate().await;
 
-    Ok(HttpResponse::Ok().json(result))
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(result.version)))
+        .json(result))
 }
@@ -0,0 +1,86 @@
+use actix_web::http::header::{CacheControl, CacheDirective, ContentType};
+use actix_web::{web, HttpResponse};
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "docs",
+    responses(
+        (status = 200, description = "This OpenAPI document", content_type = "application/json"),
+    )
+)]
+pub async fn openapi_json(openapi: web::Data<utoipa::openapi::OpenApi>) -> HttpResponse {
+    HttpResponse::Ok().json(openapi.get_ref())
+}
+
+#[utoipa::path(
+    get,
+    path = "/docs",
+    tag = "docs",
+    responses(
+        (status = 200, description = "Interactive documentation rendered by Swagger UI", content_type = "text/html"),
+    )
+)]
+pub async fn api_docs() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta http-equiv="content-type" content="text/html; charset=utf-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+    <title>All Bands API</title>
+    <link rel="stylesheet" href="/docs/swagger-ui.css">
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="/docs/swagger-ui-bundle.js"></script>
+    <script>
+        window.onload = () => {
+            window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+        };
+    </script>
+</body>
+</html>
+"##)
+}
+
+// Swagger UI is vendored in `static/swagger-ui` and built into the binary,
+// so that the documentation works without reaching a CDN.
+const SWAGGER_UI_BUNDLE: &str = include_str!("../../../static/swagger-ui/swagger-ui-bundle.js");
+const SWAGGER_UI_CSS: &str = include_str!("../../../static/swagger-ui/swagger-ui.css");
+
+/// Assets only change with the binary, so browsers may keep them for a day.
+fn asset(content_type: &str, body: &'static str) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(CacheControl(vec![
+            CacheDirective::Public,
+            CacheDirective::MaxAge(86400),
+        ]))
+        .body(body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/docs/swagger-ui-bundle.js",
+    tag = "docs",
+    responses(
+        (status = 200, description = "The Swagger UI script `/docs` loads", content_type = "text/javascript"),
+    )
+)]
+pub async fn swagger_ui_bundle() -> HttpResponse {
+    asset("text/javascript; charset=utf-8", SWAGGER_UI_BUNDLE)
+}
+
+#[utoipa::path(
+    get,
+    path = "/docs/swagger-ui.css",
+    tag = "docs",
+    responses(
+        (status = 200, description = "The Swagger UI stylesheet `/docs` loads", content_type = "text/css"),
+    )
+)]
+pub async fn swagger_ui_css() -> HttpResponse {
+    asset("text/css; charset=utf-8", SWAGGER_UI_CSS)
+}
@@ -0,0 +1,229 @@
+use crate::background::BackgroundTasks;
+use crate::domain::{Artist, CatalogueFilter, Concert};
+use actix_web::http::header::{ContentDisposition, ContentType, DispositionParam, DispositionType};
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use futures_util::TryStreamExt;
+use sqlx::PgPool;
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::Instrument;
+
+#[derive(serde::Deserialize, Clone, Copy, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> ContentType {
+        match self {
+            Self::Jsonl => ContentType(
+                "application/jsonl".parse().expect("Failed to parse the JSON Lines mime type"),
+            ),
+            Self::Csv => ContentType(
+                "text/csv".parse().expect("Failed to parse the CSV mime type"),
+            ),
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct ExportParameters {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(flatten)]
+    pub filter: CatalogueFilter,
+}
+
+/// A single line of the export. Artists come first so that every concert
+/// refers to an artist that has already been written.
+#[derive(serde::Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ExportRecord {
+    Artist(Artist),
+    Concert(Concert),
+}
+
+/// The flat shape used for CSV exports, where both record types share a
+/// single header.
+#[derive(serde::Serialize, Default)]
+struct CsvRecord {
+    record_type: &'static str,
+    id: uuid::Uuid,
+    name: Option<String>,
+    sort_name: Option<String>,
+    disambiguation: Option<String>,
+    artist_id: Option<uuid::Uuid>,
+    venue: Option<String>,
+    city: Option<String>,
+    state: Option<String>,
+    country: Option<String>,
+    date: Option<chrono::NaiveDate>,
+}
+
+impl From<ExportRecord> for CsvRecord {
+    fn from(record: ExportRecord) -> Self {
+        match record {
+            ExportRecord::Artist(artist) => Self {
+                record_type: "artist",
+                id: artist.id,
+                name: Some(artist.name),
+                sort_name: Some(artist.sort_name),
+                disambiguation: Some(artist.disambiguation),
+                ..Default::default()
+            },
+            ExportRecord::Concert(concert) => Self {
+                record_type: "concert",
+                id: concert.id,
+                artist_id: Some(concert.artist_id),
+                venue: Some(concert.venue),
+                city: Some(concert.city),
+                state: concert.state,
+                country: Some(concert.country),
+                date: Some(concert.date),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+type Chunk = Result<web::Bytes, anyhow::Error>;
+
+#[utoipa::path(
+    get,
+    path = "/export",
+    tag = "export",
+    params(
+        ("format" = Option<String>, Query, description = "`jsonl` (default) or `csv`"),
+        ("artist_id" = Option<Uuid>, Query, description = "Only export this artist"),
+        ("from" = Option<NaiveDate>, Query, description = "Only export concerts on or after this date"),
+        ("to" = Option<NaiveDate>, Query, description = "Only export concerts on or before this date"),
+    ),
+    responses(
+        (status = 200, description = "A streamed export of artists followed by concerts", content_type = "application/jsonl"),
+    )
+)]
+#[tracing::instrument(
+    name = "Exporting the catalogue",
+    skip(pool, tasks)
+)]
+pub async fn export_catalogue(
+    parameters: web::Query<ExportParameters>,
+    pool: web::Data<PgPool>,
+    tasks: web::Data<BackgroundTasks>,
+) -> HttpResponse {
+    let ExportParameters { format, filter } = parameters.into_inner();
+    let pool = pool.into_inner();
+
+    // Rows are pushed through a bounded channel so that a slow client applies
+    // back-pressure on the database cursor instead of buffering the catalogue.
+    let (sender, receiver) = tokio::sync::mpsc::channel::<Chunk>(64);
+    // Tracked, so that shutdown waits for the export before closing the pool.
+    tasks.spawn(
+        async move {
+            if let Err(e) = write_export(format, &filter, &pool, &sender).await {
+                tracing::error!(error.cause_chain = ?e, "Failed to export the catalogue");
+                let _ = sender.send(Err(e)).await;
+            }
+        }
+        .instrument(tracing::Span::current()),
+    );
+
+    HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format!(
+                "allbands-export.{}",
+                format.extension()
+            ))],
+        })
+        .streaming(ReceiverStream::new(receiver))
+}
+
+async fn write_export(
+    format: ExportFormat,
+    filter: &CatalogueFilter,
+    pool: &PgPool,
+    sender: &Sender<Chunk>,
+) -> Result<(), anyhow::Error> {
+    if let ExportFormat::Csv = format {
+        let mut writer = csv::Writer::from_writer(vec![]);
+        writer
+            .write_record([
+                "record_type", "id", "name", "sort_name", "disambiguation", "artist_id",
+                "venue", "city", "state", "country", "date",
+            ])
+            .context("Failed to write the CSV header")?;
+        let header = writer.into_inner().context("Failed to write the CSV header")?;
+        if sender.send(Ok(header.into())).await.is_err() {
+            return Ok(());
+        }
+    }
+
+    // Read both tables from one snapshot, so that a concert created while the
+    // artists stream can't refer to an artist missing from the export.
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+    sqlx::query!("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut transaction)
+        .await
+        .context("Failed to start a repeatable read transaction")?;
+
+    let mut artists = Artist::stream(filter, &mut transaction);
+    while let Some(artist) = artists.try_next().await.context("Failed to read artists")? {
+        if !send(format, ExportRecord::Artist(artist), sender).await? {
+            return Ok(());
+        }
+    }
+    drop(artists);
+
+    let mut concerts = Concert::stream(filter, &mut transaction);
+    while let Some(concert) = concerts.try_next().await.context("Failed to read concerts")? {
+        if !send(format, ExportRecord::Concert(concert), sender).await? {
+            return Ok(());
+        }
+    }
+    drop(concerts);
+
+    transaction.commit().await.context("Failed to commit the transaction")?;
+    Ok(())
+}
+
+/// Encode a record and hand it to the response stream.
+///
+/// Returns `false` once the client has gone away.
+async fn send(
+    format: ExportFormat,
+    record: ExportRecord,
+    sender: &Sender<Chunk>,
+) -> Result<bool, anyhow::Error> {
+    let line = match format {
+        ExportFormat::Jsonl => {
+            let mut line = serde_json::to_vec(&record).context("Failed to encode a record")?;
+            line.push(b'\n');
+            line
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(vec![]);
+            writer
+                .serialize(CsvRecord::from(record))
+                .context("Failed to encode a record")?;
+            writer.into_inner().context("Failed to encode a record")?
+        }
+    };
+
+    Ok(sender.send(Ok(line.into())).await.is_ok())
+}
@@ -0,0 +1,16 @@
+use actix_web::{http::header::ContentType, HttpResponse};
+use async_graphql::http::GraphiQLSource;
+
+#[utoipa::path(
+    get,
+    path = "/graphql",
+    tag = "graphql",
+    responses(
+        (status = 200, description = "The GraphiQL explorer", content_type = "text/html"),
+    )
+)]
+pub async fn graphiql() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(GraphiQLSource::build().endpoint("/graphql").finish())
+}
@@ -0,0 +1,39 @@
+use crate::domain::Actor;
+use crate::graphql::{with_loaders, AppSchema, CreateLimits};
+use crate::rate_limit::{client_key, RateLimiter};
+use actix_web::{web, HttpRequest};
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+use sqlx::PgPool;
+
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    tag = "graphql",
+    request_body(
+        content = Object,
+        content_type = "application/json",
+        description = "A GraphQL request with `query`, and optionally `variables` and `operationName`"
+    ),
+    responses(
+        (status = 200, description = "The GraphQL response, including any errors", content_type = "application/json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Executing a GraphQL request",
+    skip(schema, request, pool, limiter, http_request)
+)]
+pub async fn graphql(
+    schema: web::Data<AppSchema>,
+    request: GraphQLRequest,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    limiter: web::Data<RateLimiter>,
+    http_request: HttpRequest,
+) -> GraphQLResponse {
+    let limits = CreateLimits::new(limiter.into_inner(), client_key(&http_request));
+    let request = with_loaders(request.into_inner(), &pool)
+        .data(actor)
+        .data(limits);
+
+    schema.execute(request).await.into()
+}
@@ -1,5 +1,13 @@
 use actix_web::HttpResponse;
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, description = "The application is running"),
+    )
+)]
 pub async fn health_check() -> HttpResponse {
     HttpResponse::Ok().finish()
 }
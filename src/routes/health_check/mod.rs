@@ -1,3 +1,5 @@
 mod get;
+mod ready;
 
 pub use get::*;
+pub use ready::*;
@@ -0,0 +1,144 @@
+use actix_web::{web, HttpResponse};
+use anyhow::Context;
+use redis::aio::ConnectionManager;
+use sqlx::PgPool;
+use std::future::Future;
+use std::time::Duration;
+
+/// How long a dependency has to answer before it is reported as down.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Up,
+    Down,
+}
+
+/// The outcome of checking one dependency.
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct DependencyCheck {
+    /// `postgres`, `redis` or `migrations`
+    pub name: String,
+    pub status: CheckStatus,
+    /// Why the dependency is down
+    pub error: Option<String>,
+}
+
+/// Whether the application can serve requests, and why not.
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct Readiness {
+    /// `up` only when every dependency is
+    pub status: CheckStatus,
+    /// The newest migration applied to the database, when it could be read
+    pub migration_version: Option<i64>,
+    pub checks: Vec<DependencyCheck>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "Postgres and Redis answer and the database is fully migrated", body = Readiness),
+        (status = 503, description = "At least one dependency is down", body = Readiness),
+    )
+)]
+#[tracing::instrument(name = "Readiness check", skip(pool, redis))]
+pub async fn readiness_check(
+    pool: web::Data<PgPool>,
+    redis: web::Data<ConnectionManager>,
+) -> HttpResponse {
+    let ((postgres, _), (redis, _), (mut migrations, migration_version)) = tokio::join!(
+        check("postgres", ping_postgres(&pool)),
+        check("redis", ping_redis(&redis)),
+        check("migrations", migration_version(&pool)),
+    );
+    let migration_version = migration_version.flatten();
+    if let Some(expected) = expected_migration_version() {
+        if migrations.status == CheckStatus::Up && migration_version < Some(expected) {
+            migrations.status = CheckStatus::Down;
+            migrations.error = Some(format!(
+                "The database is at migration {}, expected {}",
+                migration_version.map(|v| v.to_string()).unwrap_or_else(|| "none".into()),
+                expected,
+            ));
+        }
+    }
+
+    let checks = vec![postgres, redis, migrations];
+    let status = if checks.iter().all(|c| c.status == CheckStatus::Up) {
+        CheckStatus::Up
+    } else {
+        CheckStatus::Down
+    };
+    let readiness = Readiness {
+        status,
+        migration_version,
+        checks,
+    };
+
+    match status {
+        CheckStatus::Up => HttpResponse::Ok().json(readiness),
+        CheckStatus::Down => HttpResponse::ServiceUnavailable().json(readiness),
+    }
+}
+
+/// Run `probe` under `CHECK_TIMEOUT`, returning the check and whatever the
+/// probe found when it succeeded.
+async fn check<T>(
+    name: &str,
+    probe: impl Future<Output = Result<T, anyhow::Error>>,
+) -> (DependencyCheck, Option<T>) {
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, probe).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow::anyhow!("Timed out after {}ms", CHECK_TIMEOUT.as_millis())),
+    };
+    match result {
+        Ok(value) => (
+            DependencyCheck { name: name.to_string(), status: CheckStatus::Up, error: None },
+            Some(value),
+        ),
+        Err(e) => {
+            tracing::warn!(error = %format!("{:#}", e), "{} is not ready", name);
+            (
+                DependencyCheck { name: name.to_string(), status: CheckStatus::Down, error: Some(format!("{:#}", e)) },
+                None,
+            )
+        }
+    }
+}
+
+async fn ping_postgres(pool: &PgPool) -> Result<(), anyhow::Error> {
+    sqlx::query("SELECT 1")
+        .execute(pool)
+        .await
+        .context("Failed to query Postgres")?;
+    Ok(())
+}
+
+async fn ping_redis(connection: &ConnectionManager) -> Result<(), anyhow::Error> {
+    // A clone shares the connection.
+    let mut connection = connection.clone();
+    redis::cmd("PING")
+        .query_async::<_, String>(&mut connection)
+        .await
+        .context("Failed to ping Redis")?;
+    Ok(())
+}
+
+/// The newest migration applied to the database.
+async fn migration_version(pool: &PgPool) -> Result<Option<i64>, anyhow::Error> {
+    sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success")
+        .fetch_one(pool)
+        .await
+        .context("Failed to read the applied migrations")
+}
+
+/// The newest migration this build ships with.
+fn expected_migration_version() -> Option<i64> {
+    sqlx::migrate!("./migrations")
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+}
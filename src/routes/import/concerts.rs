@@ -0,0 +1,255 @@
+use crate::cache::ResponseCache;
+use crate::domain::{Actor, Artist, Audited, Concert, DomainError, NewConcert};
+use crate::metrics::count_created;
+use crate::routes::{error_chain_fmt, CreateConcertRequest, ProblemCode, ProblemDetails};
+use actix_web::{web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Default, PartialEq, Eq, Debug, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    /// Nothing is written unless every row is valid
+    #[default]
+    AllOrNothing,
+    /// Valid rows are written, invalid rows are reported and skipped
+    BestEffort,
+}
+
+#[derive(serde::Deserialize, Debug, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportParameters {
+    #[serde(default)]
+    pub mode: ImportMode,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A single line of the concerts spreadsheet.
+///
+/// The `artist` column accepts either an artist id or an exact artist name.
+#[derive(serde::Deserialize)]
+struct ConcertRow {
+    artist: String,
+    venue: String,
+    city: String,
+    state: String,
+    country: String,
+    date: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RowStatus {
+    Created,
+    Valid,
+    Invalid,
+    Skipped,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct RowReport {
+    pub row: usize,
+    pub status: RowStatus,
+    pub concert_id: Option<Uuid>,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub dry_run: bool,
+    pub committed: bool,
+    pub created: usize,
+    pub invalid: usize,
+    pub rows: Vec<RowReport>,
+}
+
+#[derive(thiserror::Error)]
+pub enum ImportConcertsError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
+}
+
+impl std::fmt::Debug for ImportConcertsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ImportConcertsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::ValidationError(_) => StatusCode::BAD_REQUEST,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::DomainError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::ValidationError(message) => {
+                ProblemDetails::new(self.status_code(), ProblemCode::InvalidPayload, message)
+            }
+            Self::UnexpectedError(_) => ProblemDetails::unexpected(),
+            Self::DomainError(e) => return e.error_response(),
+        }
+        .into()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/import/concerts",
+    tag = "import",
+    params(ImportParameters),
+    request_body(
+        content = String,
+        content_type = "text/csv",
+        description = "A header row followed by `artist,venue,city,state,country,date` rows"
+    ),
+    responses(
+        (status = 200, description = "The per-row import report", body = ImportReport),
+        (status = 400, description = "The CSV is malformed, or an all-or-nothing import had invalid rows", body = ImportReport),
+        (status = 404, description = "Imports are switched off", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 413, description = "The body is larger than `import.max_body_bytes`"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Importing concerts from CSV",
+    skip(body, pool, cache)
+)]
+pub async fn import_concerts(
+    parameters: web::Query<ImportParameters>,
+    body: web::Bytes,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, ImportConcertsError> {
+    let ImportParameters { mode, dry_run } = parameters.into_inner();
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(body.as_ref());
+    reader
+        .headers()
+        .map_err(|e| ImportConcertsError::ValidationError(format!("Invalid CSV header: {}", e)))?;
+
+    let mut artists: HashMap<String, Option<Uuid>> = HashMap::new();
+    let mut rows: Vec<(usize, Result<NewConcert, String>)> = Vec::new();
+
+    for (index, record) in reader.deserialize::<ConcertRow>().enumerate() {
+        // Row numbers match the spreadsheet, where the header is row 1
+        let row = index + 2;
+        let parsed = match record {
+            Ok(record) => validate_row(record, &mut artists, &pool).await?,
+            Err(e) => Err(format!("Malformed row: {}", e)),
+        };
+        rows.push((row, parsed));
+    }
+
+    let invalid = rows.iter().filter(|(_, parsed)| parsed.is_err()).count();
+    let rejected = mode == ImportMode::AllOrNothing && invalid > 0;
+    let write = !dry_run && !rejected;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+
+    let mut reports = Vec::with_capacity(rows.len());
+    for (row, parsed) in rows {
+        let report = match parsed {
+            Err(error) => RowReport {
+                row,
+                status: RowStatus::Invalid,
+                concert_id: None,
+                error: Some(error),
+            },
+            Ok(new_concert) if write => {
+                let concert = Concert::insert(&new_concert, &actor, &mut transaction).await?;
+                RowReport {
+                    row,
+                    status: RowStatus::Created,
+                    concert_id: Some(concert.id),
+                    error: None,
+                }
+            }
+            Ok(_) => RowReport {
+                row,
+                status: if dry_run { RowStatus::Valid } else { RowStatus::Skipped },
+                concert_id: None,
+                error: None,
+            },
+        };
+        reports.push(report);
+    }
+
+    let created = reports.iter().filter(|r| r.status == RowStatus::Created).count();
+    if write {
+        transaction.commit().await.context("Failed to commit the transaction")?;
+        count_created(Concert::ENTITY_TYPE, created);
+        cache.invalidate().await;
+    } else {
+        transaction.rollback().await.context("Failed to roll back the transaction")?;
+    }
+
+    let report = ImportReport {
+        mode,
+        dry_run,
+        committed: write,
+        created,
+        invalid,
+        rows: reports,
+    };
+
+    if rejected {
+        Ok(HttpResponse::BadRequest().json(report))
+    } else {
+        Ok(HttpResponse::Ok().json(report))
+    }
+}
+
+/// Resolve the artist of a row and run it through the same validation
+/// as `POST /concerts`.
+async fn validate_row(
+    record: ConcertRow,
+    artists: &mut HashMap<String, Option<Uuid>>,
+    pool: &PgPool,
+) -> Result<Result<NewConcert, String>, DomainError> {
+    let artist_id = match artists.get(&record.artist) {
+        Some(artist_id) => *artist_id,
+        None => {
+            let artist = match Uuid::parse_str(&record.artist) {
+                Ok(id) => Artist::find_by_id(id, pool).await,
+                Err(_) => Artist::find_by_name(&record.artist, pool).await.map_err(Into::into),
+            }?;
+            let artist_id = artist.map(|artist| artist.id);
+            artists.insert(record.artist.clone(), artist_id);
+            artist_id
+        }
+    };
+
+    let artist_id = match artist_id {
+        Some(artist_id) => artist_id,
+        None => return Ok(Err(format!("{} is not a known artist", record.artist))),
+    };
+
+    Ok(NewConcert::try_from(CreateConcertRequest {
+        artist_id,
+        venue: record.venue,
+        city: record.city,
+        state: record.state,
+        country: record.country,
+        date: record.date,
+    })
+    .map_err(|e| e.to_string()))
+}
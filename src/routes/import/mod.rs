@@ -0,0 +1,5 @@
+mod concerts;
+mod setlistfm;
+
+pub use concerts::*;
+pub use setlistfm::*;
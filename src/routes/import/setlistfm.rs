@@ -0,0 +1,354 @@
+use crate::cache::ResponseCache;
+use crate::domain::{
+    Actor,
+    Artist,
+    ArtistName,
+    Audited,
+    Concert,
+    DomainError,
+    NewArtist,
+    NewConcert,
+    NewSong,
+    Song,
+    SongTitle,
+};
+use crate::metrics::count_created;
+use crate::routes::{error_chain_fmt, CreateConcertRequest, ProblemCode, ProblemDetails};
+use actix_web::{web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Either a single setlist, as returned by `GET /1.0/setlist/{id}`, or a page
+/// of search results, as returned by `GET /1.0/search/setlists`.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+pub enum SetlistPayload {
+    Page { setlist: Vec<Setlist> },
+    Single(Box<Setlist>),
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Setlist {
+    pub id: Option<String>,
+    /// Formatted as `dd-MM-yyyy`
+    pub event_date: String,
+    pub artist: SetlistArtist,
+    pub venue: SetlistVenue,
+    #[serde(default)]
+    pub sets: SetlistSets,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetlistArtist {
+    pub mbid: Option<Uuid>,
+    pub name: String,
+    pub sort_name: Option<String>,
+    pub disambiguation: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetlistVenue {
+    pub name: String,
+    pub city: SetlistCity,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetlistCity {
+    pub name: String,
+    pub state: Option<String>,
+    pub state_code: Option<String>,
+    pub country: SetlistCountry,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetlistCountry {
+    pub code: String,
+    pub name: String,
+}
+
+#[derive(serde::Deserialize, Default)]
+pub struct SetlistSets {
+    #[serde(default)]
+    pub set: Vec<SetlistSet>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetlistSet {
+    pub name: Option<String>,
+    pub encore: Option<i32>,
+    #[serde(default)]
+    pub song: Vec<SetlistSong>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct SetlistSong {
+    pub name: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SetlistStatus {
+    Imported,
+    Duplicate,
+    Invalid,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct SetlistReport {
+    pub setlist_id: Option<String>,
+    pub status: SetlistStatus,
+    pub artist_id: Option<Uuid>,
+    pub concert_id: Option<Uuid>,
+    pub songs: usize,
+    pub error: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct SetlistImportReport {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub setlists: Vec<SetlistReport>,
+}
+
+#[derive(thiserror::Error)]
+pub enum ImportSetlistError {
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
+}
+
+impl std::fmt::Debug for ImportSetlistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for ImportSetlistError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::ValidationError(_) => StatusCode::BAD_REQUEST,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::DomainError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::ValidationError(message) => {
+                ProblemDetails::new(self.status_code(), ProblemCode::InvalidPayload, message)
+            }
+            Self::UnexpectedError(_) => ProblemDetails::unexpected(),
+            Self::DomainError(e) => return e.error_response(),
+        }
+        .into()
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/import/setlistfm",
+    tag = "import",
+    request_body(
+        content = Object,
+        content_type = "application/json",
+        description = "A setlist.fm setlist, or a page of setlist.fm search results"
+    ),
+    responses(
+        (status = 200, description = "The per-setlist import report", body = SetlistImportReport),
+        (status = 400, description = "The payload is not shaped like a setlist.fm response", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Imports are switched off", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 413, description = "The body is larger than `import.max_body_bytes`"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Importing setlist.fm setlists",
+    skip(body, pool, cache)
+)]
+pub async fn import_setlistfm(
+    body: web::Bytes,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, ImportSetlistError> {
+    let payload = serde_json::from_slice::<SetlistPayload>(&body).map_err(|e| {
+        ImportSetlistError::ValidationError(format!("Invalid setlist.fm payload: {}", e))
+    })?;
+    let setlists = match payload {
+        SetlistPayload::Page { setlist } => setlist,
+        SetlistPayload::Single(setlist) => vec![*setlist],
+    };
+
+    let mut reports = Vec::with_capacity(setlists.len());
+    for setlist in setlists {
+        let report = import_setlist(setlist, &actor, &pool).await?;
+        // Each setlist commits on its own, so an error further on must not
+        // leave the ones already imported out of the cached responses.
+        if report.status == SetlistStatus::Imported {
+            cache.invalidate().await;
+        }
+        reports.push(report);
+    }
+
+    let count = |status: SetlistStatus| reports.iter().filter(|r| r.status == status).count();
+    let report = SetlistImportReport {
+        imported: count(SetlistStatus::Imported),
+        duplicates: count(SetlistStatus::Duplicate),
+        invalid: count(SetlistStatus::Invalid),
+        setlists: reports,
+    };
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+/// Import a single setlist in its own transaction, so that an invalid
+/// setlist never leaves a half-created artist or concert behind. The report
+/// only names the artist once it is known to exist after the transaction.
+async fn import_setlist(
+    setlist: Setlist,
+    actor: &Actor,
+    pool: &PgPool,
+) -> Result<SetlistReport, ImportSetlistError> {
+    let mut report = SetlistReport {
+        setlist_id: setlist.id.clone(),
+        status: SetlistStatus::Invalid,
+        artist_id: None,
+        concert_id: None,
+        songs: 0,
+        error: None,
+    };
+
+    let date = match chrono::NaiveDate::parse_from_str(&setlist.event_date, "%d-%m-%Y") {
+        Ok(date) => date,
+        Err(e) => {
+            report.error = Some(format!("Error parsing event date: {}", e));
+            return Ok(report);
+        }
+    };
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")?;
+
+    let (artist_id, artist_created) = match resolve_artist(setlist.artist, actor, &mut transaction).await? {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            report.error = Some(e);
+            return Ok(report);
+        }
+    };
+
+    let city = setlist.venue.city;
+    let new_concert = match NewConcert::try_from(CreateConcertRequest {
+        artist_id,
+        venue: setlist.venue.name,
+        city: city.name,
+        state: city.state_code.or(city.state).unwrap_or_default(),
+        country: city.country.name,
+        date: date.format("%Y-%m-%d").to_string(),
+    }) {
+        Ok(new_concert) => new_concert,
+        Err(e) => {
+            report.error = Some(e.to_string());
+            return Ok(report);
+        }
+    };
+
+    let duplicate = Concert::find_likely_duplicate(&new_concert, &mut transaction)
+        .await
+        .context("Failed to look for an existing concert")?;
+    if let Some(concert) = duplicate {
+        // The artist has a concert already, so it wasn't created just now.
+        report.artist_id = Some(artist_id);
+        report.status = SetlistStatus::Duplicate;
+        report.concert_id = Some(concert.id);
+        return Ok(report);
+    }
+
+    let concert = Concert::insert(&new_concert, actor, &mut transaction).await?;
+
+    let sets = setlist.sets.set.into_iter().enumerate();
+    for (set_index, set) in sets {
+        for (song_index, song) in set.song.into_iter().enumerate() {
+            let title = match SongTitle::parse(song.name) {
+                Ok(title) => title,
+                Err(e) => {
+                    report.error = Some(e);
+                    return Ok(report);
+                }
+            };
+            let new_song = NewSong {
+                concert_id: concert.id,
+                set_number: set_index as i32 + 1,
+                set_name: set.name.clone(),
+                encore: set.encore,
+                position: song_index as i32 + 1,
+                title,
+            };
+            Song::insert(&new_song, &mut transaction)
+                .await
+                .map_err(DomainError::from)?;
+            report.songs += 1;
+        }
+    }
+
+    transaction.commit().await.context("Failed to commit the transaction")?;
+    if artist_created {
+        count_created(Artist::ENTITY_TYPE, 1);
+    }
+    count_created(Concert::ENTITY_TYPE, 1);
+
+    report.status = SetlistStatus::Imported;
+    report.artist_id = Some(artist_id);
+    report.concert_id = Some(concert.id);
+    Ok(report)
+}
+
+/// Find the artist of a setlist by MBID, then by name, creating it when
+/// neither matches, and return its id and whether it was created.
+async fn resolve_artist(
+    artist: SetlistArtist,
+    actor: &Actor,
+    transaction: &mut Transaction<'_, Postgres>,
+) -> Result<Result<(Uuid, bool), String>, ImportSetlistError> {
+    if let Some(mbid) = artist.mbid {
+        let existing = Artist::find_by_mbid(mbid, &mut *transaction)
+            .await
+            .context("Failed to look up the artist by MBID")?;
+        if let Some(existing) = existing {
+            return Ok(Ok((existing.id, false)));
+        }
+    }
+
+    let existing = Artist::find_by_name(&artist.name, &mut *transaction)
+        .await
+        .context("Failed to look up the artist by name")?;
+    if let Some(existing) = existing {
+        return Ok(Ok((existing.id, false)));
+    }
+
+    let name = match ArtistName::parse(artist.name) {
+        Ok(name) => name,
+        Err(e) => return Ok(Err(e)),
+    };
+    let new_artist = NewArtist {
+        sort_name: artist.sort_name.unwrap_or_else(|| name.as_ref().to_string()),
+        disambiguation: artist.disambiguation.unwrap_or_default(),
+        mbid: artist.mbid,
+        name,
+    };
+
+    let created = Artist::insert(&new_artist, actor, transaction).await?;
+
+    Ok(Ok((created.id, true)))
+}
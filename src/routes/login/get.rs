@@ -0,0 +1,23 @@
+use crate::utils::render;
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use actix_web_flash_messages::IncomingFlashMessages;
+use askama::Template;
+
+#[derive(Template)]
+#[template(path = "login.html")]
+struct LoginPage {
+    flash_messages: IncomingFlashMessages,
+}
+
+#[utoipa::path(
+    get,
+    path = "/login",
+    tag = "moderation",
+    responses(
+        (status = 200, description = "The moderator login form", content_type = "text/html"),
+    )
+)]
+pub async fn login_form(flash_messages: IncomingFlashMessages) -> Result<HttpResponse, actix_web::Error> {
+    render(StatusCode::OK, &LoginPage { flash_messages })
+}
@@ -0,0 +1,79 @@
+use crate::authentication::{validate_credentials, AuthError, Credentials};
+use crate::routes::error_chain_fmt;
+use crate::session_state::TypedSession;
+use crate::utils::see_other;
+use actix_web::error::InternalError;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use secrecy::Secret;
+use sqlx::PgPool;
+
+#[derive(serde::Deserialize, utoipa::ToSchema)]
+pub struct LoginForm {
+    username: String,
+    #[schema(value_type = String, format = Password)]
+    password: Secret<String>,
+}
+
+#[derive(thiserror::Error)]
+pub enum LoginError {
+    #[error("Authentication failed")]
+    AuthError(#[source] anyhow::Error),
+    #[error("Something went wrong")]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for LoginError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "moderation",
+    request_body(content = LoginForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "Logged in, redirecting to `/moderation/submissions`; or failed, redirecting back to `/login`"),
+    )
+)]
+#[tracing::instrument(
+    skip(form, pool, session),
+    fields(username=tracing::field::Empty, moderator_id=tracing::field::Empty)
+)]
+pub async fn login(
+    form: web::Form<LoginForm>,
+    pool: web::Data<PgPool>,
+    session: TypedSession,
+) -> Result<HttpResponse, InternalError<LoginError>> {
+    let credentials = Credentials {
+        username: form.0.username,
+        password: form.0.password,
+    };
+    tracing::Span::current().record("username", tracing::field::display(&credentials.username));
+
+    match validate_credentials(credentials, &pool).await {
+        Ok(moderator_id) => {
+            tracing::Span::current().record("moderator_id", tracing::field::display(&moderator_id));
+            session.renew();
+            session
+                .insert_moderator_id(moderator_id)
+                .map_err(|e| login_redirect(LoginError::UnexpectedError(e.into())))?;
+            Ok(see_other("/moderation/submissions"))
+        }
+        Err(e) => {
+            let e = match e {
+                AuthError::InvalidCredentials(_) => LoginError::AuthError(e.into()),
+                AuthError::UnexpectedError(_) => LoginError::UnexpectedError(e.into()),
+            };
+            Err(login_redirect(e))
+        }
+    }
+}
+
+/// Redirect to the login page with an error message.
+fn login_redirect(e: LoginError) -> InternalError<LoginError> {
+    FlashMessage::error(e.to_string()).send();
+    InternalError::from_response(e, see_other("/login"))
+}
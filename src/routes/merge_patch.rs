@@ -0,0 +1,43 @@
+//! Helpers for RFC 7396 JSON merge patches, where a member that is absent
+//! leaves a field unchanged and a member set to `null` removes it.
+use crate::domain::ValidationErrors;
+use serde::{Deserialize, Deserializer};
+
+/// Deserialize a merge patch member into `None` when absent and `Some(None)`
+/// when `null`. Fields using it need `#[serde(default)]` as well.
+pub fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Validate a member for a field that may be changed but not removed.
+pub fn required<V, T>(
+    errors: &mut ValidationErrors,
+    field: &str,
+    value: Option<Option<V>>,
+    parse: impl FnOnce(V) -> Result<T, String>,
+) -> Option<T> {
+    match value? {
+        Some(value) => errors.check(field, parse(value)),
+        None => {
+            errors.push(field, format!("{} cannot be removed", field));
+            None
+        }
+    }
+}
+
+/// Validate a member for a field that may be removed with `null`.
+pub fn optional<V, T>(
+    errors: &mut ValidationErrors,
+    field: &str,
+    value: Option<Option<V>>,
+    parse: impl FnOnce(V) -> Result<T, String>,
+) -> Option<Option<T>> {
+    match value? {
+        Some(value) => errors.check(field, parse(value)).map(Some),
+        None => Some(None),
+    }
+}
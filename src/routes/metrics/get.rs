@@ -0,0 +1,25 @@
+use crate::metrics::observe_pool;
+use crate::utils::e500;
+use actix_web::{web, HttpResponse};
+use prometheus::{Encoder, TextEncoder};
+use sqlx::PgPool;
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "metrics",
+    responses(
+        (status = 200, description = "Every metric, in the Prometheus text format", content_type = "text/plain"),
+    )
+)]
+pub async fn metrics(pool: web::Data<PgPool>) -> Result<HttpResponse, actix_web::Error> {
+    observe_pool(&pool);
+
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    encoder.encode(&prometheus::gather(), &mut body).map_err(e500)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(body))
+}
@@ -1,7 +1,32 @@
+mod actor;
+mod admin;
 mod artist;
 mod concert;
+mod docs;
+mod export;
+mod graphql;
 mod health_check;
+mod import;
+mod login;
+mod merge_patch;
+mod metrics;
+mod moderation;
+mod preconditions;
+mod problem;
+mod submissions;
 
+pub use admin::*;
 pub use artist::*;
 pub use health_check::*;
 pub use concert::*;
+pub use docs::*;
+pub use export::*;
+pub use graphql::*;
+pub use import::*;
+pub use login::*;
+pub use merge_patch::*;
+pub use metrics::*;
+pub use moderation::*;
+pub use preconditions::*;
+pub use problem::*;
+pub use submissions::*;
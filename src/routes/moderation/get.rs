@@ -0,0 +1,84 @@
+use crate::authentication::Moderator;
+use crate::domain::{Submission, SubmissionKind};
+use crate::session_state::TypedSession;
+use crate::utils::{e500, render};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::IncomingFlashMessages;
+use askama::Template;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(Template)]
+#[template(path = "moderation/submissions.html")]
+struct ModerationQueue {
+    username: String,
+    csrf_token: String,
+    flash_messages: IncomingFlashMessages,
+    submissions: Vec<PendingSubmission>,
+}
+
+/// A submission as the queue shows it.
+struct PendingSubmission {
+    id: Uuid,
+    kind: String,
+    submitted_by: String,
+    created_at: String,
+    payload: String,
+}
+
+impl TryFrom<Submission> for PendingSubmission {
+    type Error = serde_json::Error;
+
+    fn try_from(submission: Submission) -> Result<Self, Self::Error> {
+        let kind = match submission.kind {
+            SubmissionKind::NewConcert => "New concert".to_string(),
+            SubmissionKind::UpdateArtist => format!(
+                "Update of artist {}",
+                submission.target_id.map(|id| id.to_string()).unwrap_or_default()
+            ),
+        };
+        Ok(Self {
+            id: submission.id,
+            kind,
+            submitted_by: submission.submitted_by,
+            created_at: submission.created_at.format("%Y-%m-%d %H:%M").to_string(),
+            payload: serde_json::to_string_pretty(&submission.payload)?,
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/moderation/submissions",
+    tag = "moderation",
+    responses(
+        (status = 200, description = "The pending submissions, with forms to approve or reject them", content_type = "text/html"),
+        (status = 303, description = "Not logged in, redirecting to `/login`"),
+    )
+)]
+#[tracing::instrument(
+    name = "List pending submissions",
+    skip(session, flash_messages, pool)
+)]
+pub async fn moderation_queue(
+    moderator: Moderator,
+    session: TypedSession,
+    flash_messages: IncomingFlashMessages,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let submissions = Submission::find_pending(&pool)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(PendingSubmission::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(e500)?;
+
+    render(StatusCode::OK, &ModerationQueue {
+        username: moderator.username,
+        csrf_token: session.csrf_token().map_err(e500)?,
+        flash_messages,
+        submissions,
+    })
+}
@@ -0,0 +1,27 @@
+use crate::authentication::{reject_forged_requests, CsrfForm, Moderator};
+use crate::session_state::TypedSession;
+use crate::utils::see_other;
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+
+#[utoipa::path(
+    post,
+    path = "/logout",
+    tag = "moderation",
+    request_body(content = CsrfForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "Logged out, redirecting to `/login`"),
+        (status = 403, description = "The form did not carry the session's CSRF token"),
+    )
+)]
+#[tracing::instrument(name = "Logging out", skip(session, form))]
+pub async fn log_out(
+    moderator: Moderator,
+    session: TypedSession,
+    form: web::Form<CsrfForm>,
+) -> Result<HttpResponse, actix_web::Error> {
+    reject_forged_requests(&session, &form.csrf_token)?;
+    session.log_out();
+    FlashMessage::info("You have successfully logged out.").send();
+    Ok(see_other("/login"))
+}
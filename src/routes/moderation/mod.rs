@@ -0,0 +1,7 @@
+mod get;
+mod logout;
+mod review;
+
+pub use get::*;
+pub use logout::*;
+pub use review::*;
@@ -0,0 +1,216 @@
+use crate::authentication::{reject_forged_requests, CsrfForm, Moderator};
+use crate::cache::ResponseCache;
+use crate::domain::{
+    Actor,
+    Artist,
+    Audited,
+    Concert,
+    DomainError,
+    NewConcert,
+    Submission,
+    SubmissionKind,
+    SubmissionStatus,
+    UpdateArtist,
+};
+use crate::metrics::count_created;
+use crate::routes::{BodyData, CreateConcertRequest};
+use crate::session_state::TypedSession;
+use crate::utils::{e500, see_other};
+use actix_web::{web, HttpResponse};
+use actix_web_flash_messages::FlashMessage;
+use anyhow::Context;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Why an approved submission could not be applied.
+#[derive(thiserror::Error, Debug)]
+enum ApplyError {
+    /// Shown to the moderator; the submission stays pending
+    #[error("{0}")]
+    Rejected(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl From<DomainError> for ApplyError {
+    fn from(e: DomainError) -> Self {
+        match e {
+            DomainError::Conflict(message) | DomainError::InvalidReference(message) => {
+                Self::Rejected(message)
+            }
+            DomainError::NotFound => Self::Rejected("The artist no longer exists".into()),
+            DomainError::VersionMismatch => {
+                Self::Rejected("The artist changed since this was submitted".into())
+            }
+            DomainError::Unexpected(e) => Self::UnexpectedError(e.into()),
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/moderation/submissions/{id}/approve",
+    tag = "moderation",
+    params(
+        ("id" = Uuid, Path, description = "The submission id"),
+    ),
+    request_body(content = CsrfForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "Redirecting to `/moderation/submissions`, with a message saying whether the change was applied"),
+        (status = 403, description = "The form did not carry the session's CSRF token"),
+    )
+)]
+#[tracing::instrument(
+    name = "Approving a submission",
+    skip(session, form, pool, cache)
+)]
+pub async fn approve_submission(
+    id: web::Path<Uuid>,
+    moderator: Moderator,
+    session: TypedSession,
+    form: web::Form<CsrfForm>,
+    pool: web::Data<PgPool>,
+    cache: web::Data<ResponseCache>,
+) -> Result<HttpResponse, actix_web::Error> {
+    reject_forged_requests(&session, &form.csrf_token)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")
+        .map_err(e500)?;
+
+    let submission = match Submission::lock_pending(*id, &mut transaction).await {
+        Ok(submission) => submission,
+        Err(DomainError::NotFound) => return Ok(already_reviewed()),
+        Err(e) => return Err(e500(e)),
+    };
+
+    // Attribute the change to whoever proposed it, and to who let it in.
+    let actor = Actor::new(format!(
+        "{} (approved by {})",
+        submission.submitted_by, moderator.username
+    ));
+    match apply(&submission, &actor, &mut transaction).await {
+        Ok(result_id) => {
+            Submission::review(
+                submission.id,
+                SubmissionStatus::Approved,
+                &moderator.username,
+                Some(result_id),
+                &mut transaction,
+            )
+            .await
+            .map_err(e500)?;
+            transaction
+                .commit()
+                .await
+                .context("Failed to commit the transaction")
+                .map_err(e500)?;
+            if let SubmissionKind::NewConcert = submission.kind {
+                count_created(Concert::ENTITY_TYPE, 1);
+            }
+            cache.invalidate().await;
+            FlashMessage::info("The submission was approved.").send();
+        }
+        Err(ApplyError::Rejected(reason)) => {
+            FlashMessage::error(format!("The submission could not be applied: {}", reason)).send();
+        }
+        Err(e) => return Err(e500(e)),
+    }
+
+    Ok(see_other("/moderation/submissions"))
+}
+
+#[utoipa::path(
+    post,
+    path = "/moderation/submissions/{id}/reject",
+    tag = "moderation",
+    params(
+        ("id" = Uuid, Path, description = "The submission id"),
+    ),
+    request_body(content = CsrfForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 303, description = "Redirecting to `/moderation/submissions`"),
+        (status = 403, description = "The form did not carry the session's CSRF token"),
+    )
+)]
+#[tracing::instrument(
+    name = "Rejecting a submission",
+    skip(session, form, pool)
+)]
+pub async fn reject_submission(
+    id: web::Path<Uuid>,
+    moderator: Moderator,
+    session: TypedSession,
+    form: web::Form<CsrfForm>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    reject_forged_requests(&session, &form.csrf_token)?;
+
+    let mut transaction = pool
+        .begin()
+        .await
+        .context("Failed to acquire a Postgres connection from the pool")
+        .map_err(e500)?;
+
+    match Submission::lock_pending(*id, &mut transaction).await {
+        Ok(_) => {}
+        Err(DomainError::NotFound) => return Ok(already_reviewed()),
+        Err(e) => return Err(e500(e)),
+    };
+    Submission::review(*id, SubmissionStatus::Rejected, &moderator.username, None, &mut transaction)
+        .await
+        .map_err(e500)?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to commit the transaction")
+        .map_err(e500)?;
+
+    FlashMessage::info("The submission was rejected.").send();
+    Ok(see_other("/moderation/submissions"))
+}
+
+fn already_reviewed() -> HttpResponse {
+    FlashMessage::error("The submission does not exist or was already reviewed.").send();
+    see_other("/moderation/submissions")
+}
+
+/// Apply a submission through the same paths as a direct edit, returning the
+/// id of the concert or artist it created or updated.
+async fn apply(
+    submission: &Submission,
+    actor: &Actor,
+    transaction: &mut Transaction<'static, Postgres>,
+) -> Result<Uuid, ApplyError> {
+    let payload = submission.payload.clone();
+    match submission.kind {
+        SubmissionKind::NewConcert => {
+            let request = serde_json::from_value::<CreateConcertRequest>(payload)
+                .context("Failed to deserialize the submitted concert")?;
+            let new_concert = NewConcert::try_from(request)
+                .map_err(|e| ApplyError::Rejected(e.to_string()))?;
+            let duplicate = Concert::find_likely_duplicate(&new_concert, transaction)
+                .await
+                .context("Failed to look for a duplicate concert")?;
+            if let Some(duplicate) = duplicate {
+                return Err(ApplyError::Rejected(format!(
+                    "This concert looks like a duplicate of concert {}",
+                    duplicate.id
+                )));
+            }
+            let concert = Concert::insert(&new_concert, actor, transaction).await?;
+            Ok(concert.id)
+        }
+        SubmissionKind::UpdateArtist => {
+            let request = serde_json::from_value::<BodyData>(payload)
+                .context("Failed to deserialize the submitted artist")?;
+            let update = UpdateArtist::try_from(request)
+                .map_err(|e| ApplyError::Rejected(e.to_string()))?;
+            let expected_versions = submission.target_version.map(|version| vec![version]);
+            let artist = Artist::update(&update, expected_versions.as_deref(), actor, transaction).await?;
+            Ok(artist.id)
+        }
+    }
+}
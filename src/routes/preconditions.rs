@@ -0,0 +1,99 @@
+use crate::routes::{ProblemCode, ProblemDetails};
+use actix_web::http::header::{self, EntityTag, Header, IfMatch, IfNoneMatch};
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+
+/// The strong entity tag of an artist or concert at `version`.
+pub fn entity_tag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+/// The version a tag was generated from.
+///
+/// Tags are the version, optionally followed by `-` and a digest of related
+/// records, as on the artist dashboard. Only the version matters when
+/// checking `If-Match`.
+fn tag_version(tag: &EntityTag) -> Option<i32> {
+    if tag.weak {
+        return None;
+    }
+
+    tag.tag().split('-').next()?.parse().ok()
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum PreconditionError {
+    #[error("An If-Match header with the entity tag of the current version is required")]
+    Missing,
+    #[error("The If-Match header does not name a version of this record")]
+    Failed,
+}
+
+impl ResponseError for PreconditionError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Missing => StatusCode::PRECONDITION_REQUIRED,
+            Self::Failed => StatusCode::PRECONDITION_FAILED,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let code = match self {
+            Self::Missing => ProblemCode::PreconditionRequired,
+            Self::Failed => ProblemCode::PreconditionFailed,
+        };
+
+        ProblemDetails::new(self.status_code(), code, self.to_string()).into()
+    }
+}
+
+/// The versions `If-Match` allows an update to overwrite, or `None` for `*`.
+///
+/// Updates must always send `If-Match`, so that two editors cannot silently
+/// overwrite each other.
+pub fn expected_versions(req: &HttpRequest) -> Result<Option<Vec<i32>>, PreconditionError> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return Err(PreconditionError::Missing);
+    }
+
+    match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => Ok(None),
+        Ok(IfMatch::Items(tags)) => {
+            let versions = tags.iter().filter_map(tag_version).collect::<Vec<_>>();
+            if versions.is_empty() {
+                Err(PreconditionError::Failed)
+            } else {
+                Ok(Some(versions))
+            }
+        }
+        Err(_) => Err(PreconditionError::Failed),
+    }
+}
+
+/// Whether `If-None-Match` names `tag`, so the client's copy is current and
+/// a `304 Not Modified` will do.
+pub fn is_fresh(req: &HttpRequest, tag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|t| t.weak_eq(tag)),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{entity_tag, tag_version};
+    use actix_web::http::header::EntityTag;
+
+    #[test]
+    fn the_version_is_read_back_from_a_tag() {
+        assert_eq!(Some(3), tag_version(&entity_tag(3)));
+        assert_eq!(Some(3), tag_version(&EntityTag::new_strong("3-abcdef".into())));
+    }
+
+    #[test]
+    fn weak_and_foreign_tags_name_no_version() {
+        assert_eq!(None, tag_version(&EntityTag::new_weak("3".into())));
+        assert_eq!(None, tag_version(&EntityTag::new_strong("abc".into())));
+    }
+}
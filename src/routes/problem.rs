@@ -0,0 +1,148 @@
+use crate::domain::{DomainError, FieldError, ValidationErrors};
+use actix_web::error::InternalError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use uuid::Uuid;
+
+tokio::task_local! {
+    /// The id `TracingLogger` assigned to the request being handled, so that
+    /// error responses can point at the matching log lines.
+    pub static TRACE_ID: Option<String>;
+}
+
+/// A stable, machine-readable identifier for each kind of problem.
+///
+/// Clients should match on these rather than on `detail`, which is meant for
+/// humans and may change.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemCode {
+    /// One or more fields failed validation, see `errors`
+    ValidationFailed,
+    /// The body, query string or path could not be deserialized
+    InvalidPayload,
+    NotFound,
+    /// The write clashes with a unique value, such as an artist name, that
+    /// is already taken
+    Conflict,
+    /// The write refers to a record that does not exist, such as a concert
+    /// for an unknown artist
+    InvalidReference,
+    /// Updates must send `If-Match` with the entity tag they are based on
+    PreconditionRequired,
+    /// The record changed since the entity tag sent in `If-Match`
+    PreconditionFailed,
+    /// The concert looks like a duplicate of `existing_id`
+    DuplicateConcert,
+    /// The `Idempotency-Key` header is empty or too long
+    InvalidIdempotencyKey,
+    /// The `Idempotency-Key` was already used for a request with a different
+    /// body
+    IdempotencyKeyReused,
+    /// The client sent more requests than the route allows, see `Retry-After`
+    RateLimited,
+    /// The endpoint is switched off by a feature flag
+    FeatureDisabled,
+    InternalError,
+}
+
+/// An RFC 7807 `application/problem+json` error body.
+#[derive(serde::Serialize, serde::Deserialize, Debug, utoipa::ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: ProblemCode,
+    pub trace_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub existing_id: Option<Uuid>,
+}
+
+impl ProblemDetails {
+    pub fn new(status: StatusCode, code: ProblemCode, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: status.canonical_reason().unwrap_or_default().to_string(),
+            status: status.as_u16(),
+            detail: detail.into(),
+            code,
+            trace_id: TRACE_ID.try_with(Clone::clone).ok().flatten(),
+            errors: vec![],
+            existing_id: None,
+        }
+    }
+
+    pub fn validation(errors: &ValidationErrors) -> Self {
+        Self {
+            errors: errors.errors().to_vec(),
+            ..Self::new(
+                StatusCode::BAD_REQUEST,
+                ProblemCode::ValidationFailed,
+                "The payload failed validation",
+            )
+        }
+    }
+
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, ProblemCode::NotFound, detail)
+    }
+
+    /// The details of unexpected errors are logged, never sent to the client.
+    pub fn unexpected() -> Self {
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ProblemCode::InternalError,
+            "An unexpected error occurred",
+        )
+    }
+}
+
+impl From<ProblemDetails> for HttpResponse {
+    fn from(problem: ProblemDetails) -> Self {
+        let status = StatusCode::from_u16(problem.status)
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        HttpResponse::build(status)
+            .content_type("application/problem+json")
+            .json(problem)
+    }
+}
+
+impl ResponseError for DomainError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::InvalidReference(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::VersionMismatch => StatusCode::PRECONDITION_FAILED,
+            Self::Unexpected(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let code = match self {
+            Self::Conflict(_) => ProblemCode::Conflict,
+            Self::InvalidReference(_) => ProblemCode::InvalidReference,
+            Self::NotFound => ProblemCode::NotFound,
+            Self::VersionMismatch => ProblemCode::PreconditionFailed,
+            Self::Unexpected(_) => return ProblemDetails::unexpected().into(),
+        };
+
+        ProblemDetails::new(self.status_code(), code, self.to_string()).into()
+    }
+}
+
+/// Error handler for the `Json`, `Query` and `Path` extractors, so that
+/// malformed requests are reported as problems too.
+pub fn payload_error_handler<E>(err: E, _req: &HttpRequest) -> actix_web::Error
+where
+    E: ResponseError + 'static,
+{
+    let problem = ProblemDetails::new(err.status_code(), ProblemCode::InvalidPayload, err.to_string());
+
+    InternalError::from_response(err, problem.into()).into()
+}
@@ -0,0 +1,31 @@
+use crate::domain::{DomainError, Submission};
+use crate::routes::SubmissionError;
+use actix_web::{web, HttpResponse};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[utoipa::path(
+    get,
+    path = "/submissions/{id}",
+    tag = "submissions",
+    params(
+        ("id" = Uuid, Path, description = "The submission id"),
+    ),
+    responses(
+        (status = 200, description = "The submission and whether it was reviewed", body = Submission),
+        (status = 404, description = "Submission not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Get a submission",
+    skip(pool)
+)]
+pub async fn get_submission(
+    id: web::Path<Uuid>,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SubmissionError> {
+    let submission = Submission::find_by_id(*id, &pool).await?.ok_or(DomainError::NotFound)?;
+
+    Ok(HttpResponse::Ok().json(submission))
+}
@@ -0,0 +1,143 @@
+use crate::domain::{
+    Actor,
+    Artist,
+    DomainError,
+    NewConcert,
+    NewSubmission,
+    Submission,
+    SubmissionKind,
+    UpdateArtist,
+    ValidationErrors,
+};
+use crate::routes::{error_chain_fmt, BodyData, CreateConcertRequest, ProblemDetails};
+use actix_web::{web, HttpResponse, ResponseError};
+use anyhow::Context;
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[derive(thiserror::Error)]
+pub enum SubmissionError {
+    #[error("{0}")]
+    ValidationError(ValidationErrors),
+    #[error("{0}")]
+    UnexpectedError(#[from] anyhow::Error),
+    #[error(transparent)]
+    DomainError(#[from] DomainError),
+}
+
+impl std::fmt::Debug for SubmissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl ResponseError for SubmissionError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::ValidationError(_) => StatusCode::BAD_REQUEST,
+            Self::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::DomainError(e) => e.status_code(),
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        match self {
+            Self::ValidationError(errors) => ProblemDetails::validation(errors).into(),
+            Self::UnexpectedError(_) => ProblemDetails::unexpected().into(),
+            Self::DomainError(e) => e.error_response(),
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/submissions/concerts",
+    tag = "submissions",
+    params(
+        ("X-Client-Id" = Option<String>, Header, description = "Who the submission is attributed to"),
+    ),
+    request_body = CreateConcertRequest,
+    responses(
+        (status = 202, description = "The concert is waiting for a moderator", body = Submission),
+        (status = 400, description = "The payload failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Submissions are switched off", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "The artist does not exist", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Submitting a new concert",
+    skip(body, pool)
+)]
+pub async fn submit_concert(
+    body: web::Json<CreateConcertRequest>,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SubmissionError> {
+    let payload = serde_json::to_value(&body.0).context("Failed to serialize the submission")?;
+    let artist_id = body.artist_id;
+    NewConcert::try_from(body.into_inner()).map_err(SubmissionError::ValidationError)?;
+
+    Artist::find_by_id(artist_id, &pool).await?.ok_or_else(|| {
+        DomainError::InvalidReference(format!("Artist {} does not exist", artist_id))
+    })?;
+
+    let submission = NewSubmission {
+        kind: SubmissionKind::NewConcert,
+        target_id: None,
+        target_version: None,
+        payload,
+    };
+    let submission = Submission::insert(&submission, &actor, &pool).await?;
+
+    Ok(HttpResponse::Accepted().json(submission))
+}
+
+#[utoipa::path(
+    post,
+    path = "/submissions/artists/{id}",
+    tag = "submissions",
+    params(
+        ("id" = Uuid, Path, description = "The artist id"),
+        ("X-Client-Id" = Option<String>, Header, description = "Who the submission is attributed to"),
+    ),
+    request_body = UpdateArtistRequest,
+    responses(
+        (status = 202, description = "The change is waiting for a moderator", body = Submission),
+        (status = 400, description = "The payload failed validation or its id does not match the path", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Artist not found, or submissions are switched off", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Unexpected error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[tracing::instrument(
+    name = "Submitting an artist update",
+    skip(body, pool)
+)]
+pub async fn submit_artist_update(
+    id: web::Path<Uuid>,
+    body: web::Json<BodyData>,
+    actor: Actor,
+    pool: web::Data<PgPool>,
+) -> Result<HttpResponse, SubmissionError> {
+    let payload = serde_json::to_value(&body.0).context("Failed to serialize the submission")?;
+    let update = UpdateArtist::try_from(body.into_inner()).map_err(SubmissionError::ValidationError)?;
+    if update.id != *id {
+        return Err(SubmissionError::ValidationError(ValidationErrors::field(
+            "id",
+            "The artist id in the path does not match the artist id in the body",
+        )));
+    }
+
+    let artist = Artist::find_by_id(*id, &pool).await?.ok_or(DomainError::NotFound)?;
+
+    let submission = NewSubmission {
+        kind: SubmissionKind::UpdateArtist,
+        target_id: Some(artist.id),
+        target_version: Some(artist.version),
+        payload,
+    };
+    let submission = Submission::insert(&submission, &actor, &pool).await?;
+
+    Ok(HttpResponse::Accepted().json(submission))
+}
@@ -0,0 +1,69 @@
+use actix_session::{Session, SessionExt, SessionGetError, SessionInsertError};
+use actix_web::dev::Payload;
+use actix_web::{FromRequest, HttpRequest};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::future::{ready, Ready};
+use uuid::Uuid;
+
+/// The session, with typed accessors for what the application stores in it.
+pub struct TypedSession(Session);
+
+impl TypedSession {
+    const MODERATOR_ID_KEY: &'static str = "moderator_id";
+    const CSRF_TOKEN_KEY: &'static str = "csrf_token";
+
+    pub fn renew(&self) {
+        self.0.renew();
+    }
+
+    pub fn insert_moderator_id(&self, moderator_id: Uuid) -> Result<(), SessionInsertError> {
+        self.0.insert(Self::MODERATOR_ID_KEY, moderator_id)
+    }
+
+    pub fn get_moderator_id(&self) -> Result<Option<Uuid>, SessionGetError> {
+        self.0.get(Self::MODERATOR_ID_KEY)
+    }
+
+    /// The token forms must send back for `verify_csrf_token`, created the
+    /// first time a form is rendered in the session.
+    pub fn csrf_token(&self) -> Result<String, anyhow::Error> {
+        if let Some(token) = self.0.get::<String>(Self::CSRF_TOKEN_KEY)? {
+            return Ok(token);
+        }
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .map(char::from)
+            .take(32)
+            .collect();
+        self.0.insert(Self::CSRF_TOKEN_KEY, &token)?;
+        Ok(token)
+    }
+
+    /// Whether `submitted` is the session's CSRF token, compared in constant
+    /// time.
+    pub fn verify_csrf_token(&self, submitted: &str) -> Result<bool, SessionGetError> {
+        let expected = match self.0.get::<String>(Self::CSRF_TOKEN_KEY)? {
+            Some(expected) => expected,
+            None => return Ok(false),
+        };
+        let difference = expected
+            .bytes()
+            .zip(submitted.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b));
+        Ok(expected.len() == submitted.len() && difference == 0)
+    }
+
+    pub fn log_out(self) {
+        self.0.purge()
+    }
+}
+
+impl FromRequest for TypedSession {
+    type Error = <Session as FromRequest>::Error;
+    type Future = Ready<Result<TypedSession, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Ok(TypedSession(req.get_session())))
+    }
+}
@@ -1,33 +1,94 @@
 use crate::routes::{
     create_artist, 
     health_check, 
+    readiness_check,
+    metrics,
     artist_dashboard, 
     update_artist, 
+    patch_artist,
+    get_artist_concerts,
+    get_artist_history,
+    revert_artist,
     create_concert,
     get_concert, 
     update_concert, 
+    patch_concert,
+    get_concert_history,
+    revert_concert,
     get_concerts, 
+    get_concert_duplicates,
     artists_dashboard,
+    import_concerts,
+    import_setlistfm,
+    export_catalogue,
+    openapi_json,
+    api_docs,
+    swagger_ui_bundle,
+    swagger_ui_css,
+    graphql,
+    graphiql,
+    submit_concert,
+    submit_artist_update,
+    get_submission,
+    login_form,
+    login,
+    log_out,
+    moderation_queue,
+    approve_submission,
+    reject_submission,
+    admin_artists,
+    admin_create_artist,
+    new_artist_form,
+    edit_artist_form,
+    admin_update_artist,
+    admin_concerts,
+    admin_create_concert,
+    new_concert_form,
+    edit_concert_form,
+    admin_update_concert,
+    payload_error_handler,
+    TRACE_ID,
 };
-use crate::configuration::{DatabaseSettings, Settings};
+use crate::background::BackgroundTasks;
+use crate::cache::ResponseCache;
+use crate::configuration::{get_configuration, BodyLoggingSettings, CacheSettings, ConfigurationError, DatabaseSettings, IdempotencySettings, ImportSettings, RateLimitSettings, Settings};
+use crate::features::{Feature, Features};
+use crate::graphql::build_schema;
+use crate::idempotency::delete_expired_keys_periodically;
+use crate::metrics::{CountingSessionStore, HttpTimer, DB_POOL_MAX_CONNECTIONS};
+use crate::musicbrainz::{ArtistDump, IndexedArtistDump};
+use crate::openapi::api_doc;
+use crate::rate_limit::{RateLimit, RateLimiter, TrustProxyHeaders};
+use crate::telemetry::{spawn_blocking_with_tracing, BodyLogging};
 use actix_session::SessionMiddleware;
 use actix_session::storage::RedisSessionStore;
-use actix_web::dev::Server;
+use actix_web::dev::{Server, Service};
 use actix_web::web::Data;
 use actix_web::cookie::Key;
-use actix_web::{web, App, HttpServer};
+use actix_web::http::Method;
+use actix_web::{web, App, FromRequest, Handler, HttpMessage, HttpServer, Responder, Route};
 use actix_web_flash_messages::FlashMessagesFramework;
 use actix_web_flash_messages::storage::CookieMessageStore;
+use anyhow::Context;
+use redis::aio::ConnectionManager;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
+use futures_util::future::Either;
+use std::collections::HashMap;
+use std::future::{ready, Future};
 use std::net::TcpListener;
-use tracing_actix_web::TracingLogger;
+use tokio::sync::oneshot;
+use tracing_actix_web::{RequestId, TracingLogger};
 use secrecy::{Secret, ExposeSecret};
 
 
 pub struct Application {
     port: u16,
     server: Server,
+    db_pool: PgPool,
+    features: Features,
+    idempotency_expiry: std::time::Duration,
+    background_tasks: BackgroundTasks,
 }
 
 impl Application {
@@ -40,28 +101,184 @@ impl Application {
             );
         let listener = TcpListener::bind(address)?;
         let port = listener.local_addr().unwrap().port();
+        let features = Features::new(configuration.features);
+        let idempotency_expiry = configuration.idempotency.expiry();
+        let background_tasks = BackgroundTasks::default();
+        let artist_dump = match configuration.musicbrainz.artist_dump {
+            Some(path) => Some(index_artist_dump(path).await?),
+            None => None,
+        };
         let server = run(
             listener, 
-            connection_pool, 
+            connection_pool.clone(), 
             configuration.application.base_url,
             configuration.application.hmac_secret,
             configuration.redis_uri,
+            artist_dump,
+            configuration.idempotency,
+            configuration.telemetry.body_logging,
+            configuration.rate_limit,
+            configuration.application.trust_proxy_headers,
+            configuration.cache,
+            configuration.import,
+            features.clone(),
+            background_tasks.clone(),
+            configuration.application.shutdown_timeout_seconds,
             ).await?;
 
-        Ok(Self { port, server })
+        Ok(Self {
+            port,
+            server,
+            db_pool: connection_pool,
+            features,
+            idempotency_expiry,
+            background_tasks,
+        })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    /// The feature flags the handlers consult, which can be replaced while
+    /// the application runs.
+    pub fn features(&self) -> Features {
+        self.features.clone()
+    }
+
+    /// Serve until the process receives SIGTERM or Ctrl-C, then shut down
+    /// gracefully. The feature flags are reloaded on every SIGHUP meanwhile.
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.server.await
-    }    
+        tokio::spawn(reload_features_on_hangup(self.features.clone()));
+        self.run_until(shutdown_signal()).await
+    }
+
+    /// Serve until `shutdown` completes, then stop accepting connections,
+    /// give in-flight requests up to `shutdown_timeout_seconds` to finish and
+    /// close the database pool. Expired idempotency keys are deleted in the
+    /// background while serving; before the pool is closed, that cleanup is
+    /// stopped and the background tasks requests started are waited for.
+    pub async fn run_until(self, shutdown: impl Future<Output = ()>) -> Result<(), std::io::Error> {
+        let handle = self.server.handle();
+        let mut server = tokio::spawn(self.server);
+        let (stop_cleanup, cleanup_stopped) = oneshot::channel::<()>();
+        let cleanup = tokio::spawn(delete_expired_keys_periodically(
+            self.db_pool.clone(),
+            self.idempotency_expiry,
+            async {
+                let _ = cleanup_stopped.await;
+            },
+        ));
+
+        let stopped_early = tokio::select! {
+            result = &mut server => Some(result),
+            _ = shutdown => None,
+        };
+        let result = match stopped_early {
+            Some(result) => result,
+            None => {
+                tracing::info!("Shutting down: no longer accepting connections, draining in-flight requests");
+                handle.stop(true).await;
+                server.await
+            }
+        };
+
+        let _ = stop_cleanup.send(());
+        if let Err(e) = cleanup.await {
+            tracing::error!(error.cause_chain = ?e, "The idempotency key cleanup failed");
+        }
+        self.background_tasks.wait().await;
+        result.map_err(std::io::Error::other)??;
+
+        tracing::info!("Requests drained, closing the database pool");
+        self.db_pool.close().await;
+        tracing::info!("Shutdown complete");
+        Ok(())
+    }
+}
+
+/// Completes on SIGTERM, as sent by orchestrators, or on Ctrl-C.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error.cause_chain = ?e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error.cause_chain = ?e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl-C"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+/// Reads the configuration again on every SIGHUP and switches to its feature
+/// flags. Other settings only take effect on a restart. An invalid
+/// configuration is logged and the flags in effect are kept.
+#[cfg(unix)]
+async fn reload_features_on_hangup(features: Features) {
+    let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(signal) => signal,
+        Err(e) => {
+            tracing::error!(error.cause_chain = ?e, "Failed to listen for SIGHUP");
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        tracing::info!("Received SIGHUP");
+        reload_features(&features, get_configuration);
+    }
+}
+
+#[cfg(not(unix))]
+async fn reload_features_on_hangup(_features: Features) {}
+
+/// Replace the feature flags with those of the configuration `load` reads,
+/// keeping the flags in effect when it fails.
+pub fn reload_features(
+    features: &Features,
+    load: impl FnOnce() -> Result<Settings, ConfigurationError>,
+) {
+    match load() {
+        Ok(configuration) => {
+            features.replace(configuration.features);
+            tracing::info!(features = ?configuration.features, "Reloaded the feature flags");
+        }
+        Err(e) => {
+            tracing::error!(error.cause_chain = ?e, "Failed to reload the configuration, keeping the feature flags");
+        }
+    }
+}
+
+/// Index the MusicBrainz artist dump once, so that prefilling an artist
+/// doesn't scan the whole file on every request.
+async fn index_artist_dump(path: String) -> Result<IndexedArtistDump, anyhow::Error> {
+    let dump = spawn_blocking_with_tracing(move || ArtistDump::new(path).index())
+        .await?
+        .context("Failed to index the MusicBrainz artist dump")?;
+    tracing::info!(artists = dump.len(), "Indexed the MusicBrainz artist dump");
+
+    Ok(dump)
 }
 
 pub fn get_connection_pool(configuration: &DatabaseSettings) -> PgPool {
+    DB_POOL_MAX_CONNECTIONS.set(i64::from(configuration.max_connections));
     PgPoolOptions::new()
+        .max_connections(configuration.max_connections)
         .acquire_timeout(std::time::Duration::from_secs(2))
         .connect_lazy_with(configuration.with_db())
 }
@@ -72,47 +289,214 @@ pub fn get_connection_pool(configuration: &DatabaseSettings) -> PgPool {
 // a raw `String` would expose us to conflicts.
 pub struct ApplicationBaseUrl(pub String);
 
+#[allow(clippy::too_many_arguments)]
 async fn run(
     listener: TcpListener,
     db_pool: PgPool,
     base_url: String,
     hmac_secret: Secret<String>,
     redis_uri: Secret<String>,
+    artist_dump: Option<IndexedArtistDump>,
+    idempotency: IdempotencySettings,
+    body_logging: BodyLoggingSettings,
+    rate_limit: RateLimitSettings,
+    trust_proxy_headers: bool,
+    cache: CacheSettings,
+    import: ImportSettings,
+    features: Features,
+    background_tasks: BackgroundTasks,
+    shutdown_timeout_seconds: u64,
 ) -> Result<Server, anyhow::Error> {
     let base_url = Data::new(ApplicationBaseUrl(base_url));
+    let idempotency = Data::new(idempotency);
+    let trust_proxy_headers = Data::new(TrustProxyHeaders(trust_proxy_headers));
+    let background_tasks = Data::new(background_tasks);
     let db_pool = Data::new(db_pool);
 
     let secret_key = Key::from(hmac_secret.expose_secret().as_bytes());
     let message_store = CookieMessageStore::builder(secret_key.clone()).build();
     let message_framework = FlashMessagesFramework::builder(message_store).build();
-    let redis_store = RedisSessionStore::new(redis_uri.expose_secret()).await?;
+    let redis_store = CountingSessionStore(RedisSessionStore::new(redis_uri.expose_secret()).await?);
+    // The session store keeps its connection to itself, so the readiness
+    // check keeps one of its own to the same server, reconnecting as needed.
+    let redis_client = redis::Client::open(redis_uri.expose_secret().as_str())?;
+    let redis_connection = Data::new(ConnectionManager::new(redis_client.clone()).await?);
+    let openapi = Data::new(api_doc(&rate_limit));
+    let rate_limiter = Data::new(RateLimiter::new(rate_limit, redis_client.clone()).await?);
+    let cache = ResponseCache::new(cache, redis_client).await?;
+    let schema = Data::new(build_schema(db_pool.get_ref().clone(), cache.clone()));
+    let cache = Data::new(cache);
+
+    let artist_dump = artist_dump.map(Data::new);
 
     let server = HttpServer::new(move || {
-        App::new()
+        let routes = routes();
+        let gated = routes
+            .iter()
+            .filter_map(|(method, path, feature, _)| Some(((method.clone(), path.to_string()), (*feature)?)))
+            .collect::<HashMap<_, _>>();
+        let app = App::new()
+            // Innermost, so that a switched off endpoint answers `404` before
+            // its payload is extracted, and inside the `TRACE_ID` scope.
+            .wrap_fn({
+                let features = features.clone();
+                move |req, srv| {
+                    let route = (req.method().clone(), req.match_pattern().unwrap_or_default());
+                    let required = match gated.get(&route) {
+                        Some(feature) => features.require(*feature),
+                        None => Ok(()),
+                    };
+                    match required {
+                        Ok(()) => Either::Left(srv.call(req)),
+                        Err(e) => Either::Right(ready(Ok(req.error_response(e)))),
+                    }
+                }
+            })
+            // Inside `TracingLogger`, so that bodies are logged as part of
+            // the request's span, and inside `RateLimit`, so that rejected
+            // requests aren't read at all.
+            .wrap(BodyLogging::new(body_logging.clone()))
+            // Inside the `TRACE_ID` scope, so that rejections carry the
+            // request id, and inside `SessionMiddleware`, to tell logged in
+            // moderators apart.
+            .wrap(RateLimit::new(rate_limiter.clone().into_inner()))
+            // Make the request id available to `ProblemDetails`. This runs
+            // inside `TracingLogger`, which is what assigns the id.
+            .wrap_fn(|req, srv| {
+                let trace_id = req.extensions().get::<RequestId>().map(|id| id.to_string());
+                TRACE_ID.scope(trace_id, srv.call(req))
+            })
             .wrap(message_framework.clone())
             .wrap(SessionMiddleware::new(
                 redis_store.clone(),
                 secret_key.clone(),
             ))
             .wrap(TracingLogger::default())
-            .route("/health", web::get().to(health_check))
-            .route("/artists/dashboard", web::get().to(artists_dashboard))
-            .route("/artists", web::post().to(create_artist))
-            .route("/artists/{id}", web::get().to(artist_dashboard))
-            .route("/artists/{id}", web::put().to(update_artist))
-            .route("/concerts", web::post().to(create_concert))
-            .route("/concerts", web::get().to(get_concerts))
-            .route("/concerts/{id}", web::get().to(get_concert))
-            .route("/concerts/{id}", web::put().to(update_concert))
+            .wrap_fn(|req, srv| {
+                let timer = HttpTimer::start(&req);
+                let response = srv.call(req);
+                async move {
+                    let response = response.await;
+                    timer.observe(&response);
+                    response
+                }
+            })
             .app_data(db_pool.clone())
             .app_data(base_url.clone())
-            .app_data(Data::new(HmacSecret(hmac_secret.clone())))
+            .app_data(schema.clone())
+            .app_data(idempotency.clone())
+            .app_data(redis_connection.clone())
+            .app_data(cache.clone())
+            .app_data(trust_proxy_headers.clone())
+            .app_data(openapi.clone())
+            .app_data(rate_limiter.clone())
+            .app_data(background_tasks.clone())
+            .app_data(web::JsonConfig::default().error_handler(payload_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(payload_error_handler))
+            .app_data(web::PathConfig::default().error_handler(payload_error_handler))
+            // Only the imports read raw bodies, which are far larger than
+            // actix's default limit of 256 KiB.
+            .app_data(web::PayloadConfig::new(import.max_body_bytes))
+            .app_data(Data::new(HmacSecret(hmac_secret.clone())));
+        let app = routes
+            .into_iter()
+            .fold(app, |app, (_, path, _, route)| app.route(path, route));
+
+        match &artist_dump {
+            Some(artist_dump) => app.app_data(artist_dump.clone()),
+            None => app,
+        }
     })
+    // Signals are handled by `Application::run_until_stopped`, which also
+    // closes the pool once the workers have drained.
+    .disable_signals()
+    .shutdown_timeout(shutdown_timeout_seconds)
     .listen(listener)?
     .run();
 
     Ok(server)
 }
 
+/// A route's method and path template, the feature it is behind if any, and
+/// its handler.
+pub type RouteEntry = (Method, &'static str, Option<Feature>, Route);
+
+/// Every route served by the application.
+///
+/// Routes are kept in a single table, rather than chained on `App`, so that
+/// the API tests can check them against the OpenAPI document in
+/// `openapi::ApiDoc`. Routes are matched in order: literal segments such as
+/// `/concerts/duplicates` must come before `/concerts/{id}`. Routes behind a
+/// feature flag are tagged with their `Feature`, and answer `404` while it is
+/// switched off.
+pub fn routes() -> Vec<RouteEntry> {
+    vec![
+        route(Method::GET, "/health", health_check),
+        route(Method::GET, "/health/ready", readiness_check),
+        route(Method::GET, "/metrics", metrics),
+        route(Method::GET, "/openapi.json", openapi_json),
+        route(Method::GET, "/docs", api_docs),
+        route(Method::GET, "/docs/swagger-ui-bundle.js", swagger_ui_bundle),
+        route(Method::GET, "/docs/swagger-ui.css", swagger_ui_css),
+        route(Method::GET, "/artists/dashboard", artists_dashboard),
+        route(Method::POST, "/artists", create_artist),
+        route(Method::GET, "/artists/{id}", artist_dashboard),
+        route(Method::PUT, "/artists/{id}", update_artist),
+        route(Method::PATCH, "/artists/{id}", patch_artist),
+        route(Method::GET, "/artists/{id}/concerts", get_artist_concerts),
+        route(Method::GET, "/artists/{id}/history", get_artist_history),
+        route(Method::POST, "/artists/{id}/history/{revision}/revert", revert_artist),
+        route(Method::POST, "/concerts", create_concert),
+        route(Method::GET, "/concerts", get_concerts),
+        route(Method::GET, "/concerts/duplicates", get_concert_duplicates),
+        route(Method::GET, "/concerts/{id}", get_concert),
+        route(Method::PUT, "/concerts/{id}", update_concert),
+        route(Method::PATCH, "/concerts/{id}", patch_concert),
+        route(Method::GET, "/concerts/{id}/history", get_concert_history),
+        route(Method::POST, "/concerts/{id}/history/{revision}/revert", revert_concert),
+        behind(Feature::Submissions, route(Method::POST, "/submissions/concerts", submit_concert)),
+        behind(Feature::Submissions, route(Method::POST, "/submissions/artists/{id}", submit_artist_update)),
+        route(Method::GET, "/submissions/{id}", get_submission),
+        route(Method::GET, "/login", login_form),
+        route(Method::POST, "/login", login),
+        route(Method::POST, "/logout", log_out),
+        route(Method::GET, "/moderation/submissions", moderation_queue),
+        route(Method::POST, "/moderation/submissions/{id}/approve", approve_submission),
+        route(Method::POST, "/moderation/submissions/{id}/reject", reject_submission),
+        route(Method::GET, "/admin/artists", admin_artists),
+        route(Method::POST, "/admin/artists", admin_create_artist),
+        route(Method::GET, "/admin/artists/new", new_artist_form),
+        route(Method::GET, "/admin/artists/{id}/edit", edit_artist_form),
+        route(Method::POST, "/admin/artists/{id}", admin_update_artist),
+        route(Method::GET, "/admin/concerts", admin_concerts),
+        route(Method::POST, "/admin/concerts", admin_create_concert),
+        route(Method::GET, "/admin/concerts/new", new_concert_form),
+        route(Method::GET, "/admin/concerts/{id}/edit", edit_concert_form),
+        route(Method::POST, "/admin/concerts/{id}", admin_update_concert),
+        behind(Feature::Imports, route(Method::POST, "/import/concerts", import_concerts)),
+        behind(Feature::Imports, route(Method::POST, "/import/setlistfm", import_setlistfm)),
+        route(Method::GET, "/export", export_catalogue),
+        route(Method::POST, "/graphql", graphql),
+        route(Method::GET, "/graphql", graphiql),
+    ]
+}
+
+fn route<F, Args>(method: Method, path: &'static str, handler: F) -> RouteEntry
+where
+    F: Handler<Args>,
+    Args: FromRequest + 'static,
+    F::Output: Responder + 'static,
+{
+    (method.clone(), path, None, web::method(method).to(handler))
+}
+
+/// Put `route` behind `feature`.
+fn behind(
+    feature: Feature,
+    (method, path, _, route): RouteEntry,
+) -> RouteEntry {
+    (method, path, Some(feature), route)
+}
+
 #[derive(Clone)]
 pub struct HmacSecret(pub Secret<String>);
@@ -0,0 +1,129 @@
+use crate::configuration::BodyLoggingSettings;
+use crate::telemetry::{loggable_body, loggable_prefix};
+use actix_web::body::{to_bytes, BodySize, BoxBody, MessageBody};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::PayloadError;
+use actix_web::web::{Bytes, BytesMut};
+use actix_web::HttpMessage;
+use futures_util::future::LocalBoxFuture;
+use futures_util::{Stream, StreamExt};
+use std::future::{ready, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+/// Logs the bodies of requests to the routes opted in by
+/// `BodyLoggingSettings`, and of their responses, as events of the request's
+/// span. Must be wrapped by `TracingLogger` for the events to be attached to
+/// the request.
+///
+/// At most the first `max_bytes` of a request body, give or take a chunk, are
+/// held in memory to be logged; the handler reads them followed by the rest of
+/// the payload as it arrives. Responses without a known size, such as the
+/// streamed export, are not buffered and only noted as streamed.
+pub struct BodyLogging(Rc<BodyLoggingSettings>);
+
+impl BodyLogging {
+    pub fn new(settings: BodyLoggingSettings) -> Self {
+        Self(Rc::new(settings))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for BodyLogging
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = actix_web::Error;
+    type Transform = BodyLoggingMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BodyLoggingMiddleware {
+            service: Rc::new(service),
+            settings: self.0.clone(),
+        }))
+    }
+}
+
+pub struct BodyLoggingMiddleware<S> {
+    service: Rc<S>,
+    settings: Rc<BodyLoggingSettings>,
+}
+
+impl<S, B> Service<ServiceRequest> for BodyLoggingMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let settings = self.settings.clone();
+
+        Box::pin(async move {
+            let logged = settings.enabled
+                && req
+                    .match_pattern()
+                    .is_some_and(|pattern| settings.routes.contains(&pattern));
+            if !logged {
+                return Ok(service.call(req).await?.map_into_boxed_body());
+            }
+
+            let mut payload = req.take_payload();
+            let (prefix, complete) = read_prefix(&mut payload, settings.max_bytes).await?;
+            let logged_body = if complete {
+                loggable_body(&prefix, settings.max_bytes)
+            } else {
+                loggable_prefix(&prefix, settings.max_bytes)
+            };
+            tracing::info!(http.request.body = %logged_body, "Request body");
+            req.set_payload(replay(prefix, payload));
+
+            let response = service.call(req).await?.map_into_boxed_body();
+            if let BodySize::Stream = response.response().body().size() {
+                tracing::info!(http.response.body = "<streamed>", "Response body");
+                return Ok(response);
+            }
+
+            let (request, response) = response.into_parts();
+            let (response, body) = response.into_parts();
+            let body = to_bytes(body)
+                .await
+                .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+            tracing::info!(
+                http.response.body = %loggable_body(&body, settings.max_bytes),
+                "Response body"
+            );
+            let response = response.set_body(BoxBody::new(body));
+            Ok(ServiceResponse::new(request, response))
+        })
+    }
+}
+
+/// Reads the payload until more than `limit` bytes have arrived or it ends,
+/// returning what was read and whether that is the whole body.
+async fn read_prefix(payload: &mut Payload, limit: usize) -> Result<(Bytes, bool), PayloadError> {
+    let mut prefix = BytesMut::new();
+    while prefix.len() <= limit {
+        match payload.next().await {
+            Some(chunk) => prefix.extend_from_slice(&chunk?),
+            None => return Ok((prefix.freeze(), true)),
+        }
+    }
+    Ok((prefix.freeze(), false))
+}
+
+/// A payload yielding `prefix` again, then the rest of `payload`, for the
+/// handler to read.
+fn replay(prefix: Bytes, payload: Payload) -> Payload {
+    let stream: Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>> =
+        Box::pin(futures_util::stream::once(ready(Ok(prefix))).chain(payload));
+    Payload::from(stream)
+}
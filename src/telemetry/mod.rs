@@ -1,5 +1,9 @@
+mod body_logging;
+mod redaction;
 mod subscriber;
 mod spawn;
 
 pub use subscriber::*;
 pub use spawn::*;
+pub use body_logging::*;
+pub use redaction::*;
@@ -0,0 +1,203 @@
+use serde_json::Value;
+use std::io::Write;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// What replaces a redacted value.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Parts of field names that mark their value as a credential.
+const SENSITIVE_NAMES: [&str; 7] = [
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "authorization",
+    "cookie",
+    "api_key",
+];
+
+/// Whether a field called `name` holds a credential.
+pub fn is_sensitive(name: &str) -> bool {
+    let name = name.to_lowercase().replace('-', "_");
+    SENSITIVE_NAMES.iter().any(|sensitive| name.contains(sensitive))
+}
+
+/// Mask the values of sensitive fields, at any depth.
+///
+/// `secrecy::Secret` already debug-prints as `Secret([REDACTED ...])`; such
+/// values are masked the same way so that they read alike in the logs.
+pub fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, value) in fields.iter_mut() {
+                if is_sensitive(name) {
+                    *value = Value::String(REDACTED.into());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_json),
+        Value::String(s) if s.starts_with("Secret([REDACTED") => {
+            *s = REDACTED.into();
+        }
+        _ => {}
+    }
+}
+
+/// Mask the values of sensitive fields in an `application/x-www-form-urlencoded`
+/// body.
+pub fn redact_form(body: &str) -> String {
+    body.split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_sensitive(name) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// A body as it can be logged: redacted when it is JSON or a form, and cut
+/// to `max_bytes`.
+pub fn loggable_body(body: &[u8], max_bytes: usize) -> String {
+    let redacted = match serde_json::from_slice::<Value>(body) {
+        Ok(mut json) => {
+            redact_json(&mut json);
+            json.to_string()
+        }
+        Err(_) => redact_form(&String::from_utf8_lossy(body)),
+    };
+    if redacted.len() <= max_bytes {
+        return redacted;
+    }
+    let mut end = max_bytes;
+    while !redacted.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... ({} bytes)", &redacted[..end], body.len())
+}
+
+/// The start of a body too long to be read whole, as it can be logged. A cut
+/// JSON document can't be parsed to find its credentials, so only its size
+/// is logged; anything else is redacted as a form and cut to `max_bytes`.
+pub fn loggable_prefix(prefix: &[u8], max_bytes: usize) -> String {
+    let text = String::from_utf8_lossy(prefix);
+    if text.trim_start().starts_with(['{', '[']) {
+        return format!("<JSON body over {} bytes, not logged>", max_bytes);
+    }
+    let redacted = redact_form(&text);
+    let mut end = max_bytes.min(redacted.len());
+    while !redacted.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}... (over {} bytes)", &redacted[..end], max_bytes)
+}
+
+/// Wraps the sink of the bunyan logs, masking sensitive fields of each JSON
+/// record before it is written.
+pub struct Redacting<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Redacting<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+pub struct RedactingWriter<W>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    /// `BunyanFormattingLayer` writes each record, newline included, at once.
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        for line in buf.split_inclusive(|b| *b == b'\n') {
+            match serde_json::from_slice::<Value>(line) {
+                Ok(mut record) => {
+                    redact_json(&mut record);
+                    serde_json::to_writer(&mut self.0, &record)?;
+                    if line.ends_with(b"\n") {
+                        self.0.write_all(b"\n")?;
+                    }
+                }
+                Err(_) => self.0.write_all(line)?,
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{loggable_body, loggable_prefix, redact_form, redact_json, Redacting, REDACTED};
+    use std::io::Write;
+    use std::sync::Mutex;
+    use tracing_subscriber::fmt::MakeWriter;
+
+    #[test]
+    fn credentials_are_masked_at_any_depth() {
+        let mut json = serde_json::json!({
+            "name": "Goose",
+            "password": "hunter2",
+            "nested": [{ "csrf_token": "abc", "venue": "Red Rocks" }],
+            "key": "Secret([REDACTED alloc::string::String])",
+        });
+
+        redact_json(&mut json);
+
+        assert_eq!(
+            serde_json::json!({
+                "name": "Goose",
+                "password": REDACTED,
+                "nested": [{ "csrf_token": REDACTED, "venue": "Red Rocks" }],
+                "key": REDACTED,
+            }),
+            json
+        );
+    }
+
+    #[test]
+    fn form_credentials_are_masked() {
+        assert_eq!(
+            "username=goose&password=[REDACTED]",
+            redact_form("username=goose&password=hunter2")
+        );
+    }
+
+    #[test]
+    fn long_bodies_are_cut() {
+        let body = "a".repeat(100);
+
+        let logged = loggable_body(body.as_bytes(), 10);
+
+        assert_eq!("aaaaaaaaaa... (100 bytes)", logged);
+    }
+
+    #[test]
+    fn cut_json_is_not_logged_and_cut_forms_are_redacted() {
+        assert_eq!(
+            "<JSON body over 16 bytes, not logged>",
+            loggable_prefix(br#"{"password":"hunter2","name":"#, 16)
+        );
+        assert_eq!(
+            "password=[REDACTED]&name... (over 24 bytes)",
+            loggable_prefix(b"password=hunter2&name=goose&city=denver", 24)
+        );
+    }
+
+    #[test]
+    fn log_records_are_redacted_before_they_are_written() {
+        let make_writer = Redacting(Mutex::new(Vec::new()));
+
+        make_writer
+            .make_writer()
+            .write_all(b"{\"msg\":\"login\",\"authorization\":\"Bearer abc\"}\n")
+            .unwrap();
+
+        let written = String::from_utf8(make_writer.0.into_inner().unwrap()).unwrap();
+        assert_eq!("{\"authorization\":\"[REDACTED]\",\"msg\":\"login\"}\n", written);
+    }
+}
@@ -1,12 +1,24 @@
+use crate::configuration::TelemetrySettings;
+use crate::telemetry::Redacting;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::trace::{self, Sampler, Tracer};
+use opentelemetry::sdk::Resource;
+use opentelemetry::trace::TraceError;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
 use tracing::{Subscriber, subscriber::set_global_default};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
 use tracing_subscriber::{fmt::MakeWriter, EnvFilter, Registry, layer::SubscriberExt};
 
+/// Compose the layers spans and events go through: bunyan logs to `sink`,
+/// with credentials redacted, and, when `tracer` is set, export to
+/// OpenTelemetry.
 pub fn get_subscriber<Sink>(
     name: String,
     env_filter: String,
     sink: Sink,
+    tracer: Option<Tracer>,
 ) -> impl Subscriber + Sync + Send
 where
     Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
@@ -14,12 +26,50 @@ where
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
 
-    let formatting_layer = BunyanFormattingLayer::new(name, sink);
+    let formatting_layer = BunyanFormattingLayer::new(name, Redacting(sink));
+    let otel_layer = tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
 
     Registry::default()
         .with(env_filter)
         .with(JsonStorageLayer)
         .with(formatting_layer)
+        .with(otel_layer)
+}
+
+/// An OTLP exporter for the spans of this process, or `None` when no
+/// endpoint is configured.
+///
+/// Spans are exported in batches from a background task, so this must be
+/// called from within a Tokio runtime. Installs the W3C trace context
+/// propagator whether or not an endpoint is configured, so that
+/// `TracingLogger` always continues the trace of an incoming `traceparent`
+/// header.
+pub fn get_tracer(settings: &TelemetrySettings) -> Result<Option<Tracer>, TraceError> {
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    let endpoint = match &settings.otlp_endpoint {
+        Some(endpoint) => endpoint,
+        None => return Ok(None),
+    };
+
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(settings.sampling_ratio)));
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .http()
+                .with_endpoint(endpoint),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_sampler(sampler)
+                .with_resource(Resource::new(vec![KeyValue::new(
+                    "service.name",
+                    settings.service_name.clone(),
+                )])),
+        )
+        .install_batch(opentelemetry::runtime::Tokio)?;
+
+    Ok(Some(tracer))
 }
 
 pub fn init_subscriber(subscriber: impl Subscriber + Sync + Send) {
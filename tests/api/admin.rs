@@ -0,0 +1,244 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+use allbands::domain::Artist;
+
+#[tokio::test]
+async fn the_admin_area_requires_a_login() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_admin_page("/admin/artists").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn a_form_with_a_forged_csrf_token_is_forbidden() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_moderator.login(&app).await;
+
+    // Act
+    let response = app
+        .post_admin_form("/admin/artists", &serde_json::json!({
+            "csrf_token": "forged",
+            "name": "Goose",
+            "sort_name": "Goose",
+            "disambiguation": "",
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+    assert_eq!(0, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn a_form_without_the_csrf_token_is_forbidden() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_moderator.login(&app).await;
+
+    // Act
+    let response = app
+        .post_admin_form("/admin/artists", &serde_json::json!({
+            "name": "Goose",
+            "sort_name": "Goose",
+            "disambiguation": "",
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(403, response.status().as_u16());
+    assert_eq!(0, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn creating_an_artist_redirects_to_the_list_with_a_message() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_moderator.login(&app).await;
+    let csrf_token = app.csrf_token().await;
+
+    // Act
+    let response = app
+        .post_admin_form("/admin/artists", &serde_json::json!({
+            "csrf_token": csrf_token,
+            "name": "Goose",
+            "sort_name": "Goose",
+            "disambiguation": "Connecticut jam band",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/artists");
+    let html = app.get_admin_html("/admin/artists").await;
+    assert!(html.contains("Created Goose."));
+    assert_eq!(1, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn an_invalid_form_is_shown_again_with_the_errors_next_to_the_fields() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_moderator.login(&app).await;
+    let csrf_token = app.csrf_token().await;
+
+    // Act
+    let response = app
+        .post_admin_form("/admin/concerts", &serde_json::json!({
+            "csrf_token": csrf_token,
+            "venue": "Red Rocks",
+            "city": "Morrison",
+            "state": "CO",
+            "country": "US",
+            "date": "not a date",
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let html = response.text().await.unwrap();
+    assert!(html.contains("An artist is required"));
+    assert!(html.contains("Error parsing date"));
+    assert!(html.contains(r#"value="Red Rocks""#));
+}
+
+#[tokio::test]
+async fn editing_an_artist_someone_else_changed_shows_a_conflict() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist = app
+        .post_artist(serde_json::json!({
+            "name": "Goose",
+            "sort_name": "Goose",
+            "disambiguation": "",
+        }))
+        .await
+        .json::<Artist>()
+        .await
+        .unwrap();
+    app.test_moderator.login(&app).await;
+    let csrf_token = app.csrf_token().await;
+    let path = format!("/admin/artists/{}", artist.id);
+    let form = |name: &str| serde_json::json!({
+        "csrf_token": csrf_token,
+        "version": artist.version,
+        "name": name,
+        "sort_name": "Goose",
+        "disambiguation": "",
+    });
+    app.post_admin_form(&path, &form("Goose!")).await;
+
+    // Act
+    let response = app.post_admin_form(&path, &form("Goose?")).await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+    let html = response.text().await.unwrap();
+    assert!(html.contains("changed by someone else"));
+}
+
+#[tokio::test]
+async fn an_edit_without_the_version_it_was_rendered_from_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist = app
+        .post_artist(serde_json::json!({
+            "name": "Goose",
+            "sort_name": "Goose",
+            "disambiguation": "",
+        }))
+        .await
+        .json::<Artist>()
+        .await
+        .unwrap();
+    app.test_moderator.login(&app).await;
+    let csrf_token = app.csrf_token().await;
+
+    // Act
+    let response = app
+        .post_admin_form(&format!("/admin/artists/{}", artist.id), &serde_json::json!({
+            "csrf_token": csrf_token,
+            "name": "Goose!",
+            "sort_name": "Goose",
+            "disambiguation": "",
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let unchanged = app.get_artist_by_id(artist.id).await.text().await.unwrap();
+    assert!(!unchanged.contains("Goose!"));
+}
+
+#[tokio::test]
+async fn artist_details_are_escaped_on_the_artist_page() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist = app
+        .post_artist(serde_json::json!({
+            "name": "Simon & Garfunkel",
+            "sort_name": "Simon & Garfunkel",
+            "disambiguation": "<script>alert(1)</script>",
+        }))
+        .await
+        .json::<Artist>()
+        .await
+        .unwrap();
+
+    // Act
+    let html = app.get_admin_html(&format!("/artists/{}", artist.id)).await;
+
+    // Assert
+    assert!(!html.contains("<script>"));
+    assert!(html.contains("&lt;script&gt;"));
+    assert!(html.contains("Simon &amp; Garfunkel"));
+}
+
+#[tokio::test]
+async fn a_likely_duplicate_concert_is_shown_again_until_saved_anyway() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist = app.post_artist(serde_json::json!({
+        "name": "Goose",
+        "sort_name": "Goose",
+        "disambiguation": "Connecticut jam band",
+    }))
+    .await
+    .json::<Artist>()
+    .await
+    .expect("Failed to deserialize the artist");
+    app.post_concert(serde_json::json!({
+        "artist_id": artist.id,
+        "venue": "Red Rocks",
+        "city": "Morrison",
+        "state": "CO",
+        "country": "US",
+        "date": "2022-06-24",
+    }))
+    .await;
+    app.test_moderator.login(&app).await;
+    let csrf_token = app.csrf_token().await;
+    let form = |force: bool| serde_json::json!({
+        "csrf_token": csrf_token,
+        "artist_id": artist.id,
+        "venue": "Red Rocks",
+        "city": "Morrison",
+        "state": "CO",
+        "country": "US",
+        "date": "2022-06-24",
+        "force": force,
+    });
+
+    // Act
+    let refused = app.post_admin_form("/admin/concerts", &form(false)).await;
+    let forced = app.post_admin_form("/admin/concerts", &form(true)).await;
+
+    // Assert
+    assert_eq!(409, refused.status().as_u16());
+    assert!(refused.text().await.unwrap().contains("looks like a duplicate of the one at Red Rocks"));
+    assert_is_redirect_to(&forced, "/admin/concerts");
+    assert_eq!(2, app.concert_count().await);
+}
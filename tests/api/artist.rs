@@ -1,4 +1,4 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{etag, spawn_app, TestApp};
 use allbands::domain::Artist;
 use uuid::Uuid;
 
@@ -136,13 +136,14 @@ async fn update_artist_returns_200_ok() {
         "The API did not succeed to create a new Artist"
     );
 
+    let etag = etag(&response);
     let artist_id = response.json::<Artist>()
         .await
         .expect("Failed to parse response")
         .id;
 
     // Act
-   let response = app.update_artist(artist_id, serde_json::json!({
+   let response = app.update_artist(artist_id, &etag, serde_json::json!({
         "id": artist_id,
         "name": "Billy Strings",
         "sort_name": "Strings, Billy",
@@ -166,3 +167,142 @@ async fn update_artist_returns_200_ok() {
     assert_eq!("Strings, Billy", artist.sort_name);
     assert_eq!("Bluegrass", artist.disambiguation);
 }
+
+#[tokio::test]
+async fn create_artist_returns_409_for_a_taken_name() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = serde_json::json!({
+        "name": "Billy Strings",
+        "sort_name": "Strings, Billy",
+        "disambiguation": "Bluegrass",
+    });
+    app.post_artist(body.clone()).await;
+
+    // Act
+    let response = app.post_artist(body).await;
+
+    // Assert
+    assert_eq!(
+        409,
+        response.status().as_u16(),
+        "The API did not return a 409 CONFLICT for an artist name that is already taken"
+    );
+    let problem = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("conflict", problem["code"]);
+}
+
+#[tokio::test]
+async fn update_artist_returns_404_for_an_unknown_artist() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = Uuid::new_v4();
+
+    // Act
+    let response = app.update_artist(artist_id, "*", serde_json::json!({
+        "id": artist_id,
+        "name": "Billy Strings",
+        "sort_name": "Strings, Billy",
+        "disambiguation": "Bluegrass",
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(
+        404,
+        response.status().as_u16(),
+        "The API did not return a 404 NOT FOUND for an unknown artist"
+    );
+}
+
+async fn artist_with_concerts(app: &TestApp, dates: &[chrono::NaiveDate]) -> Uuid {
+    let artist_id = app.post_artist(serde_json::json!({
+        "name": format!("Goose {}", Uuid::new_v4()),
+        "sort_name": "Goose",
+        "disambiguation": "Jam band from Connecticut",
+    }))
+    .await
+    .json::<Artist>()
+    .await
+    .expect("Failed to deserialize the artist")
+    .id;
+
+    for (i, date) in dates.iter().enumerate() {
+        let response = app.post_concert(serde_json::json!({
+            "artist_id": artist_id,
+            "venue": format!("Venue {}", i),
+            "city": "Denver",
+            "state": "CO",
+            "country": "USA",
+            "date": date.to_string(),
+        }))
+        .await;
+        assert_eq!(201, response.status().as_u16());
+    }
+
+    artist_id
+}
+
+#[tokio::test]
+async fn artist_page_splits_upcoming_and_past_shows() {
+    // Arrange
+    let app = spawn_app().await;
+    let today = chrono::Utc::now().date_naive();
+    let artist_id = artist_with_concerts(&app, &[
+        today - chrono::Duration::days(30),
+        today + chrono::Duration::days(30),
+    ])
+    .await;
+
+    // Act
+    let html = app.get_artist_by_id(artist_id).await.text().await.unwrap();
+
+    // Assert
+    let (upcoming, past) = html.split_once("<section id=\"past\">").unwrap();
+    assert!(upcoming.contains("Venue 1"));
+    assert!(!upcoming.contains("Venue 0"));
+    assert!(past.contains("Venue 0"));
+    assert!(!past.contains("Venue 1"));
+}
+
+#[tokio::test]
+async fn artist_concerts_are_paginated() {
+    // Arrange
+    let app = spawn_app().await;
+    let today = chrono::Utc::now().date_naive();
+    let dates: Vec<_> = (1..=5).map(|days| today - chrono::Duration::days(days)).collect();
+    let artist_id = artist_with_concerts(&app, &dates).await;
+    let other_artist = artist_with_concerts(&app, &[today]).await;
+
+    // Act
+    let first: serde_json::Value = app.get_artist_concerts(artist_id, "when=past&per_page=2").await.json().await.unwrap();
+    let last: serde_json::Value = app.get_artist_concerts(artist_id, "when=past&per_page=2&page=3").await.json().await.unwrap();
+    let other: serde_json::Value = app.get_artist_concerts(other_artist, "").await.json().await.unwrap();
+
+    // Assert
+    assert_eq!(5, first["total"]);
+    let first_dates: Vec<_> = first["concerts"].as_array().unwrap().iter().map(|c| c["date"].clone()).collect();
+    assert_eq!(vec![serde_json::json!(dates[0].to_string()), serde_json::json!(dates[1].to_string())], first_dates);
+    assert_eq!(1, last["concerts"].as_array().unwrap().len());
+    assert_eq!(dates[4].to_string(), last["concerts"][0]["date"]);
+    assert_eq!(1, other["total"]);
+}
+
+#[tokio::test]
+async fn artist_concerts_reject_an_out_of_range_page_size() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = artist_with_concerts(&app, &[]).await;
+
+    for query in ["per_page=0", "per_page=101", "page=0"] {
+        // Act
+        let response = app.get_artist_concerts(artist_id, query).await;
+
+        // Assert
+        assert_eq!(400, response.status().as_u16(), "The API did not reject {}", query);
+    }
+
+    // An unknown artist has no concerts to list
+    let response = app.get_artist_concerts(Uuid::new_v4(), "").await;
+    assert_eq!(404, response.status().as_u16());
+}
@@ -0,0 +1,84 @@
+use crate::helpers::{spawn_app, spawn_app_with, test_configuration, TestApp};
+use allbands::domain::{Actor, Artist, NewArtist};
+use allbands::routes::CreateArtistRequest;
+
+/// Insert an artist straight into Postgres, as a write the cache is not told
+/// about.
+async fn insert_behind_the_cache(app: &TestApp, name: &str) {
+    let new_artist = NewArtist::try_from(CreateArtistRequest {
+        name: name.into(),
+        sort_name: Some(name.into()),
+        disambiguation: Some("".into()),
+        mbid: None,
+    })
+    .unwrap();
+    let mut transaction = app.db_pool.begin().await.unwrap();
+    Artist::insert(&new_artist, &Actor::new("test"), &mut transaction)
+        .await
+        .unwrap();
+    transaction.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn reads_are_served_from_the_cache_until_a_write_invalidates_it() {
+    // Arrange
+    let app = spawn_app().await;
+    let before = app.get_admin_html("/artists/dashboard").await;
+    insert_behind_the_cache(&app, "Goose").await;
+    let cached = app.get_admin_html("/artists/dashboard").await;
+
+    // Act
+    app.post_artist(serde_json::json!({
+        "name": "Phish",
+        "sort_name": "Phish",
+        "disambiguation": "",
+    }))
+    .await;
+    let after = app.get_admin_html("/artists/dashboard").await;
+
+    // Assert
+    assert_eq!(before, cached);
+    assert!(after.contains("Goose"));
+    assert!(after.contains("Phish"));
+}
+
+#[tokio::test]
+async fn cached_reads_tell_clients_how_long_to_reuse_them() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/concerts", &app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(
+        Some("public, max-age=10"),
+        response.headers().get("Cache-Control").map(|v| v.to_str().unwrap())
+    );
+    assert_eq!(
+        Some("application/json"),
+        response.headers().get("Content-Type").map(|v| v.to_str().unwrap())
+    );
+}
+
+#[tokio::test]
+async fn a_disabled_cache_always_reads_from_postgres() {
+    // Arrange
+    let mut configuration = test_configuration().await;
+    configuration.cache.enabled = false;
+    let app = spawn_app_with(configuration).await;
+    app.get_admin_html("/artists/dashboard").await;
+
+    // Act
+    insert_behind_the_cache(&app, "Goose").await;
+    let html = app.get_admin_html("/artists/dashboard").await;
+
+    // Assert
+    assert!(html.contains("Goose"));
+}
@@ -0,0 +1,39 @@
+use std::process::Command;
+
+fn allbands(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_allbands"))
+        .args(args)
+        .output()
+        .expect("Failed to run allbands")
+}
+
+#[test]
+fn arguments_other_than_the_documented_ones_are_a_usage_error() {
+    for args in [
+        &["--check-config", "extra"][..],
+        &["--import-musicbrainz"],
+        &["--create-moderator", "alice", "bob"],
+        &["--check-configuration"],
+        &["serve"],
+    ] {
+        // Act
+        let output = allbands(args);
+
+        // Assert
+        assert_eq!(Some(2), output.status.code(), "{:?}", args);
+        assert!(output.stdout.is_empty(), "{:?}", args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with("Usage: allbands"), "{:?}: {}", args, stderr);
+    }
+}
+
+#[test]
+fn check_config_prints_the_configuration() {
+    // Act
+    let output = allbands(&["--check-config"]);
+
+    // Assert
+    assert!(output.status.success());
+    let configuration: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("[REDACTED]", configuration["application"]["hmac_secret"]);
+}
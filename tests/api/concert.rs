@@ -1,5 +1,6 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{etag, spawn_app};
 use allbands::domain::{Concert, Artist};
+use allbands::routes::LikelyDuplicate;
 
 #[tokio::test]
 async fn concerts_returns_201_created_for_valid_data() {
@@ -208,7 +209,7 @@ async fn update_concert_returns_400_when_id_doesnt_match_body_id() {
     let app = spawn_app().await;
 
     // Act
-    let result = app.update_concert(uuid::Uuid::new_v4(), serde_json::json!({
+    let result = app.update_concert(uuid::Uuid::new_v4(), "*", serde_json::json!({
         "id": uuid::Uuid::new_v4(),
         "artist_id": uuid::Uuid::new_v4(),
         "venue": "The Fillmore",
@@ -243,7 +244,7 @@ async fn upgrade_concert_returns_200_for_valid_input() {
     .expect("Failed to deserialize the artist")
     .id;
 
-    let concert_id = app.post_concert(serde_json::json!({
+    let response = app.post_concert(serde_json::json!({
         "artist_id": artist_id,
         "venue": "The Fillmore",
         "city": "San Francisco",
@@ -251,15 +252,17 @@ async fn upgrade_concert_returns_200_for_valid_input() {
         "country": "USA",
         "date": "2021-07-17"
     }))
-    .await
-    .json::<Concert>()
-    .await
-    .expect("Failed to deserialize the concert")
-    .id;
+    .await;
+    let etag = etag(&response);
+    let concert_id = response
+        .json::<Concert>()
+        .await
+        .expect("Failed to deserialize the concert")
+        .id;
 
 
     // Act
-    let response = app.update_concert(concert_id, serde_json::json!({
+    let response = app.update_concert(concert_id, &etag, serde_json::json!({
         "id": concert_id,
         "artist_id": artist_id,
         "venue": "The Fillmore",
@@ -280,3 +283,281 @@ async fn upgrade_concert_returns_200_for_valid_input() {
     let updated_concert = response.json::<Concert>().await.unwrap();
     assert_eq!(updated_concert.date, chrono::NaiveDate::parse_from_str("2021-07-18", "%Y-%m-%d").unwrap());
 }
+
+#[tokio::test]
+async fn create_concert_returns_409_for_a_likely_duplicate() {
+    // Arrange
+    let app = spawn_app().await;
+
+    let artist_id = app.post_artist(serde_json::json!({
+        "name": "Billy Strings",
+        "sort_name": "Strings, Billy",
+        "disambiguation": "Bluegrass musician from Lansing, MI",
+    }))
+    .await
+    .json::<Artist>()
+    .await
+    .expect("Failed to deserialize the artist")
+    .id;
+
+    let concert_id = app.post_concert(serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    }))
+    .await
+    .json::<Concert>()
+    .await
+    .expect("Failed to deserialize the concert")
+    .id;
+
+    // Act
+    let response = app.post_concert(serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "Fillmore",
+        "city": "san francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(
+        409,
+        response.status().as_u16(),
+        "The API did not return a 409 CONFLICT response for a duplicate concert"
+    );
+    let body = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(concert_id.to_string(), body["existing_id"]);
+    assert_eq!("duplicate_concert", body["code"]);
+}
+
+#[tokio::test]
+async fn create_concert_with_force_allows_a_likely_duplicate() {
+    // Arrange
+    let app = spawn_app().await;
+
+    let artist_id = app.post_artist(serde_json::json!({
+        "name": "Billy Strings",
+        "sort_name": "Strings, Billy",
+        "disambiguation": "Bluegrass musician from Lansing, MI",
+    }))
+    .await
+    .json::<Artist>()
+    .await
+    .expect("Failed to deserialize the artist")
+    .id;
+
+    let concert = serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    });
+    let original_id = app.post_concert(concert.clone())
+        .await
+        .json::<Concert>()
+        .await
+        .expect("Failed to deserialize the concert")
+        .id;
+
+    // Act
+    let response = app.post_concert_forced(concert).await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let duplicate_id = response.json::<Concert>().await.unwrap().id;
+
+    let duplicates = app.get_concert_duplicates()
+        .await
+        .json::<Vec<LikelyDuplicate>>()
+        .await
+        .expect("Failed to deserialize the duplicates report");
+    assert_eq!(1, duplicates.len());
+    assert_eq!(duplicate_id, duplicates[0].concert_id);
+    assert_eq!(original_id, duplicates[0].duplicate_of);
+}
+
+#[tokio::test]
+async fn update_concert_returns_409_when_it_would_duplicate_another() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let (original, _) = app.create_concert(artist_id, "2021-07-17").await;
+    let (concert, etag) = app.create_concert(artist_id, "2021-07-18").await;
+
+    // Act
+    let response = app.update_concert(concert.id, &etag, serde_json::json!({
+        "id": concert.id,
+        "artist_id": artist_id,
+        "venue": "Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+    let body = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(original.id.to_string(), body["existing_id"]);
+    assert_eq!("duplicate_concert", body["code"]);
+    let unchanged = app.get_concert_by_id(concert.id).await.json::<Concert>().await.unwrap();
+    assert_eq!(concert.version, unchanged.version);
+}
+
+#[tokio::test]
+async fn patch_concert_returns_409_when_it_would_duplicate_another_unless_forced() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let (original, _) = app.create_concert(artist_id, "2021-07-17").await;
+    let (concert, etag) = app.create_concert(artist_id, "2021-07-18").await;
+
+    // Act
+    let response = app.patch_concert(concert.id, &etag, serde_json::json!({"date": "2021-07-17"})).await;
+    let forced = app
+        .api_client
+        .patch(format!("{}/concerts/{}?force=true", &app.address, concert.id))
+        .header("If-Match", &etag)
+        .header("Content-Type", "application/merge-patch+json")
+        .body(serde_json::json!({"date": "2021-07-17"}).to_string())
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(409, response.status().as_u16());
+    let body = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!(original.id.to_string(), body["existing_id"]);
+    assert_eq!(200, forced.status().as_u16());
+    let patched = forced.json::<Concert>().await.unwrap();
+    assert_eq!(concert.version + 1, patched.version);
+}
+
+#[tokio::test]
+async fn the_same_artist_at_another_venue_on_the_same_day_is_not_a_duplicate() {
+    // Arrange
+    let app = spawn_app().await;
+
+    let artist_id = app.post_artist(serde_json::json!({
+        "name": "Billy Strings",
+        "sort_name": "Strings, Billy",
+        "disambiguation": "Bluegrass musician from Lansing, MI",
+    }))
+    .await
+    .json::<Artist>()
+    .await
+    .expect("Failed to deserialize the artist")
+    .id;
+
+    app.post_concert(serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    }))
+    .await;
+
+    // Act
+    let response = app.post_concert(serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "Red Rocks Amphitheatre",
+        "city": "Morrison",
+        "state": "CO",
+        "country": "USA",
+        "date": "2021-07-17"
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn create_concert_returns_422_for_an_unknown_artist() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_concert(serde_json::json!({
+        "artist_id": uuid::Uuid::new_v4(),
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(
+        422,
+        response.status().as_u16(),
+        "The API did not return a 422 UNPROCESSABLE ENTITY for an unknown artist"
+    );
+    let problem = response.json::<serde_json::Value>().await.unwrap();
+    assert_eq!("invalid_reference", problem["code"]);
+}
+
+#[tokio::test]
+async fn update_concert_returns_404_for_an_unknown_concert() {
+    // Arrange
+    let app = spawn_app().await;
+    let concert_id = uuid::Uuid::new_v4();
+
+    // Act
+    let response = app.update_concert(concert_id, "*", serde_json::json!({
+        "id": concert_id,
+        "artist_id": uuid::Uuid::new_v4(),
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(
+        404,
+        response.status().as_u16(),
+        "The API did not return a 404 NOT FOUND for an unknown concert"
+    );
+}
+
+#[tokio::test]
+async fn concurrent_likely_duplicates_create_a_single_concert() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let body = serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17"
+    });
+
+    // Act
+    let responses = futures_util::future::join_all(
+        (0..4).map(|_| app.post_concert(body.clone()))
+    )
+    .await;
+
+    // Assert
+    let mut statuses = responses.iter().map(|r| r.status().as_u16()).collect::<Vec<_>>();
+    statuses.sort_unstable();
+    assert_eq!(vec![201, 409, 409, 409], statuses);
+    assert_eq!(1, app.concert_count().await);
+}
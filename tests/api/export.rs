@@ -0,0 +1,71 @@
+use crate::helpers::spawn_app;
+
+fn json_lines(body: &str) -> Vec<serde_json::Value> {
+    body.lines()
+        .map(|line| serde_json::from_str(line).expect("Failed to parse a JSON line"))
+        .collect()
+}
+
+#[tokio::test]
+async fn export_streams_artists_then_concerts_as_json_lines() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist_named("Billy Strings").await.0.id;
+    let concert_id = app.create_concert(artist_id, "2021-07-17").await.0.id;
+
+    // Act
+    let response = app.export("format=jsonl").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!("application/jsonl", response.headers()["Content-Type"]);
+    let lines = json_lines(&response.text().await.unwrap());
+    assert_eq!(2, lines.len());
+    assert_eq!("artist", lines[0]["type"]);
+    assert_eq!(artist_id.to_string(), lines[0]["id"]);
+    assert_eq!("concert", lines[1]["type"]);
+    assert_eq!(concert_id.to_string(), lines[1]["id"]);
+}
+
+#[tokio::test]
+async fn export_writes_a_single_csv_header() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist_named("Billy Strings").await.0.id;
+    app.create_concert(artist_id, "2021-07-17").await;
+
+    // Act
+    let response = app.export("format=csv").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let body = response.text().await.unwrap();
+    let lines = body.lines().collect::<Vec<_>>();
+    assert_eq!(3, lines.len());
+    assert!(lines[0].starts_with("record_type,id,name"));
+    assert!(lines[1].starts_with("artist,"));
+    assert!(lines[2].starts_with("concert,"));
+}
+
+#[tokio::test]
+async fn export_filters_by_artist_and_date_range() {
+    // Arrange
+    let app = spawn_app().await;
+    let billy = app.create_artist_named("Billy Strings").await.0.id;
+    let molly = app.create_artist_named("Molly Tuttle").await.0.id;
+    app.create_concert(billy, "2021-07-17").await;
+    let kept = app.create_concert(billy, "2022-07-17").await.0.id;
+    app.create_concert(molly, "2022-07-18").await;
+
+    // Act
+    let response = app
+        .export(&format!("artist_id={}&from=2022-01-01&to=2022-12-31", billy))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let lines = json_lines(&response.text().await.unwrap());
+    assert_eq!(2, lines.len());
+    assert_eq!(billy.to_string(), lines[0]["id"]);
+    assert_eq!(kept.to_string(), lines[1]["id"]);
+}
@@ -0,0 +1,96 @@
+use crate::helpers::{spawn_app_with, test_configuration};
+use allbands::configuration::{ConfigurationError, FeatureFlags};
+use allbands::features::Features;
+use allbands::routes::{ProblemCode, ProblemDetails};
+use allbands::startup::reload_features;
+
+const CSV: &str = "artist,venue,city,state,country,date\n";
+
+#[tokio::test]
+async fn switched_off_endpoints_return_404_until_switched_back_on() {
+    // Arrange
+    let mut configuration = test_configuration().await;
+    configuration.features = FeatureFlags { imports: false, submissions: false };
+    let app = spawn_app_with(configuration).await;
+
+    // Act - Part 1 - Both features are off
+    let import = app.import_concerts(CSV, "").await;
+    let submission = app.api_client
+        .post(format!("{}/submissions/concerts", &app.address))
+        .json(&serde_json::json!({
+            "artist_id": uuid::Uuid::new_v4(),
+            "venue": "The Fillmore",
+            "city": "San Francisco",
+            "state": "CA",
+            "country": "USA",
+            "date": "2021-07-17",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert - Part 1
+    for response in [import, submission] {
+        assert_eq!(404, response.status().as_u16());
+        let problem = response.json::<ProblemDetails>().await.unwrap();
+        assert_eq!(ProblemCode::FeatureDisabled, problem.code);
+    }
+
+    // Act - Part 2 - Imports are switched back on, as on a SIGHUP
+    app.features.replace(FeatureFlags { imports: true, submissions: false });
+    let import = app.import_concerts(CSV, "").await;
+
+    // Assert - Part 2
+    assert_eq!(200, import.status().as_u16());
+}
+
+#[tokio::test]
+async fn switched_off_endpoints_return_404_whatever_the_payload() {
+    // Arrange
+    let mut configuration = test_configuration().await;
+    configuration.features = FeatureFlags { imports: true, submissions: false };
+    let app = spawn_app_with(configuration).await;
+
+    // Act
+    let response = app.api_client
+        .post(format!("{}/submissions/concerts", &app.address))
+        .header("Content-Type", "application/json")
+        .body("not json")
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+    let problem = response.json::<ProblemDetails>().await.unwrap();
+    assert_eq!(ProblemCode::FeatureDisabled, problem.code);
+}
+
+#[tokio::test]
+async fn reloading_the_configuration_replaces_the_feature_flags() {
+    // Arrange
+    let features = Features::new(FeatureFlags::default());
+    let mut configuration = test_configuration().await;
+    configuration.features = FeatureFlags { imports: false, submissions: true };
+
+    // Act
+    reload_features(&features, || Ok(configuration));
+
+    // Assert
+    assert_eq!(FeatureFlags { imports: false, submissions: true }, features.current());
+}
+
+#[test]
+fn a_configuration_that_fails_to_load_keeps_the_feature_flags() {
+    // Arrange
+    let flags = FeatureFlags { imports: false, submissions: true };
+    let features = Features::new(flags);
+
+    // Act
+    reload_features(&features, || {
+        Err(ConfigurationError::Environment("staging is not a supported environment".into()))
+    });
+
+    // Assert
+    assert_eq!(flags, features.current());
+}
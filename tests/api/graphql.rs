@@ -0,0 +1,164 @@
+use crate::helpers::spawn_app;
+
+const CREATE_ARTIST: &str = r#"
+    mutation CreateArtist($input: CreateArtistInput!) {
+        createArtist(input: $input) { id name sortName }
+    }
+"#;
+
+const CREATE_CONCERT: &str = r#"
+    mutation CreateConcert($input: CreateConcertInput!) {
+        createConcert(input: $input) { id venue artist { name } }
+    }
+"#;
+
+#[tokio::test]
+async fn create_mutations_return_the_created_entities() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let artist = app.post_graphql(CREATE_ARTIST, serde_json::json!({
+        "input": {
+            "name": "Billy Strings",
+            "sortName": "Strings, Billy",
+            "disambiguation": "Bluegrass musician from Lansing, MI",
+        }
+    }))
+    .await;
+    let artist_id = artist["data"]["createArtist"]["id"].clone();
+    let concert = app.post_graphql(CREATE_CONCERT, serde_json::json!({
+        "input": {
+            "artistId": artist_id,
+            "venue": "The Fillmore",
+            "city": "San Francisco",
+            "state": "CA",
+            "country": "USA",
+            "date": "2021-07-17",
+        }
+    }))
+    .await;
+
+    // Assert
+    assert!(artist["errors"].is_null(), "{}", artist);
+    assert_eq!(artist["data"]["createArtist"]["sortName"], "Strings, Billy");
+    assert!(concert["errors"].is_null(), "{}", concert);
+    assert_eq!(concert["data"]["createConcert"]["venue"], "The Fillmore");
+    assert_eq!(concert["data"]["createConcert"]["artist"]["name"], "Billy Strings");
+}
+
+#[tokio::test]
+async fn create_artist_reports_validation_errors() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_graphql(CREATE_ARTIST, serde_json::json!({
+        "input": { "name": "", "sortName": "", "disambiguation": "" }
+    }))
+    .await;
+
+    // Assert
+    assert!(response["data"].is_null());
+    assert_eq!(response["errors"][0]["extensions"]["code"], "VALIDATION_ERROR");
+    assert_eq!(response["errors"][0]["extensions"]["fields"][0]["field"], "name");
+}
+
+#[tokio::test]
+async fn artists_resolve_their_concerts_and_concerts_their_artist() {
+    // Arrange
+    let app = spawn_app().await;
+    for (name, venues) in [("Billy Strings", ["The Fillmore", "Red Rocks"]), ("Goose", ["The Capitol Theatre", "MSG"])] {
+        let artist = app.post_graphql(CREATE_ARTIST, serde_json::json!({
+            "input": { "name": name, "sortName": name, "disambiguation": "" }
+        }))
+        .await;
+        for (day, venue) in venues.into_iter().enumerate() {
+            app.post_graphql(CREATE_CONCERT, serde_json::json!({
+                "input": {
+                    "artistId": artist["data"]["createArtist"]["id"],
+                    "venue": venue,
+                    "city": "Somewhere",
+                    "state": "CA",
+                    "country": "USA",
+                    "date": format!("2021-07-1{}", day),
+                }
+            }))
+            .await;
+        }
+    }
+
+    // Act
+    let response = app.post_graphql(
+        "{ artists { name concerts { venue artist { name } } } }",
+        serde_json::json!({}),
+    )
+    .await;
+
+    // Assert
+    assert!(response["errors"].is_null(), "{}", response);
+    let artists = response["data"]["artists"].as_array().unwrap();
+    assert_eq!(artists.len(), 2);
+    for artist in artists {
+        let concerts = artist["concerts"].as_array().unwrap();
+        assert_eq!(concerts.len(), 2);
+        for concert in concerts {
+            assert_eq!(concert["artist"]["name"], artist["name"]);
+        }
+    }
+}
+
+#[tokio::test]
+async fn graphiql_is_served_on_get() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.api_client
+        .get(format!("{}/graphql", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.text().await.unwrap().contains("graphiql"));
+}
+
+const UPDATE_ARTIST: &str = r#"
+    mutation UpdateArtist($input: UpdateArtistInput!, $version: Int!) {
+        updateArtist(input: $input, version: $version) { name version }
+    }
+"#;
+
+#[tokio::test]
+async fn update_artist_requires_the_version_it_was_read_at() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist = app.post_graphql(CREATE_ARTIST, serde_json::json!({
+        "input": { "name": "Goose", "sortName": "Goose", "disambiguation": "" }
+    }))
+    .await;
+    let input = serde_json::json!({
+        "id": artist["data"]["createArtist"]["id"],
+        "name": "Goose",
+        "sortName": "Goose",
+        "disambiguation": "Jam band from Wilton, CT",
+    });
+
+    // Act
+    let without_version = app.post_graphql(
+        "mutation($input: UpdateArtistInput!) { updateArtist(input: $input) { version } }",
+        serde_json::json!({ "input": input }),
+    )
+    .await;
+    let stale = app.post_graphql(UPDATE_ARTIST, serde_json::json!({ "input": input, "version": 0 })).await;
+    let current = app.post_graphql(UPDATE_ARTIST, serde_json::json!({ "input": input, "version": 1 })).await;
+
+    // Assert
+    assert!(without_version["data"].is_null(), "{}", without_version);
+    assert!(!without_version["errors"].is_null());
+    assert_eq!(stale["errors"][0]["extensions"]["code"], "PRECONDITION_FAILED");
+    assert!(current["errors"].is_null(), "{}", current);
+    assert_eq!(current["data"]["updateArtist"]["version"], 2);
+}
@@ -1,4 +1,8 @@
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, test_configuration};
+use actix_web::{test, web, App};
+use allbands::routes::{readiness_check, CheckStatus, Readiness};
+use allbands::startup::{get_connection_pool, Application};
+use redis::aio::ConnectionManager;
 
 #[tokio::test]
 async fn health_check_works() {
@@ -9,7 +13,7 @@ async fn health_check_works() {
 
     // Act
     let response = client
-        .get(&format!("{}/health", &app.address))
+        .get(format!("{}/health", &app.address))
         .send()
         .await
         .expect("Failed to execute request");
@@ -18,3 +22,128 @@ async fn health_check_works() {
     assert!(response.status().is_success());
     assert_eq!(Some(0), response.content_length());
 }
+
+fn newest_migration() -> i64 {
+    sqlx::migrate!("./migrations")
+        .iter()
+        .map(|migration| migration.version)
+        .max()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn readiness_reports_every_dependency_up() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(format!("{}/health/ready", &app.address))
+        .await
+        .expect("Failed to execute request");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let readiness = response.json::<Readiness>().await.unwrap();
+    assert_eq!(CheckStatus::Up, readiness.status);
+    assert_eq!(Some(newest_migration()), readiness.migration_version);
+    let names = readiness.checks.iter().map(|c| c.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(vec!["postgres", "redis", "migrations"], names);
+}
+
+#[tokio::test]
+async fn readiness_fails_when_the_database_is_behind_the_migrations() {
+    // Arrange
+    let app = spawn_app().await;
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = $1")
+        .bind(newest_migration())
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let response = reqwest::get(format!("{}/health/ready", &app.address))
+        .await
+        .expect("Failed to execute request");
+
+    // Assert
+    assert_eq!(503, response.status().as_u16());
+    let readiness = response.json::<Readiness>().await.unwrap();
+    assert_eq!(CheckStatus::Down, readiness.status);
+    let migrations = readiness.checks.iter().find(|c| c.name == "migrations").unwrap();
+    assert_eq!(CheckStatus::Down, migrations.status);
+    let postgres = readiness.checks.iter().find(|c| c.name == "postgres").unwrap();
+    assert_eq!(CheckStatus::Up, postgres.status);
+}
+
+/// A local port nothing listens on.
+fn closed_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+fn check_status(readiness: &Readiness, name: &str) -> CheckStatus {
+    readiness.checks.iter().find(|c| c.name == name).unwrap().status
+}
+
+#[tokio::test]
+async fn readiness_fails_when_postgres_is_unreachable() {
+    // Arrange
+    let mut configuration = test_configuration().await;
+    configuration.database.port = closed_port();
+    let application = Application::build(configuration)
+        .await
+        .expect("Failed to build the application");
+    let address = format!("http://localhost:{}", application.port());
+    tokio::spawn(application.run_until_stopped());
+
+    // Act
+    let response = reqwest::get(format!("{}/health/ready", address))
+        .await
+        .expect("Failed to execute request");
+
+    // Assert
+    assert_eq!(503, response.status().as_u16());
+    let readiness = response.json::<Readiness>().await.unwrap();
+    assert_eq!(CheckStatus::Down, readiness.status);
+    assert_eq!(CheckStatus::Down, check_status(&readiness, "postgres"));
+    assert_eq!(CheckStatus::Down, check_status(&readiness, "migrations"));
+    assert_eq!(CheckStatus::Up, check_status(&readiness, "redis"));
+    assert_eq!(None, readiness.migration_version);
+}
+
+#[tokio::test]
+async fn readiness_fails_when_redis_is_unreachable() {
+    // Arrange
+    // A Redis server that goes away once the connection is made.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let redis_address = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let _connection = listener.accept().await.unwrap();
+        std::future::pending::<()>().await;
+    });
+    let client = redis::Client::open(format!("redis://{}", redis_address)).unwrap();
+    let redis = ConnectionManager::new(client).await.expect("Failed to connect");
+    server.abort();
+    let configuration = test_configuration().await;
+    let service = test::init_service(
+        App::new()
+            .app_data(web::Data::new(get_connection_pool(&configuration.database)))
+            .app_data(web::Data::new(redis))
+            .route("/health/ready", web::get().to(readiness_check)),
+    )
+    .await;
+
+    // Act
+    let request = test::TestRequest::get().uri("/health/ready").to_request();
+    let response = test::call_service(&service, request).await;
+
+    // Assert
+    assert_eq!(503, response.status().as_u16());
+    let readiness: Readiness = test::read_body_json(response).await;
+    assert_eq!(CheckStatus::Down, readiness.status);
+    assert_eq!(CheckStatus::Down, check_status(&readiness, "redis"));
+    assert_eq!(CheckStatus::Up, check_status(&readiness, "postgres"));
+}
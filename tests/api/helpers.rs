@@ -1,11 +1,23 @@
 use allbands::{
-    configuration::{get_configuration, DatabaseSettings},
+    authentication::create_moderator,
+    configuration::{get_configuration, DatabaseSettings, Settings},
+    domain::{Artist, Concert},
+    features::Features,
+    openapi::api_doc,
     startup::{Application, get_connection_pool},
     telemetry::{get_subscriber, init_subscriber},
 };
 use sqlx::{Connection, Executor, PgConnection, PgPool};
+use secrecy::Secret;
 use uuid::Uuid;
 use once_cell::sync::Lazy;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
 
 static TRACING: Lazy<()> = Lazy::new(|| {
     let default_filter_level = "info".to_string();
@@ -18,84 +30,519 @@ static TRACING: Lazy<()> = Lazy::new(|| {
         let subscriber = get_subscriber(
             subscriber_name,
             default_filter_level,
-            std::io::stdout
+            std::io::stdout,
+            None,
         );
 
-        init_subscriber(subscriber);
+        init_subscriber(subscriber.with(UndocumentedResponses::default()));
     } else {
         let subscriber = get_subscriber(
             subscriber_name,
             default_filter_level,
-            std::io::sink
+            std::io::sink,
+            None,
         );
 
-        init_subscriber(subscriber);
+        init_subscriber(subscriber.with(UndocumentedResponses::default()));
     }
 });
 
+/// The statuses the served OpenAPI document lists for each operation, by
+/// method and path template.
+static DOCUMENTED_STATUSES: Lazy<HashMap<(String, String), BTreeSet<u16>>> = Lazy::new(|| {
+    let configuration = get_configuration().expect("Failed to read configuration");
+    let spec = serde_json::to_value(api_doc(&configuration.rate_limit)).unwrap();
+    let mut statuses = HashMap::new();
+    for (path, item) in spec["paths"].as_object().into_iter().flatten() {
+        for (method, operation) in item.as_object().into_iter().flatten() {
+            let documented = operation["responses"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter_map(|(status, _)| status.parse().ok())
+                .collect();
+            statuses.insert((method.to_uppercase(), path.clone()), documented);
+        }
+    }
+    statuses
+});
+
+/// Whether the OpenAPI document lists `status` for the route with `method`
+/// and template `route`. Server errors are never documented, so they pass.
+pub fn is_documented(method: &str, route: &str, status: u16) -> bool {
+    status >= 500
+        || DOCUMENTED_STATUSES
+            .get(&(method.to_string(), route.to_string()))
+            .is_some_and(|statuses| statuses.contains(&status))
+}
+
+/// The host, method, route and status of a response.
+type Response = (String, String, String, u16);
+
+/// Every response whose status the OpenAPI document doesn't list, across
+/// the test apps of this process.
+static UNDOCUMENTED: Lazy<Mutex<BTreeSet<Response>>> = Lazy::new(Default::default);
+
+/// Reads the root span `TracingLogger` opens for each request, so that
+/// every request a test makes also checks the OpenAPI document against the
+/// status the handler returned.
+#[derive(Default)]
+struct UndocumentedResponses {
+    requests: Mutex<HashMap<Id, RequestFields>>,
+}
+
+#[derive(Default)]
+struct RequestFields {
+    host: String,
+    method: String,
+    route: String,
+    status: Option<i64>,
+}
+
+impl Visit for RequestFields {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        if field.name() == "http.status_code" {
+            self.status = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let value = format!("{:?}", value);
+        match field.name() {
+            "http.host" => self.host = value,
+            "http.method" => self.method = value,
+            "http.route" => self.route = value,
+            _ => {}
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for UndocumentedResponses {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        if attrs.metadata().fields().field("http.route").is_some() {
+            let mut fields = RequestFields::default();
+            attrs.record(&mut fields);
+            self.requests.lock().unwrap().insert(id.clone(), fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut requests = self.requests.lock().unwrap();
+        let Some(fields) = requests.get_mut(id) else {
+            return;
+        };
+        values.record(fields);
+        // `default` is the route of requests that matched none.
+        if let (Some(status), false) = (fields.status, fields.route == "default") {
+            let status = status as u16;
+            if !is_documented(&fields.method, &fields.route, status) {
+                UNDOCUMENTED.lock().unwrap().insert((
+                    fields.host.clone(),
+                    fields.method.clone(),
+                    fields.route.clone(),
+                    status,
+                ));
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, _ctx: Context<'_, S>) {
+        self.requests.lock().unwrap().remove(&id);
+    }
+}
+
 pub struct TestApp {
     pub address: String,
     pub port: u16,
     pub db_pool: PgPool,
     pub api_client: reqwest::Client,
+    pub test_moderator: TestModerator,
+    /// The application's feature flags, to switch them as a SIGHUP would
+    pub features: Features,
+}
+
+impl Drop for TestApp {
+    /// Fail the test when the application answered with a status that its
+    /// OpenAPI document doesn't list for the route.
+    fn drop(&mut self) {
+        let host = format!("localhost:{}", self.port);
+        let seen = UNDOCUMENTED
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(h, ..)| *h == host)
+            .map(|(_, method, route, status)| (method.clone(), route.clone(), *status))
+            .collect::<Vec<_>>();
+        if !seen.is_empty() && !std::thread::panicking() {
+            panic!("These responses are missing from the OpenAPI document: {:?}", seen);
+        }
+    }
+}
+
+pub struct TestModerator {
+    pub username: String,
+    pub password: String,
+}
+
+impl TestModerator {
+    fn generate() -> Self {
+        Self {
+            username: Uuid::new_v4().to_string(),
+            password: Uuid::new_v4().to_string(),
+        }
+    }
+
+    async fn store(&self, pool: &PgPool) {
+        create_moderator(&self.username, Secret::new(self.password.clone()), pool)
+            .await
+            .expect("Failed to store the test moderator");
+    }
+
+    pub async fn login(&self, app: &TestApp) {
+        app.post_login(&serde_json::json!({
+            "username": &self.username,
+            "password": &self.password,
+        }))
+        .await;
+    }
 }
 
 impl TestApp {
     pub async fn post_artist(&self, body: serde_json::Value) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/artists", &self.address))
+            .post(format!("{}/artists", &self.address))
             .json(&body)
             .send()
             .await
             .expect("Failed to execute request")
     }
 
+    /// POST `body` to `path` with an `Idempotency-Key` header, as `client_id`.
+    pub async fn post_idempotent(
+        &self,
+        path: &str,
+        key: &str,
+        client_id: &str,
+        body: &serde_json::Value,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}{}", &self.address, path))
+            .header("Idempotency-Key", key)
+            .header("X-Client-Id", client_id)
+            .json(body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
     pub async fn get_artist_by_id(&self, id: uuid::Uuid) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/artists/{}", &self.address, id))
+            .get(format!("{}/artists/{}", &self.address, id))
             .send()
             .await
             .expect("Failed to execute the request")
     }
 
-    pub async fn update_artist(&self, id: uuid::Uuid, body: serde_json::Value) -> reqwest::Response {
+    /// The artist's concerts, with `query` appended, e.g. `when=past&page=2`
+    pub async fn get_artist_concerts(&self, id: uuid::Uuid, query: &str) -> reqwest::Response {
         self.api_client
-            .put(&format!("{}/artists/{}", &self.address, id))
+            .get(format!("{}/artists/{}/concerts?{}", &self.address, id, query))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn update_artist(&self, id: uuid::Uuid, if_match: &str, body: serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .put(format!("{}/artists/{}", &self.address, id))
+            .header("If-Match", if_match)
             .json(&body)
             .send()
             .await
             .expect("Failed to execute the request")
     }
 
+    pub async fn patch_artist(&self, id: uuid::Uuid, if_match: &str, body: serde_json::Value) -> reqwest::Response {
+        self.patch(format!("{}/artists/{}", &self.address, id), if_match, body).await
+    }
+
     pub async fn post_concert(&self, body: serde_json::Value) -> reqwest::Response {
         self.api_client
-            .post(&format!("{}/concerts", &self.address))
+            .post(format!("{}/concerts", &self.address))
+            .json(&body)
+            .send()
+            .await
+            .expect("Failed to execute request")
+    }
+
+    pub async fn post_concert_forced(&self, body: serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/concerts?force=true", &self.address))
             .json(&body)
             .send()
             .await
             .expect("Failed to execute request")
     }
 
+    /// Create Billy Strings, returning the artist and its `ETag`.
+    pub async fn create_artist(&self) -> (Artist, String) {
+        created(self.post_artist(serde_json::json!({
+            "name": "Billy Strings",
+            "sort_name": "Strings, Billy",
+            "disambiguation": "Bluegrass musician from Lansing, MI",
+        }))
+        .await)
+        .await
+    }
+
+    /// Create an artist sorted under its own `name`, returning the artist
+    /// and its `ETag`.
+    pub async fn create_artist_named(&self, name: &str) -> (Artist, String) {
+        created(self.post_artist(serde_json::json!({
+            "name": name,
+            "sort_name": name,
+            "disambiguation": "",
+        }))
+        .await)
+        .await
+    }
+
+    /// Create a concert at The Fillmore on `date`, returning the concert and
+    /// its `ETag`.
+    pub async fn create_concert(&self, artist_id: Uuid, date: &str) -> (Concert, String) {
+        created(self.post_concert(serde_json::json!({
+            "artist_id": artist_id,
+            "venue": "The Fillmore",
+            "city": "San Francisco",
+            "state": "CA",
+            "country": "USA",
+            "date": date,
+        }))
+        .await)
+        .await
+    }
+
+    pub async fn get_concert_duplicates(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/concerts/duplicates", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn get_concert_if_none_match(&self, id: uuid::Uuid, etag: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/concerts/{}", &self.address, id))
+            .header("If-None-Match", etag)
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
     pub async fn get_concert_by_id(&self, id: uuid::Uuid) -> reqwest::Response {
         self.api_client
-            .get(&format!("{}/concerts/{}", &self.address, id))
+            .get(format!("{}/concerts/{}", &self.address, id))
             .send()
             .await
             .expect("Failed to execute the request")
     }
 
-    pub async fn update_concert(&self, id: uuid::Uuid, body: serde_json::Value) -> reqwest::Response {
+    pub async fn update_concert(&self, id: uuid::Uuid, if_match: &str, body: serde_json::Value) -> reqwest::Response {
         self.api_client
-            .put(&format!("{}/concerts/{}", &self.address, id))
+            .put(format!("{}/concerts/{}", &self.address, id))
+            .header("If-Match", if_match)
             .json(&body)
             .send()
             .await
             .expect("Failed to execute the request")
     }
+
+    pub async fn patch_concert(&self, id: uuid::Uuid, if_match: &str, body: serde_json::Value) -> reqwest::Response {
+        self.patch(format!("{}/concerts/{}", &self.address, id), if_match, body).await
+    }
+
+    async fn patch(&self, url: String, if_match: &str, body: serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .patch(url)
+            .header("If-Match", if_match)
+            .header("Content-Type", "application/merge-patch+json")
+            .body(body.to_string())
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    /// The history of the artist or concert at `path`, e.g. `/artists/{id}`
+    pub async fn get_history(&self, path: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}{}/history", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn revert(&self, path: &str, revision: i32, if_match: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}{}/history/{}/revert", &self.address, path, revision))
+            .header("If-Match", if_match)
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn post_login<Body: serde::Serialize>(&self, body: &Body) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/login", &self.address))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn get_login_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/login", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn get_moderation_queue(&self) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/moderation/submissions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn get_moderation_queue_html(&self) -> String {
+        self.get_moderation_queue().await.text().await.unwrap()
+    }
+
+    /// Approve or reject a submission, `decision` being `approve` or `reject`
+    pub async fn review_submission(&self, id: Uuid, decision: &str) -> reqwest::Response {
+        let csrf_token = self.csrf_token().await;
+        self.api_client
+            .post(format!("{}/moderation/submissions/{}/{}", &self.address, id, decision))
+            .form(&serde_json::json!({ "csrf_token": csrf_token }))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    /// The CSRF token of the logged in session, as the moderation queue
+    /// embeds it in its forms.
+    pub async fn csrf_token(&self) -> String {
+        let html = self.get_moderation_queue_html().await;
+        let (_, rest) = html
+            .split_once(r#"name="csrf_token" value=""#)
+            .expect("The page has no CSRF token");
+        rest.split('"').next().unwrap().to_string()
+    }
+
+    pub async fn get_admin_page(&self, path: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}{}", &self.address, path))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn get_admin_html(&self, path: &str) -> String {
+        self.get_admin_page(path).await.text().await.unwrap()
+    }
+
+    pub async fn post_admin_form<Body: serde::Serialize>(&self, path: &str, body: &Body) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}{}", &self.address, path))
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn import_concerts(&self, csv: &str, query: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/import/concerts?{}", &self.address, query))
+            .header("Content-Type", "text/csv")
+            .body(csv.to_string())
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn export(&self, query: &str) -> reqwest::Response {
+        self.api_client
+            .get(format!("{}/export?{}", &self.address, query))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn import_setlistfm(&self, body: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/import/setlistfm", &self.address))
+            .header("Content-Type", "application/json")
+            .body(body.to_string())
+            .send()
+            .await
+            .expect("Failed to execute the request")
+    }
+
+    pub async fn post_graphql(&self, query: &str, variables: serde_json::Value) -> serde_json::Value {
+        self.api_client
+            .post(format!("{}/graphql", &self.address))
+            .json(&serde_json::json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .expect("Failed to execute the request")
+            .json()
+            .await
+            .expect("Failed to deserialize the GraphQL response")
+    }
+
+    pub async fn artist_count(&self) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM artists")
+            .fetch_one(&self.db_pool)
+            .await
+            .expect("Failed to count artists")
+    }
+
+    pub async fn concert_count(&self) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM concerts")
+            .fetch_one(&self.db_pool)
+            .await
+            .expect("Failed to count concerts")
+    }
 }
 
 // Launch our application in the background ~somehow~
-pub async fn spawn_app() -> TestApp {
+/// The entity a create responded with, and its `ETag`.
+async fn created<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> (T, String) {
+    assert_eq!(201, response.status().as_u16(), "Failed to create the entity");
+    let etag = etag(&response);
+    let entity = response.json::<T>().await.expect("Failed to deserialize the entity");
+
+    (entity, etag)
+}
+
+/// The `ETag` header of a response.
+pub fn etag(response: &reqwest::Response) -> String {
+    response
+        .headers()
+        .get("ETag")
+        .expect("The response has no ETag")
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+/// The configuration of an application on a random port, backed by a freshly
+/// migrated database of its own.
+pub async fn test_configuration() -> Settings {
     // The first time `initialize` is invoked the code in `TRACING` is executed.
     // All other invocations will instead skip execution.
     Lazy::force(&TRACING);
@@ -104,10 +551,26 @@ pub async fn spawn_app() -> TestApp {
         let mut c = get_configuration().expect("Failed to read configuration");
         c.database.database_name = Uuid::new_v4().to_string();
         c.application.port = 0;
+        c.musicbrainz.artist_dump = Some("tests/fixtures/musicbrainz/artist".into());
+        // Every test app connects from localhost to the same Redis, so each
+        // counts its requests and caches its responses apart from the
+        // others'.
+        c.rate_limit.key_prefix = format!("rate_limit:{}", Uuid::new_v4());
+        c.cache.key_prefix = format!("cache:{}", Uuid::new_v4());
         c
     };
 
     configure_database(&configuration.database).await;
+    configuration
+}
+
+pub async fn spawn_app() -> TestApp {
+    spawn_app_with(test_configuration().await).await
+}
+
+/// Spawn the application with a configuration from `test_configuration`,
+/// changed as the test needs.
+pub async fn spawn_app_with(configuration: Settings) -> TestApp {
 
     let application = Application::build(configuration.clone())
         .await
@@ -115,7 +578,8 @@ pub async fn spawn_app() -> TestApp {
     let application_port = application.port();
     // Get the port before spawning the application
     let address = format!("http://localhost:{}", application.port());
-    let _ = tokio::spawn(application.run_until_stopped());
+    let features = application.features();
+    tokio::spawn(application.run_until_stopped());
 
     let client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
@@ -125,12 +589,16 @@ pub async fn spawn_app() -> TestApp {
 
     
 
-    TestApp {
+    let test_app = TestApp {
         address,
         port: application_port,
         db_pool: get_connection_pool(&configuration.database),
         api_client: client,
-    }
+        test_moderator: TestModerator::generate(),
+        features,
+    };
+    test_app.test_moderator.store(&test_app.db_pool).await;
+    test_app
 }
 
 async fn configure_database(config: &DatabaseSettings) -> PgPool {
@@ -158,8 +626,8 @@ async fn configure_database(config: &DatabaseSettings) -> PgPool {
 }
 
 // Little helper function - we will be doing this check several times throughout
+#[allow(dead_code)]
 pub fn assert_is_redirect_to(response: &reqwest::Response, location: &str) {
     assert_eq!(response.status().as_u16(), 303);
     assert_eq!(response.headers().get("Location").unwrap(), location);
 }
-
@@ -0,0 +1,99 @@
+use crate::helpers::{etag, spawn_app, TestApp};
+use allbands::domain::{Artist, AuditEntry, Concert};
+use uuid::Uuid;
+
+async fn history(app: &TestApp, path: &str) -> Vec<AuditEntry> {
+    app.get_history(path)
+        .await
+        .json::<Vec<AuditEntry>>()
+        .await
+        .expect("Failed to deserialize the history")
+}
+
+#[tokio::test]
+async fn history_records_who_changed_which_fields() {
+    // Arrange
+    let app = spawn_app().await;
+    let (Artist { id, .. }, etag) = app.create_artist().await;
+
+    // Act
+    app.api_client
+        .patch(format!("{}/artists/{}", &app.address, id))
+        .header("X-Client-Id", "bob")
+        .header("If-Match", etag)
+        .header("Content-Type", "application/merge-patch+json")
+        .body(r#"{"name": "Billy Strings Band"}"#)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    let history = history(&app, &format!("/artists/{}", id)).await;
+    assert_eq!(2, history.len());
+    assert_eq!((1, "created", "anonymous"), (history[0].revision, history[0].action.as_str(), history[0].actor.as_str()));
+    assert_eq!((2, "updated", "bob"), (history[1].revision, history[1].action.as_str(), history[1].actor.as_str()));
+    assert_eq!(
+        serde_json::json!({"name": {"before": "Billy Strings", "after": "Billy Strings Band"}}),
+        history[1].changes,
+    );
+}
+
+#[tokio::test]
+async fn reverting_a_concert_restores_the_revision_as_a_new_version() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let response = app
+        .post_concert(serde_json::json!({
+            "artist_id": artist_id,
+            "venue": "The Fillmore",
+            "city": "San Francisco",
+            "state": "CA",
+            "country": "USA",
+            "date": "2021-07-17",
+        }))
+        .await;
+    let concert = response.json::<Concert>().await.expect("Failed to deserialize the concert");
+    let path = format!("/concerts/{}", concert.id);
+    let response = app
+        .patch_concert(concert.id, "*", serde_json::json!({"venue": "Red Rocks", "state": null}))
+        .await;
+    let etag = etag(&response);
+
+    // Act
+    let response = app.revert(&path, 1, &etag).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let reverted = response.json::<Concert>().await.expect("Failed to deserialize the concert");
+    assert_eq!("The Fillmore", reverted.venue);
+    assert_eq!(Some("CA".to_string()), reverted.state);
+    assert_eq!(3, reverted.version);
+    let history = history(&app, &path).await;
+    assert_eq!("reverted to revision 1", history[2].action);
+}
+
+#[tokio::test]
+async fn reverting_to_an_unknown_revision_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+    let (Artist { id, .. }, etag) = app.create_artist().await;
+
+    // Act
+    let response = app.revert(&format!("/artists/{}", id), 7, &etag).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn history_of_an_unknown_artist_returns_404() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_history(&format!("/artists/{}", Uuid::new_v4())).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+}
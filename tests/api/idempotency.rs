@@ -0,0 +1,180 @@
+use crate::helpers::spawn_app;
+use allbands::configuration::IdempotencySettings;
+use allbands::domain::Artist;
+use allbands::idempotency::{delete_expired_keys, delete_expired_keys_periodically};
+use allbands::routes::{ProblemCode, ProblemDetails};
+
+fn artist(name: &str) -> serde_json::Value {
+    serde_json::json!({
+        "name": name,
+        "sort_name": "Strings, Billy",
+        "disambiguation": "Bluegrass musician from Lansing, MI",
+    })
+}
+
+#[tokio::test]
+async fn retrying_a_create_artist_replays_the_saved_response() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = artist("Billy Strings");
+    let first = app.post_idempotent("/artists", "key-1", "phone", &body).await;
+    let first_etag = first.headers().get("ETag").cloned();
+    let first = first.json::<Artist>().await.expect("Failed to deserialize the artist");
+
+    // Act
+    let retry = app.post_idempotent("/artists", "key-1", "phone", &body).await;
+
+    // Assert
+    assert_eq!(201, retry.status().as_u16());
+    assert_eq!(first_etag, retry.headers().get("ETag").cloned());
+    assert!(retry.headers().contains_key("Idempotent-Replayed"));
+    let retry = retry.json::<Artist>().await.expect("Failed to deserialize the artist");
+    assert_eq!(first.id, retry.id);
+    assert_eq!(1, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn retrying_a_create_concert_replays_the_saved_response_instead_of_a_duplicate() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let body = serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17",
+    });
+    app.post_idempotent("/concerts", "key-1", "phone", &body).await;
+
+    // Act
+    let retry = app.post_idempotent("/concerts", "key-1", "phone", &body).await;
+
+    // Assert
+    assert_eq!(201, retry.status().as_u16());
+    assert_eq!(1, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn reusing_a_key_for_a_different_body_returns_422() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_idempotent("/artists", "key-1", "phone", &artist("Billy Strings")).await;
+
+    // Act
+    let response = app
+        .post_idempotent("/artists", "key-1", "phone", &artist("Molly Tuttle"))
+        .await;
+
+    // Assert
+    assert_eq!(422, response.status().as_u16());
+    let problem = response
+        .json::<ProblemDetails>()
+        .await
+        .expect("Failed to deserialize the problem");
+    assert_eq!(ProblemCode::IdempotencyKeyReused, problem.code);
+    assert_eq!(1, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn keys_are_scoped_to_the_client() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_idempotent("/artists", "key-1", "phone", &artist("Billy Strings")).await;
+
+    // Act
+    let response = app
+        .post_idempotent("/artists", "key-1", "tablet", &artist("Molly Tuttle"))
+        .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    assert_eq!(2, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn an_expired_key_can_be_reused() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_idempotent("/artists", "key-1", "phone", &artist("Billy Strings")).await;
+    sqlx::query("UPDATE idempotency SET created_at = now() - interval '25 hours'")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to age the idempotency key");
+
+    // Act
+    let response = app
+        .post_idempotent("/artists", "key-1", "phone", &artist("Molly Tuttle"))
+        .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    assert_eq!(2, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn keys_are_scoped_to_the_logged_in_moderator_whatever_the_client_id() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_idempotent("/artists", "key-1", "phone", &artist("Billy Strings")).await;
+    app.test_moderator.login(&app).await;
+
+    // Act
+    let response = app
+        .post_idempotent("/artists", "key-1", "phone", &artist("Molly Tuttle"))
+        .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    assert!(!response.headers().contains_key("Idempotent-Replayed"));
+    assert_eq!(2, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn deleting_expired_keys_keeps_the_others() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_idempotent("/artists", "key-1", "phone", &artist("Billy Strings")).await;
+    app.post_idempotent("/artists", "key-2", "phone", &artist("Molly Tuttle")).await;
+    sqlx::query("UPDATE idempotency SET created_at = now() - interval '25 hours' WHERE idempotency_key = 'key-1'")
+        .execute(&app.db_pool)
+        .await
+        .expect("Failed to age the idempotency key");
+
+    // Act
+    let deleted = delete_expired_keys(&app.db_pool, IdempotencySettings::default().expiry())
+        .await
+        .expect("Failed to delete the expired keys");
+
+    // Assert
+    assert_eq!(1, deleted);
+    let keys = sqlx::query_scalar::<_, String>("SELECT idempotency_key FROM idempotency")
+        .fetch_all(&app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(vec!["key-2".to_string()], keys);
+}
+
+#[tokio::test]
+async fn the_periodic_cleanup_stops_when_told_to() {
+    // Arrange
+    let app = spawn_app().await;
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let cleanup = tokio::spawn(delete_expired_keys_periodically(
+        app.db_pool.clone(),
+        IdempotencySettings::default().expiry(),
+        async {
+            let _ = stopped.await;
+        },
+    ));
+
+    // Act
+    stop.send(()).unwrap();
+
+    // Assert
+    tokio::time::timeout(std::time::Duration::from_secs(1), cleanup)
+        .await
+        .expect("The cleanup kept running")
+        .unwrap();
+}
@@ -0,0 +1,137 @@
+use crate::helpers::{spawn_app, spawn_app_with, test_configuration};
+use allbands::routes::{ImportReport, RowStatus};
+
+#[tokio::test]
+async fn import_concerts_resolves_artists_by_name_and_id() {
+    // Arrange
+    let app = spawn_app().await;
+    let (artist, _) = app.create_artist().await;
+    let csv = format!(
+        "artist,venue,city,state,country,date\n\
+         Billy Strings,The Fillmore,San Francisco,CA,USA,2021-07-17\n\
+         {},Red Rocks,Morrison,CO,USA,2022-06-10\n",
+        artist.id
+    );
+
+    // Act
+    let response = app.import_concerts(&csv, "mode=all_or_nothing").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let report = response.json::<ImportReport>().await.unwrap();
+    assert!(report.committed);
+    assert_eq!(2, report.created);
+    assert!(report.rows.iter().all(|row| row.status == RowStatus::Created));
+    assert_eq!(2, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn import_concerts_all_or_nothing_rejects_the_whole_file() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_artist().await;
+    let csv = "artist,venue,city,state,country,date\n\
+               Billy Strings,The Fillmore,San Francisco,CA,USA,2021-07-17\n\
+               Billy Strings,The Fillmore,San Francisco,CA,USA,17/07/2021\n\
+               Unknown Artist,The Fillmore,San Francisco,CA,USA,2021-07-18\n";
+
+    // Act
+    let response = app.import_concerts(csv, "mode=all_or_nothing").await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let report = response.json::<ImportReport>().await.unwrap();
+    assert!(!report.committed);
+    assert_eq!(2, report.invalid);
+    assert_eq!(RowStatus::Skipped, report.rows[0].status);
+    assert_eq!(RowStatus::Invalid, report.rows[1].status);
+    assert_eq!(3, report.rows[1].row);
+    assert_eq!(RowStatus::Invalid, report.rows[2].status);
+    assert_eq!(0, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn import_concerts_best_effort_writes_valid_rows() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_artist().await;
+    let csv = "artist,venue,city,state,country,date\n\
+               Billy Strings,The Fillmore,San Francisco,CA,USA,2021-07-17\n\
+               Billy Strings,The Fillmore,San Francisco,California,USA,2021-07-18\n";
+
+    // Act
+    let response = app.import_concerts(csv, "mode=best_effort").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let report = response.json::<ImportReport>().await.unwrap();
+    assert!(report.committed);
+    assert_eq!(1, report.created);
+    assert_eq!(1, report.invalid);
+    assert!(report.rows[0].concert_id.is_some());
+    assert!(report.rows[1].error.is_some());
+    assert_eq!(1, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn import_concerts_dry_run_writes_nothing() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_artist().await;
+    let csv = "artist,venue,city,state,country,date\n\
+               Billy Strings,The Fillmore,San Francisco,CA,USA,2021-07-17\n";
+
+    // Act
+    let response = app.import_concerts(csv, "mode=best_effort&dry_run=true").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let report = response.json::<ImportReport>().await.unwrap();
+    assert!(!report.committed);
+    assert_eq!(RowStatus::Valid, report.rows[0].status);
+    assert_eq!(0, app.concert_count().await);
+}
+
+/// A header and `rows` valid rows for the artist created by `create_artist`,
+/// each at a different venue.
+fn concerts_csv(rows: usize) -> String {
+    let mut csv = "artist,venue,city,state,country,date\n".to_string();
+    for row in 0..rows {
+        csv.push_str(&format!("Billy Strings,Venue {},San Francisco,CA,USA,2021-07-17\n", row));
+    }
+    csv
+}
+
+#[tokio::test]
+async fn import_concerts_reads_bodies_over_actix_default_limit() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_artist().await;
+    let csv = concerts_csv(6_000);
+    assert!(csv.len() > 256 * 1024);
+
+    // Act
+    let response = app.import_concerts(&csv, "mode=best_effort&dry_run=true").await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let report = response.json::<ImportReport>().await.unwrap();
+    assert_eq!(6_000, report.rows.len());
+    assert!(report.rows.iter().all(|row| row.status == RowStatus::Valid));
+}
+
+#[tokio::test]
+async fn import_bodies_over_the_configured_limit_are_refused() {
+    // Arrange
+    let mut configuration = test_configuration().await;
+    configuration.import.max_body_bytes = 1024;
+    let app = spawn_app_with(configuration).await;
+    app.create_artist().await;
+
+    // Act
+    let response = app.import_concerts(&concerts_csv(100), "mode=best_effort").await;
+
+    // Assert
+    assert_eq!(413, response.status().as_u16());
+    assert_eq!(0, app.concert_count().await);
+}
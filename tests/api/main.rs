@@ -1,4 +1,24 @@
 mod helpers;
 mod health_check;
+mod history;
+mod admin;
+mod cli;
 mod artist;
+mod cache;
 mod concert;
+mod export;
+mod features;
+mod graphql;
+mod idempotency;
+mod import;
+mod moderation;
+mod musicbrainz;
+mod merge_patch;
+mod metrics;
+mod openapi;
+mod preconditions;
+mod problem;
+mod rate_limit;
+mod setlistfm;
+mod shutdown;
+mod telemetry;
@@ -0,0 +1,86 @@
+use crate::helpers::spawn_app;
+use allbands::domain::{Artist, Concert};
+
+#[tokio::test]
+async fn patch_artist_changes_only_the_supplied_fields() {
+    // Arrange
+    let app = spawn_app().await;
+    let (Artist { id, .. }, etag) = app.create_artist().await;
+
+    // Act
+    let response = app
+        .patch_artist(id, &etag, serde_json::json!({"disambiguation": "Guitarist"}))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let artist = response.json::<Artist>().await.expect("Failed to deserialize the artist");
+    assert_eq!(artist.name, "Billy Strings");
+    assert_eq!(artist.sort_name, "Strings, Billy");
+    assert_eq!(artist.disambiguation, "Guitarist");
+    assert_eq!(artist.version, 2);
+}
+
+#[tokio::test]
+async fn patch_concert_with_null_state_removes_it() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let (Concert { id, .. }, etag) = app.create_concert(artist_id, "2021-07-17").await;
+
+    // Act
+    let response = app
+        .patch_concert(id, &etag, serde_json::json!({"state": null, "venue": "Red Rocks"}))
+        .await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let concert = response.json::<Concert>().await.expect("Failed to deserialize the concert");
+    assert_eq!(concert.state, None);
+    assert_eq!(concert.venue, "Red Rocks");
+    assert_eq!(concert.city, "San Francisco");
+}
+
+#[tokio::test]
+async fn patch_reports_only_the_invalid_supplied_fields() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let (Concert { id, .. }, etag) = app.create_concert(artist_id, "2021-07-17").await;
+
+    // Act
+    let response = app
+        .patch_concert(id, &etag, serde_json::json!({"date": "not a date", "city": null}))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let problem = response.json::<serde_json::Value>().await.expect("Failed to deserialize the problem");
+    let mut fields: Vec<_> = problem["errors"]
+        .as_array()
+        .expect("Expected per-field errors")
+        .iter()
+        .map(|error| error["field"].as_str().unwrap().to_string())
+        .collect();
+    fields.sort();
+    assert_eq!(fields, vec!["city", "date"]);
+}
+
+#[tokio::test]
+async fn patch_artist_returns_428_without_if_match() {
+    // Arrange
+    let app = spawn_app().await;
+    let id = app.create_artist().await.0.id;
+
+    // Act
+    let response = app.api_client
+        .patch(format!("{}/artists/{}", &app.address, id))
+        .header("Content-Type", "application/merge-patch+json")
+        .body(r#"{"name": "Billy"}"#)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(428, response.status().as_u16());
+}
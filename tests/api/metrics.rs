@@ -0,0 +1,49 @@
+use crate::helpers::spawn_app;
+use uuid::Uuid;
+
+async fn scrape(address: &str) -> String {
+    let response = reqwest::get(format!("{}/metrics", address))
+        .await
+        .expect("Failed to execute request");
+    assert_eq!(200, response.status().as_u16());
+    response.text().await.unwrap()
+}
+
+#[tokio::test]
+async fn requests_are_counted_by_route_template() {
+    // Arrange
+    let app = spawn_app().await;
+    let id = Uuid::new_v4();
+    reqwest::get(format!("{}/concerts/{}", &app.address, id))
+        .await
+        .expect("Failed to execute request");
+
+    // Act
+    let metrics = scrape(&app.address).await;
+
+    // Assert
+    assert!(metrics.contains(r#"http_requests_total{method="GET",route="/concerts/{id}",status="404"}"#));
+    assert!(metrics.contains(r#"http_request_duration_seconds_count{method="GET",route="/concerts/{id}"}"#));
+    assert!(!metrics.contains(&id.to_string()));
+}
+
+#[tokio::test]
+async fn pool_and_domain_metrics_are_exposed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_artist(serde_json::json!({
+        "name": "Goose",
+        "sort_name": "Goose",
+        "disambiguation": "",
+    }))
+    .await;
+
+    // Act
+    let metrics = scrape(&app.address).await;
+
+    // Assert
+    assert!(metrics.contains(r#"entities_created_total{entity_type="artist"}"#));
+    assert!(metrics.contains("db_pool_max_connections 10"));
+    assert!(metrics.contains("db_pool_idle_connections"));
+    assert!(metrics.contains("db_pool_connections"));
+}
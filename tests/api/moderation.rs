@@ -0,0 +1,225 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app, TestApp};
+use allbands::domain::{AuditEntry, Submission, SubmissionStatus};
+use uuid::Uuid;
+
+async fn submit_concert(app: &TestApp, artist_id: Uuid) -> reqwest::Response {
+    app.api_client
+        .post(format!("{}/submissions/concerts", &app.address))
+        .header("X-Client-Id", "fan")
+        .json(&serde_json::json!({
+            "artist_id": artist_id,
+            "venue": "The Fillmore",
+            "city": "San Francisco",
+            "state": "CA",
+            "country": "USA",
+            "date": "2021-07-17",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute the request")
+}
+
+async fn submit_artist_update(app: &TestApp, artist_id: Uuid, name: &str) -> Submission {
+    app.api_client
+        .post(format!("{}/submissions/artists/{}", &app.address, artist_id))
+        .header("X-Client-Id", "fan")
+        .json(&serde_json::json!({
+            "id": artist_id,
+            "name": name,
+            "sort_name": "Strings, Billy",
+            "disambiguation": "Bluegrass musician from Lansing, MI",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute the request")
+        .json::<Submission>()
+        .await
+        .expect("Failed to deserialize the submission")
+}
+
+#[tokio::test]
+async fn a_submitted_concert_waits_for_a_moderator() {
+    // Arrange
+    let app = spawn_app().await;
+    let (artist, _) = app.create_artist().await;
+
+    // Act
+    let response = submit_concert(&app, artist.id).await;
+
+    // Assert
+    assert_eq!(202, response.status().as_u16());
+    let submission = response.json::<Submission>().await.expect("Failed to deserialize the submission");
+    assert_eq!(SubmissionStatus::Pending, submission.status);
+    assert_eq!("fan", submission.submitted_by);
+    assert_eq!(0, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn an_invalid_submission_returns_400() {
+    // Arrange
+    let app = spawn_app().await;
+    let (artist, _) = app.create_artist().await;
+
+    // Act
+    let response = app.api_client
+        .post(format!("{}/submissions/artists/{}", &app.address, artist.id))
+        .json(&serde_json::json!({
+            "id": artist.id,
+            "name": "",
+            "sort_name": "Strings, Billy",
+            "disambiguation": "",
+        }))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_moderation_queue_requires_a_login() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_moderation_queue().await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    let html = app.get_login_html().await;
+    assert!(html.contains("Please log in to moderate submissions."));
+}
+
+#[tokio::test]
+async fn a_wrong_password_redirects_back_to_the_login_form() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_login(&serde_json::json!({
+            "username": &app.test_moderator.username,
+            "password": "not the password",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    let html = app.get_login_html().await;
+    assert!(html.contains("Authentication failed"));
+}
+
+#[tokio::test]
+async fn approving_a_concert_creates_it_with_attribution() {
+    // Arrange
+    let app = spawn_app().await;
+    let (artist, _) = app.create_artist().await;
+    let submission = submit_concert(&app, artist.id)
+        .await
+        .json::<Submission>()
+        .await
+        .expect("Failed to deserialize the submission");
+    app.test_moderator.login(&app).await;
+
+    // Act
+    let response = app.review_submission(submission.id, "approve").await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/moderation/submissions");
+    let html = app.get_moderation_queue_html().await;
+    assert!(html.contains("The submission was approved."));
+    assert!(html.contains("There are no pending submissions."));
+
+    let submission = app.api_client
+        .get(format!("{}/submissions/{}", &app.address, submission.id))
+        .send()
+        .await
+        .expect("Failed to execute the request")
+        .json::<Submission>()
+        .await
+        .expect("Failed to deserialize the submission");
+    assert_eq!(SubmissionStatus::Approved, submission.status);
+    let concert_id = submission.result_id.expect("The approved submission has no result");
+    let history = app
+        .get_history(&format!("/concerts/{}", concert_id))
+        .await
+        .json::<Vec<AuditEntry>>()
+        .await
+        .expect("Failed to deserialize the history");
+    assert_eq!(
+        format!("fan (approved by {})", app.test_moderator.username),
+        history[0].actor
+    );
+}
+
+#[tokio::test]
+async fn rejecting_an_artist_update_leaves_the_artist_unchanged() {
+    // Arrange
+    let app = spawn_app().await;
+    let (artist, _) = app.create_artist().await;
+    let submission = submit_artist_update(&app, artist.id, "Billy Stringz").await;
+    app.test_moderator.login(&app).await;
+
+    // Act
+    app.review_submission(submission.id, "reject").await;
+
+    // Assert
+    let html = app.get_moderation_queue_html().await;
+    assert!(html.contains("The submission was rejected."));
+    let stored = sqlx::query_scalar::<_, String>("SELECT name FROM artists WHERE id = $1")
+        .bind(artist.id)
+        .fetch_one(&app.db_pool)
+        .await
+        .expect("Failed to fetch the artist");
+    assert_eq!("Billy Strings", stored);
+}
+
+#[tokio::test]
+async fn approving_an_artist_update_based_on_a_stale_version_is_refused() {
+    // Arrange
+    let app = spawn_app().await;
+    let (artist, _) = app.create_artist().await;
+    let submission = submit_artist_update(&app, artist.id, "Billy Stringz").await;
+    app.patch_artist(artist.id, "*", serde_json::json!({"disambiguation": "Guitarist"}))
+        .await;
+    app.test_moderator.login(&app).await;
+
+    // Act
+    app.review_submission(submission.id, "approve").await;
+
+    // Assert
+    let html = app.get_moderation_queue_html().await;
+    assert!(html.contains("The artist changed since this was submitted"));
+    assert!(html.contains("Billy Stringz"));
+}
+
+#[tokio::test]
+async fn approving_a_concert_that_was_created_meanwhile_is_refused() {
+    // Arrange
+    let app = spawn_app().await;
+    let (artist, _) = app.create_artist().await;
+    let submission = submit_concert(&app, artist.id)
+        .await
+        .json::<Submission>()
+        .await
+        .expect("Failed to deserialize the submission");
+    app.post_concert(serde_json::json!({
+        "artist_id": artist.id,
+        "venue": "Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": "2021-07-17",
+    }))
+    .await;
+    app.test_moderator.login(&app).await;
+
+    // Act
+    app.review_submission(submission.id, "approve").await;
+
+    // Assert
+    let html = app.get_moderation_queue_html().await;
+    assert!(html.contains("This concert looks like a duplicate of concert"));
+    assert_eq!(1, app.concert_count().await);
+}
@@ -0,0 +1,210 @@
+use crate::helpers::spawn_app;
+use allbands::domain::{Artist, AuditEntry, Audited};
+use allbands::musicbrainz::{import_artists, ArtistDump};
+use uuid::Uuid;
+
+const BILLY_STRINGS: &str = "0a3e5a3c-2f7f-4c15-9a4b-0a6a1a6b3f5e";
+
+#[tokio::test]
+async fn importing_a_dump_creates_artists_with_their_mbid() {
+    // Arrange
+    let app = spawn_app().await;
+    let dump = ArtistDump::new("tests/fixtures/musicbrainz/artist");
+
+    // Act
+    let summary = import_artists(&dump, &app.db_pool)
+        .await
+        .expect("Failed to import the dump");
+
+    // Assert
+    assert_eq!(2, summary.created);
+    assert_eq!(0, summary.updated);
+    assert_eq!(1, summary.skipped);
+
+    let artist = Artist::find_by_mbid(Uuid::parse_str(BILLY_STRINGS).unwrap(), &app.db_pool)
+        .await
+        .unwrap()
+        .expect("The artist was not imported");
+    assert_eq!("Billy Strings", artist.name);
+    assert_eq!("Strings, Billy", artist.sort_name);
+    assert_eq!("US bluegrass guitarist", artist.disambiguation);
+}
+
+#[tokio::test]
+async fn reimporting_a_dump_updates_existing_artists() {
+    // Arrange
+    let app = spawn_app().await;
+    import_artists(&ArtistDump::new("tests/fixtures/musicbrainz/artist"), &app.db_pool)
+        .await
+        .expect("Failed to import the dump");
+
+    // Act
+    let summary = import_artists(
+        &ArtistDump::new("tests/fixtures/musicbrainz/artist-updated"),
+        &app.db_pool,
+    )
+    .await
+    .expect("Failed to import the dump");
+
+    // Assert
+    assert_eq!(0, summary.created);
+    assert_eq!(1, summary.updated);
+
+    let artist = Artist::find_by_mbid(Uuid::parse_str(BILLY_STRINGS).unwrap(), &app.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!("Grammy-winning bluegrass guitarist", artist.disambiguation);
+}
+
+#[tokio::test]
+async fn reimporting_an_unchanged_artist_leaves_its_version_and_history_alone() {
+    // Arrange
+    let app = spawn_app().await;
+    let dump = ArtistDump::new("tests/fixtures/musicbrainz/artist");
+    import_artists(&dump, &app.db_pool)
+        .await
+        .expect("Failed to import the dump");
+
+    // Act
+    let summary = import_artists(&dump, &app.db_pool)
+        .await
+        .expect("Failed to import the dump");
+
+    // Assert
+    assert_eq!(0, summary.created);
+    assert_eq!(0, summary.updated);
+    assert_eq!(2, summary.unchanged);
+
+    let artist = Artist::find_by_mbid(Uuid::parse_str(BILLY_STRINGS).unwrap(), &app.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(1, artist.version);
+    let history = AuditEntry::find_by_entity(Artist::ENTITY_TYPE, artist.id, &app.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(1, history.len());
+}
+
+#[tokio::test]
+async fn artists_clashing_with_a_stored_one_are_skipped() {
+    // Arrange
+    let app = spawn_app().await;
+    app.create_artist().await;
+
+    // Act
+    let summary = import_artists(&ArtistDump::new("tests/fixtures/musicbrainz/artist"), &app.db_pool)
+        .await
+        .expect("Failed to import the dump");
+
+    // Assert
+    assert_eq!(1, summary.created);
+    assert_eq!(2, summary.skipped);
+    assert_eq!(2, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn an_unexpected_database_error_stops_the_import() {
+    // Arrange
+    let app = spawn_app().await;
+    sqlx::query("ALTER TABLE artists ADD CONSTRAINT no_mbids CHECK (mbid IS NULL)")
+        .execute(&app.db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let result = import_artists(&ArtistDump::new("tests/fixtures/musicbrainz/artist"), &app.db_pool).await;
+
+    // Assert
+    assert!(result.is_err());
+    assert_eq!(0, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn create_artist_prefills_from_the_mbid() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_artist(serde_json::json!({
+        "name": "Billy Strings",
+        "mbid": BILLY_STRINGS,
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let artist = response.json::<Artist>().await.unwrap();
+    assert_eq!("Strings, Billy", artist.sort_name);
+    assert_eq!("US bluegrass guitarist", artist.disambiguation);
+    assert_eq!(Some(Uuid::parse_str(BILLY_STRINGS).unwrap()), artist.mbid);
+}
+
+#[tokio::test]
+async fn create_artist_keeps_supplied_fields_over_the_mbid() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_artist(serde_json::json!({
+        "name": "Billy Strings",
+        "sort_name": "Billy Strings",
+        "mbid": BILLY_STRINGS,
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let artist = response.json::<Artist>().await.unwrap();
+    assert_eq!("Billy Strings", artist.sort_name);
+    assert_eq!("US bluegrass guitarist", artist.disambiguation);
+}
+
+#[tokio::test]
+async fn create_artist_rejects_an_unknown_mbid() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.post_artist(serde_json::json!({
+        "name": "Billy Strings",
+        "mbid": Uuid::new_v4(),
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn creating_an_artist_with_an_mbid_prefills_it_from_the_dump() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_artist(serde_json::json!({ "name": "Billy Strings", "mbid": BILLY_STRINGS }))
+        .await;
+
+    // Assert
+    assert_eq!(201, response.status().as_u16());
+    let artist = response.json::<Artist>().await.unwrap();
+    assert_eq!("Strings, Billy", artist.sort_name);
+    assert_eq!("US bluegrass guitarist", artist.disambiguation);
+}
+
+#[tokio::test]
+async fn creating_an_artist_with_an_mbid_missing_from_the_dump_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_artist(serde_json::json!({ "name": "Billy Strings", "mbid": Uuid::new_v4() }))
+        .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    assert_eq!(0, app.artist_count().await);
+}
@@ -0,0 +1,153 @@
+use crate::helpers::{is_documented, spawn_app, spawn_app_with, test_configuration};
+use allbands::configuration::{get_configuration, RateLimitSettings};
+use allbands::openapi::{api_doc, ApiDoc};
+use allbands::startup::routes;
+use std::collections::BTreeSet;
+use utoipa::OpenApi;
+
+fn documented_operations(spec: &serde_json::Value) -> BTreeSet<(String, String)> {
+    spec["paths"]
+        .as_object()
+        .expect("The spec has no paths")
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .expect("A path item is not an object")
+                .keys()
+                .map(move |method| (method.to_uppercase(), path.clone()))
+        })
+        .collect()
+}
+
+#[test]
+fn every_route_is_documented_and_every_documented_route_exists() {
+    // Arrange
+    let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+    // Act
+    let documented = documented_operations(&spec);
+    let served = routes()
+        .into_iter()
+        .map(|(method, path, _, _)| (method.to_string(), path.to_string()))
+        .collect::<BTreeSet<_>>();
+
+    // Assert
+    let undocumented = served.difference(&documented).collect::<Vec<_>>();
+    let missing = documented.difference(&served).collect::<Vec<_>>();
+    assert!(
+        undocumented.is_empty(),
+        "These routes are missing from the OpenAPI document: {:?}",
+        undocumented
+    );
+    assert!(
+        missing.is_empty(),
+        "These documented routes are not served: {:?}",
+        missing
+    );
+}
+
+#[tokio::test]
+async fn openapi_json_serves_the_generated_document() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.api_client
+        .get(format!("{}/openapi.json", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let spec = response.json::<serde_json::Value>().await.unwrap();
+    let rate_limit = get_configuration().unwrap().rate_limit;
+    assert_eq!(serde_json::to_value(api_doc(&rate_limit)).unwrap(), spec);
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(spec["components"]["schemas"]["CreateConcertRequest"].is_object());
+    assert!(spec["paths"]["/concerts"]["post"]["responses"]["409"].is_object());
+}
+
+#[tokio::test]
+async fn docs_serves_an_html_page_pointing_at_the_document() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.api_client
+        .get(format!("{}/docs", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let html = response.text().await.unwrap();
+    assert!(html.contains(r#"url: "/openapi.json""#));
+    assert!(html.contains(r#"src="/docs/swagger-ui-bundle.js""#));
+    assert!(html.contains(r#"href="/docs/swagger-ui.css""#));
+    assert!(!html.contains("https://"));
+}
+
+#[tokio::test]
+async fn docs_assets_are_served_by_the_application() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for (path, content_type) in [
+        ("/docs/swagger-ui-bundle.js", "text/javascript"),
+        ("/docs/swagger-ui.css", "text/css"),
+    ] {
+        // Act
+        let response = app.api_client
+            .get(format!("{}{}", &app.address, path))
+            .send()
+            .await
+            .expect("Failed to execute the request");
+
+        // Assert
+        assert_eq!(200, response.status().as_u16(), "{}", path);
+        assert!(response.headers()["Content-Type"].to_str().unwrap().starts_with(content_type));
+        assert!(!response.text().await.unwrap().is_empty());
+    }
+}
+
+#[test]
+fn only_the_documented_statuses_of_a_route_pass() {
+    assert!(is_documented("GET", "/health", 200));
+    assert!(is_documented("GET", "/health", 502));
+    assert!(!is_documented("GET", "/health", 418));
+    assert!(!is_documented("GET", "/not-a-route", 200));
+}
+
+#[tokio::test]
+async fn rate_limits_are_documented_from_the_loaded_settings() {
+    // Arrange
+    let mut configuration = test_configuration().await;
+    configuration.rate_limit.exempt_routes.push("/concerts".into());
+    let app = spawn_app_with(configuration).await;
+
+    // Act
+    let spec = app.api_client
+        .get(format!("{}/openapi.json", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute the request")
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(spec["paths"]["/concerts"]["get"]["responses"]["429"].is_null());
+    assert!(spec["paths"]["/health"]["get"]["responses"]["429"].is_null());
+    assert!(spec["paths"]["/artists"]["post"]["responses"]["429"].is_object());
+}
+
+#[test]
+fn nothing_is_documented_as_rate_limited_when_rate_limiting_is_off() {
+    let settings = RateLimitSettings { enabled: false, ..RateLimitSettings::default() };
+
+    let spec = serde_json::to_value(api_doc(&settings)).unwrap();
+
+    assert!(spec["paths"]["/artists"]["post"]["responses"]["429"].is_null());
+}
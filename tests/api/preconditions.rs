@@ -0,0 +1,101 @@
+use crate::helpers::{etag, spawn_app};
+use allbands::domain::Concert;
+use uuid::Uuid;
+
+fn concert(artist_id: Uuid, date: &str) -> serde_json::Value {
+    serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "The Fillmore",
+        "city": "San Francisco",
+        "state": "CA",
+        "country": "USA",
+        "date": date,
+    })
+}
+
+fn update(concert_id: Uuid, artist_id: Uuid, date: &str) -> serde_json::Value {
+    let mut body = concert(artist_id, date);
+    body["id"] = serde_json::json!(concert_id);
+    body
+}
+
+#[tokio::test]
+async fn update_concert_returns_428_without_if_match() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let concert_id = app.create_concert(artist_id, "2021-07-17").await.0.id;
+
+    // Act
+    let response = app.api_client
+        .put(format!("{}/concerts/{}", &app.address, concert_id))
+        .json(&update(concert_id, artist_id, "2021-07-18"))
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(428, response.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_second_of_two_updates_from_the_same_version_returns_412() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let (Concert { id: concert_id, .. }, etag) = app.create_concert(artist_id, "2021-07-17").await;
+
+    // Act
+    let first = app.update_concert(concert_id, &etag, update(concert_id, artist_id, "2021-07-18")).await;
+    let second = app.update_concert(concert_id, &etag, update(concert_id, artist_id, "2021-07-19")).await;
+
+    // Assert
+    assert_eq!(200, first.status().as_u16());
+    assert_ne!(etag, crate::helpers::etag(&first));
+    assert_eq!(412, second.status().as_u16());
+    let stored = app.get_concert_by_id(concert_id)
+        .await
+        .json::<Concert>()
+        .await
+        .unwrap();
+    assert_eq!("2021-07-18", stored.date.to_string());
+}
+
+#[tokio::test]
+async fn get_concert_returns_304_when_the_client_copy_is_current() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let (Concert { id: concert_id, .. }, etag) = app.create_concert(artist_id, "2021-07-17").await;
+
+    // Act
+    let current = app.get_concert_if_none_match(concert_id, &etag).await;
+    app.update_concert(concert_id, &etag, update(concert_id, artist_id, "2021-07-18")).await;
+    let stale = app.get_concert_if_none_match(concert_id, &etag).await;
+
+    // Assert
+    assert_eq!(304, current.status().as_u16());
+    assert_eq!(200, stale.status().as_u16());
+}
+
+#[tokio::test]
+async fn the_artist_dashboard_tag_changes_when_a_concert_is_added() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.create_artist().await.0.id;
+    let before = etag(&app.get_artist_by_id(artist_id).await);
+
+    // Act
+    let unchanged = app.api_client
+        .get(format!("{}/artists/{}", &app.address, artist_id))
+        .header("If-None-Match", &before)
+        .send()
+        .await
+        .expect("Failed to execute the request");
+    app.create_concert(artist_id, "2021-07-17").await;
+    let after = etag(&app.get_artist_by_id(artist_id).await);
+
+    // Assert
+    assert_eq!(304, unchanged.status().as_u16());
+    assert_ne!(before, after);
+}
@@ -0,0 +1,85 @@
+use crate::helpers::spawn_app;
+use allbands::domain::Artist;
+use allbands::routes::{ProblemCode, ProblemDetails};
+
+async fn problem(response: reqwest::Response) -> ProblemDetails {
+    assert_eq!(
+        Some("application/problem+json"),
+        response.headers().get("Content-Type").and_then(|v| v.to_str().ok()),
+    );
+    response.json::<ProblemDetails>()
+        .await
+        .expect("Failed to deserialize the problem details")
+}
+
+#[tokio::test]
+async fn every_invalid_field_is_reported() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist_id = app.post_artist(serde_json::json!({
+        "name": "Billy Strings",
+        "sort_name": "Strings, Billy",
+        "disambiguation": "Bluegrass musician from Lansing, MI",
+    }))
+    .await
+    .json::<Artist>()
+    .await
+    .expect("Failed to deserialize the artist")
+    .id;
+
+    // Act
+    let response = app.post_concert(serde_json::json!({
+        "artist_id": artist_id,
+        "venue": "",
+        "city": "",
+        "state": "CA",
+        "country": "USA",
+        "date": "not a date",
+    }))
+    .await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let problem = problem(response).await;
+    assert_eq!(ProblemCode::ValidationFailed, problem.code);
+    assert_eq!(400, problem.status);
+    assert!(problem.trace_id.is_some());
+    let fields = problem.errors.iter().map(|e| e.field.as_str()).collect::<Vec<_>>();
+    assert_eq!(vec!["venue", "city", "date"], fields);
+}
+
+#[tokio::test]
+async fn a_malformed_body_is_a_problem() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.api_client
+        .post(format!("{}/artists", &app.address))
+        .header("Content-Type", "application/json")
+        .body("{ not json")
+        .send()
+        .await
+        .expect("Failed to execute the request");
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+    let problem = problem(response).await;
+    assert_eq!(ProblemCode::InvalidPayload, problem.code);
+    assert!(problem.trace_id.is_some());
+}
+
+#[tokio::test]
+async fn an_unknown_concert_is_a_not_found_problem() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.get_concert_by_id(uuid::Uuid::new_v4()).await;
+
+    // Assert
+    assert_eq!(404, response.status().as_u16());
+    let problem = problem(response).await;
+    assert_eq!(ProblemCode::NotFound, problem.code);
+    assert_eq!("Not Found", problem.title);
+}
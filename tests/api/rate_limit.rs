@@ -0,0 +1,200 @@
+use crate::helpers::{spawn_app, spawn_app_with, test_configuration, TestApp};
+use actix_web::{test, web, App, HttpResponse};
+use allbands::configuration::{RateLimitSettings, RouteLimit};
+use allbands::rate_limit::{RateLimit, RateLimiter};
+use allbands::routes::{ProblemCode, ProblemDetails};
+use std::sync::Arc;
+
+fn header<'a>(response: &'a reqwest::Response, name: &str) -> Option<&'a str> {
+    response.headers().get(name).map(|value| value.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn responses_tell_the_client_how_many_requests_it_has_left() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/concerts", &app.address))
+        .send()
+        .await
+        .unwrap();
+    let probe = app
+        .api_client
+        .get(format!("{}/health", &app.address))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert_eq!(Some("300"), header(&response, "RateLimit-Limit"));
+    assert_eq!(Some("299"), header(&response, "RateLimit-Remaining"));
+    let reset: u64 = header(&response, "RateLimit-Reset").unwrap().parse().unwrap();
+    assert!(reset > 0 && reset <= 60);
+    assert_eq!(None, header(&probe, "RateLimit-Limit"));
+}
+
+#[tokio::test]
+async fn a_client_over_the_limit_of_a_route_is_told_to_retry_later() {
+    // Arrange
+    let mut configuration = test_configuration().await;
+    configuration.rate_limit.routes = vec![RouteLimit {
+        method: "POST".into(),
+        path: "/artists".into(),
+        limit: 2,
+    }];
+    let app = spawn_app_with(configuration).await;
+    for name in ["Goose", "Phish"] {
+        let response = app
+            .post_artist(serde_json::json!({
+                "name": name,
+                "sort_name": name,
+                "disambiguation": "",
+            }))
+            .await;
+        assert_eq!(201, response.status().as_u16());
+    }
+
+    // Act
+    let response = app
+        .post_artist(serde_json::json!({
+            "name": "Widespread Panic",
+            "sort_name": "Widespread Panic",
+            "disambiguation": "",
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(429, response.status().as_u16());
+    assert_eq!(Some("0"), header(&response, "RateLimit-Remaining"));
+    let retry_after: u64 = header(&response, "Retry-After").unwrap().parse().unwrap();
+    assert!(retry_after > 0);
+    let problem = response.json::<ProblemDetails>().await.unwrap();
+    assert_eq!(ProblemCode::RateLimited, problem.code);
+    assert_eq!(2, app.artist_count().await);
+
+    // Other routes are counted apart.
+    let response = app
+        .api_client
+        .get(format!("{}/concerts", &app.address))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, response.status().as_u16());
+}
+
+/// Post an artist as sent through a proxy for the client at `address`.
+async fn post_artist_forwarded_for(app: &TestApp, name: &str, address: &str) -> u16 {
+    app.api_client
+        .post(format!("{}/artists", &app.address))
+        .header("X-Forwarded-For", address)
+        .json(&serde_json::json!({ "name": name, "sort_name": name, "disambiguation": "" }))
+        .send()
+        .await
+        .expect("Failed to execute the request")
+        .status()
+        .as_u16()
+}
+
+async fn spawn_app_limiting_artists_to_one(trust_proxy_headers: bool) -> TestApp {
+    let mut configuration = test_configuration().await;
+    configuration.application.trust_proxy_headers = trust_proxy_headers;
+    configuration.rate_limit.routes = vec![RouteLimit {
+        method: "POST".into(),
+        path: "/artists".into(),
+        limit: 1,
+    }];
+    spawn_app_with(configuration).await
+}
+
+#[tokio::test]
+async fn behind_a_trusted_proxy_clients_are_told_apart_by_their_forwarded_address() {
+    // Arrange
+    let app = spawn_app_limiting_artists_to_one(true).await;
+    assert_eq!(201, post_artist_forwarded_for(&app, "Goose", "203.0.113.1").await);
+
+    // Act
+    let same_client = post_artist_forwarded_for(&app, "Phish", "203.0.113.1").await;
+    let other_client = post_artist_forwarded_for(&app, "Phish", "203.0.113.2").await;
+
+    // Assert
+    assert_eq!(429, same_client);
+    assert_eq!(201, other_client);
+}
+
+#[tokio::test]
+async fn forwarded_addresses_are_ignored_unless_proxy_headers_are_trusted() {
+    // Arrange
+    let app = spawn_app_limiting_artists_to_one(false).await;
+    assert_eq!(201, post_artist_forwarded_for(&app, "Goose", "203.0.113.1").await);
+
+    // Act
+    let status = post_artist_forwarded_for(&app, "Phish", "203.0.113.2").await;
+
+    // Assert
+    assert_eq!(429, status);
+}
+
+#[tokio::test]
+async fn requests_are_let_through_when_redis_does_not_answer_in_time() {
+    // Arrange
+    // A server that accepts connections and never answers.
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let redis_address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut connections = vec![];
+        while let Ok((connection, _)) = listener.accept().await {
+            connections.push(connection);
+        }
+    });
+    let client = redis::Client::open(format!("redis://{}", redis_address)).unwrap();
+    let settings = RateLimitSettings {
+        timeout_milliseconds: 50,
+        ..RateLimitSettings::default()
+    };
+    let limiter = RateLimiter::new(settings, client)
+        .await
+        .expect("Failed to connect to the silent server");
+    let service = test::init_service(
+        App::new()
+            .wrap(RateLimit::new(Arc::new(limiter)))
+            .route("/", web::get().to(HttpResponse::Ok)),
+    )
+    .await;
+
+    // Act
+    let response = test::call_service(&service, test::TestRequest::get().uri("/").to_request()).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    assert!(response.headers().get("RateLimit-Remaining").is_none());
+}
+
+#[tokio::test]
+async fn graphql_creates_count_against_the_limit_of_the_create_route() {
+    // Arrange
+    let app = spawn_app_limiting_artists_to_one(false).await;
+    app.create_artist().await;
+
+    // Act
+    let response = app
+        .post_graphql(
+            r#"
+                mutation CreateArtist($input: CreateArtistInput!) {
+                    createArtist(input: $input) { id }
+                }
+            "#,
+            serde_json::json!({
+                "input": { "name": "Goose", "sortName": "Goose", "disambiguation": "" }
+            }),
+        )
+        .await;
+
+    // Assert
+    assert_eq!("RATE_LIMITED", response["errors"][0]["extensions"]["code"], "{}", response);
+    assert!(response["errors"][0]["extensions"]["retryAfter"].as_u64().unwrap() > 0);
+    assert_eq!(1, app.artist_count().await);
+}
@@ -0,0 +1,138 @@
+use crate::helpers::spawn_app;
+use allbands::domain::{Artist, Concert, Song};
+use allbands::routes::{SetlistImportReport, SetlistStatus};
+
+fn fixture(name: &str) -> String {
+    std::fs::read_to_string(format!("tests/fixtures/setlistfm/{}", name))
+        .expect("Failed to read the fixture")
+}
+
+#[tokio::test]
+async fn importing_a_setlist_creates_the_artist_concert_and_songs() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.import_setlistfm(&fixture("setlist.json")).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let report = response.json::<SetlistImportReport>().await.unwrap();
+    assert_eq!(1, report.imported);
+    let setlist = &report.setlists[0];
+    assert_eq!(Some("63de4613".to_string()), setlist.setlist_id);
+    assert_eq!(4, setlist.songs);
+
+    let artist = Artist::find_by_id(setlist.artist_id.unwrap(), &app.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!("Billy Strings", artist.name);
+    assert_eq!("Strings, Billy", artist.sort_name);
+    assert!(artist.mbid.is_some());
+
+    let concert = Concert::find_by_id(setlist.concert_id.unwrap(), &app.db_pool)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!("The Fillmore", concert.venue);
+    assert_eq!(Some("CA".to_string()), concert.state);
+    assert_eq!("United States", concert.country);
+    assert_eq!(chrono::NaiveDate::from_ymd_opt(2021, 7, 17).unwrap(), concert.date);
+
+    let songs = Song::find_by_concert(concert.id, &app.db_pool).await.unwrap();
+    let titles = songs.iter().map(|s| s.title.as_str()).collect::<Vec<_>>();
+    assert_eq!(
+        vec!["Love Like Me", "Dust in a Baggie", "Meet Me at the Creek", "Away From the Mire"],
+        titles
+    );
+    assert_eq!(Some(1), songs[3].encore);
+    assert_eq!(2, songs[3].set_number);
+}
+
+#[tokio::test]
+async fn importing_a_search_page_reuses_the_artist() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.import_setlistfm(&fixture("search.json")).await;
+
+    // Assert
+    assert_eq!(200, response.status().as_u16());
+    let report = response.json::<SetlistImportReport>().await.unwrap();
+    assert_eq!(2, report.imported);
+    assert_eq!(report.setlists[0].artist_id, report.setlists[1].artist_id);
+    assert_eq!(2, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn importing_the_same_setlist_twice_is_deduplicated() {
+    // Arrange
+    let app = spawn_app().await;
+    let first = app
+        .import_setlistfm(&fixture("setlist.json"))
+        .await
+        .json::<SetlistImportReport>()
+        .await
+        .unwrap();
+
+    // Act
+    let response = app.import_setlistfm(&fixture("setlist.json")).await;
+
+    // Assert
+    let report = response.json::<SetlistImportReport>().await.unwrap();
+    assert_eq!(1, report.duplicates);
+    assert_eq!(SetlistStatus::Duplicate, report.setlists[0].status);
+    assert_eq!(first.setlists[0].concert_id, report.setlists[0].concert_id);
+    assert_eq!(1, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn an_invalid_setlist_leaves_nothing_behind() {
+    // Arrange
+    let app = spawn_app().await;
+    let mut setlist: serde_json::Value = serde_json::from_str(&fixture("setlist.json")).unwrap();
+    setlist["eventDate"] = "2021-07-17".into();
+
+    // Act
+    let response = app.import_setlistfm(&setlist.to_string()).await;
+
+    // Assert
+    let report = response.json::<SetlistImportReport>().await.unwrap();
+    assert_eq!(1, report.invalid);
+    assert!(report.setlists[0].error.is_some());
+    assert_eq!(0, app.concert_count().await);
+}
+
+#[tokio::test]
+async fn a_setlist_with_an_invalid_song_rolls_back_its_artist_and_concert() {
+    // Arrange
+    let app = spawn_app().await;
+    let mut setlist: serde_json::Value = serde_json::from_str(&fixture("setlist.json")).unwrap();
+    // The artist and the concert are inserted before the songs are parsed
+    setlist["sets"]["set"][1]["song"][0]["name"] = " ".into();
+
+    // Act
+    let response = app.import_setlistfm(&setlist.to_string()).await;
+
+    // Assert
+    let report = response.json::<SetlistImportReport>().await.unwrap();
+    assert_eq!(1, report.invalid);
+    assert!(report.setlists[0].error.is_some());
+    assert_eq!(None, report.setlists[0].artist_id);
+    assert_eq!(0, app.concert_count().await);
+    assert_eq!(0, app.artist_count().await);
+}
+
+#[tokio::test]
+async fn a_malformed_payload_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app.import_setlistfm(r#"{"setlists": []}"#).await;
+
+    // Assert
+    assert_eq!(400, response.status().as_u16());
+}
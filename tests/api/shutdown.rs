@@ -0,0 +1,66 @@
+use crate::helpers::test_configuration;
+use allbands::startup::{get_connection_pool, Application};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+
+#[tokio::test]
+async fn shutdown_drains_in_flight_requests_and_refuses_new_connections() {
+    // Arrange
+    let configuration = test_configuration().await;
+    let application = Application::build(configuration.clone())
+        .await
+        .expect("Failed to build the application");
+    let port = application.port();
+    let (shutdown, shutdown_requested) = oneshot::channel::<()>();
+    let server = tokio::spawn(application.run_until(async {
+        let _ = shutdown_requested.await;
+    }));
+
+    let body = serde_json::json!({
+        "name": "Goose",
+        "sort_name": "Goose",
+        "disambiguation": "",
+    })
+    .to_string();
+    let mut connection = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    // Hold the body back until the server answers `100 Continue`, so that
+    // the request is in flight when the shutdown is requested.
+    connection
+        .write_all(
+            format!(
+                "POST /artists HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+                 Content-Length: {}\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await
+        .unwrap();
+    let mut interim = [0; 64];
+    let read = connection.read(&mut interim).await.unwrap();
+    assert!(interim[..read].starts_with(b"HTTP/1.1 100 Continue"));
+
+    // Act
+    shutdown.send(()).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let new_connection = TcpStream::connect(("127.0.0.1", port)).await;
+    connection.write_all(body.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    connection.read_to_string(&mut response).await.unwrap();
+
+    // Assert
+    assert!(new_connection.is_err());
+    assert!(response.starts_with("HTTP/1.1 201"), "{}", response);
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("The server did not stop")
+        .unwrap()
+        .unwrap();
+    let artists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM artists")
+        .fetch_one(&get_connection_pool(&configuration.database))
+        .await
+        .unwrap();
+    assert_eq!(1, artists);
+}
@@ -0,0 +1,214 @@
+use crate::helpers::spawn_app;
+use actix_web::{test, web, App};
+use allbands::cache::ResponseCache;
+use allbands::configuration::{BodyLoggingSettings, CacheSettings, TelemetrySettings};
+use allbands::domain::Artist;
+use allbands::routes::artist_dashboard;
+use allbands::telemetry::{get_subscriber, get_tracer, BodyLogging};
+use opentelemetry::trace::TraceContextExt;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing_actix_web::TracingLogger;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Whether `needle` appears anywhere in `haystack`, as the strings and ids
+/// of an OTLP protobuf payload are stored verbatim.
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+/// A log sink the test can read back.
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+async fn echo(body: String) -> String {
+    body
+}
+
+#[tokio::test]
+async fn bodies_of_opted_in_routes_are_logged_with_credentials_redacted() {
+    // Arrange
+    let logs = CapturedLogs::default();
+    let sink = logs.clone();
+    let subscriber = get_subscriber("test".into(), "info".into(), move || sink.clone(), None);
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let settings = BodyLoggingSettings {
+        enabled: true,
+        routes: vec!["/logged/{id}".into()],
+        ..BodyLoggingSettings::default()
+    };
+    let service = test::init_service(
+        App::new()
+            .wrap(BodyLogging::new(settings))
+            .wrap(TracingLogger::default())
+            .route("/logged/{id}", web::post().to(echo))
+            .route("/quiet", web::post().to(echo)),
+    )
+    .await;
+    let body = r#"{"username":"goose","password":"hunter2"}"#;
+
+    // Act
+    for uri in ["/logged/1", "/quiet"] {
+        let request = test::TestRequest::post().uri(uri).set_payload(body).to_request();
+        let response = test::call_service(&service, request).await;
+        assert_eq!(body.as_bytes(), test::read_body(response).await);
+    }
+
+    // Assert
+    let logs = logs.contents();
+    assert!(!logs.contains("hunter2"));
+    let logged_bodies = logs
+        .lines()
+        .filter(|line| line.contains("http.request.body") || line.contains("http.response.body"))
+        .collect::<Vec<_>>();
+    assert_eq!(2, logged_bodies.len());
+    assert!(logged_bodies.iter().all(|line| line.contains("goose") && line.contains("[REDACTED]")));
+    assert!(logged_bodies.iter().all(|line| line.contains("/logged/1")));
+}
+
+#[tokio::test]
+async fn long_request_bodies_are_logged_cut_and_passed_on_whole() {
+    // Arrange
+    let logs = CapturedLogs::default();
+    let sink = logs.clone();
+    let subscriber = get_subscriber("test".into(), "info".into(), move || sink.clone(), None);
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let settings = BodyLoggingSettings {
+        enabled: true,
+        max_bytes: 64,
+        routes: vec!["/logged".into()],
+    };
+    let service = test::init_service(
+        App::new()
+            .wrap(BodyLogging::new(settings))
+            .wrap(TracingLogger::default())
+            .route("/logged", web::post().to(|body: String| async move { body.len().to_string() })),
+    )
+    .await;
+    let body = format!("password=hunter2&notes={}", "a".repeat(10_000));
+
+    // Act
+    let request = test::TestRequest::post().uri("/logged").set_payload(body.clone()).to_request();
+    let response = test::call_service(&service, request).await;
+
+    // Assert
+    assert_eq!(body.len().to_string().as_bytes(), test::read_body(response).await);
+    let logs = logs.contents();
+    assert!(!logs.contains("hunter2"));
+    let request_body = logs.lines().find(|line| line.contains("http.request.body")).unwrap();
+    assert!(request_body.contains("(over 64 bytes)"));
+    assert!(request_body.len() < 2_000);
+}
+
+// The batch exporter runs on a Tokio worker, which `shutdown_tracer_provider`
+// blocks on while flushing.
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_continue_the_incoming_trace_and_are_exported() {
+    // Arrange
+    let app = spawn_app().await;
+    let artist = app
+        .post_artist(serde_json::json!({
+            "name": "Goose",
+            "sort_name": "Goose",
+            "disambiguation": "",
+        }))
+        .await
+        .json::<Artist>()
+        .await
+        .unwrap();
+
+    let collector = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/traces"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&collector)
+        .await;
+    let settings = TelemetrySettings {
+        otlp_endpoint: Some(format!("{}/v1/traces", collector.uri())),
+        ..TelemetrySettings::default()
+    };
+    let tracer = get_tracer(&settings).unwrap().unwrap();
+    let subscriber = get_subscriber("test".into(), "info".into(), std::io::sink, Some(tracer));
+    // The server spawned by `spawn_app` logs through the global subscriber on
+    // threads of its own, so drive the handler in-process instead.
+    let _guard = tracing::subscriber::set_default(subscriber);
+    // Disabled, so that the handler queries Postgres.
+    let cache = ResponseCache::new(
+        CacheSettings { enabled: false, ..CacheSettings::default() },
+        redis::Client::open("redis://127.0.0.1:6379").unwrap(),
+    )
+    .await
+    .unwrap();
+    let service = test::init_service(
+        App::new()
+            .wrap(TracingLogger::default())
+            .app_data(web::Data::new(app.db_pool.clone()))
+            .app_data(web::Data::new(cache))
+            .route("/artists/{id}", web::get().to(artist_dashboard)),
+    )
+    .await;
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+    let parent_span_id = "00f067aa0ba902b7";
+
+    // Act
+    let request = test::TestRequest::get()
+        .uri(&format!("/artists/{}", artist.id))
+        .insert_header(("traceparent", format!("00-{}-{}-01", trace_id, parent_span_id)))
+        .to_request();
+    let response = test::call_service(&service, request).await;
+    let status = response.status();
+    // The request's span ends once its body has been sent.
+    test::read_body(response).await;
+    opentelemetry::global::shutdown_tracer_provider();
+
+    // Assert
+    assert_eq!(200, status.as_u16());
+    let exported = collector
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .flat_map(|request| request.body)
+        .collect::<Vec<_>>();
+    assert!(contains(&exported, &hex::decode(trace_id).unwrap()));
+    assert!(contains(&exported, &hex::decode(parent_span_id).unwrap()));
+    assert!(contains(&exported, b"Find artist by id"));
+    assert!(contains(&exported, b"allbands"));
+}
+
+#[tokio::test]
+async fn incoming_traces_are_continued_without_an_exporter() {
+    // Arrange
+    let settings = TelemetrySettings { otlp_endpoint: None, ..TelemetrySettings::default() };
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+    let headers = HashMap::from([(
+        "traceparent".to_string(),
+        format!("00-{}-00f067aa0ba902b7-01", trace_id),
+    )]);
+
+    // Act
+    let tracer = get_tracer(&settings).unwrap();
+    let context = opentelemetry::global::get_text_map_propagator(|propagator| propagator.extract(&headers));
+
+    // Assert
+    assert!(tracer.is_none());
+    assert_eq!(trace_id, format!("{:032x}", context.span().span_context().trace_id()));
+}